@@ -0,0 +1,28 @@
+ALTER TABLE products
+    ADD COLUMN coins_eligible BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE INDEX idx_products_enabled_coins_eligible
+    ON products(created_at DESC)
+    WHERE enabled = true AND coins_eligible = true;
+
+ALTER TABLE orders
+    ADD COLUMN coins_redeemed INTEGER NOT NULL DEFAULT 0 CHECK (coins_redeemed >= 0);
+
+CREATE TABLE coin_ledger (
+    id                  BIGSERIAL PRIMARY KEY,
+    user_id             INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    amount              INTEGER NOT NULL CHECK (amount <> 0),
+    reason              TEXT NOT NULL
+                        CHECK (reason IN ('earned', 'redeemed', 'returned', 'adjustment')),
+    order_id            INTEGER REFERENCES orders(id) ON DELETE SET NULL,
+    created_by_user_id  INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    comment             TEXT,
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_coin_ledger_user_created ON coin_ledger(user_id, created_at DESC);
+
+-- one earn/redeem/return entry per order keeps the order hooks idempotent
+CREATE UNIQUE INDEX idx_coin_ledger_order_reason
+    ON coin_ledger(order_id, reason)
+    WHERE order_id IS NOT NULL AND reason <> 'adjustment';
@@ -0,0 +1,13 @@
+CREATE TABLE stock_reservations (
+    id          SERIAL PRIMARY KEY,
+    order_id    INTEGER NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    product_id  TEXT NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    color       TEXT,
+    quantity    INTEGER NOT NULL CHECK (quantity > 0),
+    expires_at  TIMESTAMPTZ NOT NULL,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_stock_reservations_product_color ON stock_reservations(product_id, color);
+CREATE INDEX idx_stock_reservations_order_id ON stock_reservations(order_id);
+CREATE INDEX idx_stock_reservations_expires_at ON stock_reservations(expires_at);
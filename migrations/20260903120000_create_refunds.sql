@@ -0,0 +1,23 @@
+CREATE TABLE refunds (
+    id                  SERIAL PRIMARY KEY,
+    order_id            INTEGER NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    amount              INTEGER NOT NULL CHECK (amount > 0),
+    status              TEXT NOT NULL DEFAULT 'pending'
+                        CHECK (status IN ('pending', 'approved', 'processing', 'declined', 'failed')),
+    comment             TEXT,
+    created_by_user_id  INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_refunds_order_id ON refunds(order_id);
+
+CREATE TABLE refund_items (
+    id             SERIAL PRIMARY KEY,
+    refund_id      INTEGER NOT NULL REFERENCES refunds(id) ON DELETE CASCADE,
+    order_item_id  INTEGER NOT NULL REFERENCES order_items(id) ON DELETE CASCADE,
+    quantity       INTEGER NOT NULL CHECK (quantity > 0)
+);
+
+CREATE INDEX idx_refund_items_refund_id ON refund_items(refund_id);
+CREATE INDEX idx_refund_items_order_item_id ON refund_items(order_item_id);
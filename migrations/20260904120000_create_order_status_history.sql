@@ -0,0 +1,71 @@
+ALTER TABLE orders ALTER COLUMN status TYPE TEXT;
+
+CREATE TABLE order_status_history (
+    id                  BIGSERIAL PRIMARY KEY,
+    order_id            INTEGER NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    from_status         TEXT,
+    to_status           TEXT NOT NULL,
+    source              TEXT NOT NULL
+                        CHECK (source IN ('checkout', 'flitt', 'operator', 'system')),
+    changed_by_user_id  INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    comment             TEXT,
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_order_status_history_order_id ON order_status_history(order_id, created_at);
+
+-- statuses were free text; spelling variants of a known status are mapped to
+-- it, keeping the original value in the history
+CREATE TEMPORARY TABLE legacy_order_status_map (legacy TEXT PRIMARY KEY, status TEXT NOT NULL);
+
+INSERT INTO legacy_order_status_map (legacy, status)
+SELECT DISTINCT o.status, m.status
+FROM orders o
+CROSS JOIN LATERAL (
+    SELECT CASE lower(trim(o.status))
+               WHEN 'canceled' THEN 'cancelled'
+               WHEN 'partially refunded' THEN 'partially_refunded'
+               WHEN 'partially-refunded' THEN 'partially_refunded'
+               ELSE lower(trim(o.status))
+           END AS status
+) m
+WHERE o.status <> m.status
+  AND m.status IN (
+      'pending', 'created', 'processing', 'approved', 'shipped', 'delivered', 'declined',
+      'expired', 'reversed', 'cancelled', 'returned', 'refunded', 'partially_refunded'
+  );
+
+INSERT INTO order_status_history (order_id, from_status, to_status, source, comment)
+SELECT o.id, o.status, m.status, 'system', 'legacy status normalized'
+FROM orders o
+JOIN legacy_order_status_map m ON m.legacy = o.status;
+
+UPDATE orders o SET status = m.status
+FROM legacy_order_status_map m
+WHERE m.legacy = o.status;
+
+DROP TABLE legacy_order_status_map;
+
+-- anything else can't be mapped safely; those orders have to be fixed by hand
+-- before this migration can run
+DO $$
+DECLARE
+    unknown TEXT;
+BEGIN
+    SELECT string_agg(DISTINCT quote_literal(status), ', ') INTO unknown
+    FROM orders
+    WHERE status NOT IN (
+        'pending', 'created', 'processing', 'approved', 'shipped', 'delivered', 'declined',
+        'expired', 'reversed', 'cancelled', 'returned', 'refunded', 'partially_refunded'
+    );
+
+    IF unknown IS NOT NULL THEN
+        RAISE EXCEPTION 'orders have unknown statuses: %', unknown;
+    END IF;
+END;
+$$;
+
+ALTER TABLE orders ADD CONSTRAINT orders_status_check CHECK (status IN (
+    'pending', 'created', 'processing', 'approved', 'shipped', 'delivered', 'declined',
+    'expired', 'reversed', 'cancelled', 'returned', 'refunded', 'partially_refunded'
+));
@@ -0,0 +1,8 @@
+ALTER TABLE orders
+    ADD COLUMN stock_deducted_at TIMESTAMPTZ,
+    ADD COLUMN stock_restored_at TIMESTAMPTZ;
+
+-- web orders had their stock taken when payment was approved; admin orders never did
+UPDATE orders SET stock_deducted_at = updated_at
+WHERE source = 'web'
+  AND status IN ('approved', 'shipped', 'delivered', 'partially_refunded', 'refunded');
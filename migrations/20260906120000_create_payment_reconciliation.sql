@@ -0,0 +1,13 @@
+ALTER TABLE orders ADD COLUMN payment_checked_at TIMESTAMPTZ;
+
+CREATE INDEX idx_orders_unsettled_card_payments ON orders(created_at)
+    WHERE status IN ('pending', 'expired') AND checkout_url IS NOT NULL;
+
+CREATE TABLE payment_reconciliation_reports (
+    id SERIAL PRIMARY KEY,
+    report_date DATE NOT NULL UNIQUE,
+    orders_checked INTEGER NOT NULL,
+    mismatch_count INTEGER NOT NULL,
+    mismatches JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,12 @@
+CREATE TABLE idempotency_keys (
+    id SERIAL PRIMARY KEY,
+    scope TEXT NOT NULL CHECK (scope IN ('checkout', 'admin_order')),
+    idempotency_key TEXT NOT NULL,
+    user_id INTEGER REFERENCES users(id) ON DELETE CASCADE,
+    response JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ,
+    UNIQUE (scope, idempotency_key)
+);
+
+CREATE INDEX idx_idempotency_keys_created_at ON idempotency_keys(created_at);
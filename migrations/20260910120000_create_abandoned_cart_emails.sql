@@ -0,0 +1,19 @@
+CREATE TABLE abandoned_cart_emails (
+    id                SERIAL PRIMARY KEY,
+    session_id        UUID NOT NULL UNIQUE,
+    email             TEXT NOT NULL,
+    cart              JSONB NOT NULL,
+    restore_token     UUID NOT NULL UNIQUE DEFAULT gen_random_uuid(),
+    unsubscribe_token UUID NOT NULL UNIQUE DEFAULT gen_random_uuid(),
+    sent_at           TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    restored_at       TIMESTAMPTZ
+);
+
+CREATE INDEX idx_abandoned_cart_emails_email ON abandoned_cart_emails(email, sent_at);
+
+CREATE TABLE email_opt_outs (
+    email      TEXT PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_checkout_analytics_created_at ON checkout_analytics(created_at);
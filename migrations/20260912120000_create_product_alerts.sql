@@ -0,0 +1,29 @@
+CREATE TABLE product_alerts (
+    id                SERIAL PRIMARY KEY,
+    product_id        TEXT NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    color             TEXT,
+    kind              TEXT NOT NULL CHECK (kind IN ('back_in_stock', 'price_drop')),
+    email             TEXT NOT NULL,
+    user_id           INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    -- price drops are measured against the price at subscription or at the last email
+    reference_price   DECIMAL(10, 2),
+    notified_at       TIMESTAMPTZ,
+    unsubscribe_token UUID NOT NULL UNIQUE DEFAULT gen_random_uuid(),
+    created_at        TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX idx_product_alerts_subscriber
+    ON product_alerts(product_id, COALESCE(color, ''), kind, email);
+
+CREATE TABLE product_alert_deliveries (
+    id          SERIAL PRIMARY KEY,
+    alert_id    INTEGER NOT NULL REFERENCES product_alerts(id) ON DELETE CASCADE,
+    price       DECIMAL(10, 2),
+    attempts    INTEGER NOT NULL DEFAULT 0,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    sent_at     TIMESTAMPTZ
+);
+
+-- at most one queued email per subscription
+CREATE UNIQUE INDEX idx_product_alert_deliveries_pending
+    ON product_alert_deliveries(alert_id) WHERE sent_at IS NULL;
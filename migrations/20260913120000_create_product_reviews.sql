@@ -0,0 +1,37 @@
+CREATE TABLE product_reviews (
+    id                   SERIAL PRIMARY KEY,
+    product_id           TEXT NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    user_id              INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    rating               INTEGER NOT NULL CHECK (rating BETWEEN 1 AND 5),
+    body                 TEXT,
+    status               TEXT NOT NULL DEFAULT 'pending'
+                         CHECK (status IN ('pending', 'approved', 'rejected')),
+    moderated_by_user_id INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    moderated_at         TIMESTAMPTZ,
+    moderation_comment   TEXT,
+    created_at           TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at           TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (product_id, user_id)
+);
+
+CREATE INDEX idx_product_reviews_product_status ON product_reviews(product_id, status, created_at DESC);
+CREATE INDEX idx_product_reviews_status ON product_reviews(status, created_at);
+
+CREATE TABLE product_review_images (
+    id         SERIAL PRIMARY KEY,
+    review_id  INTEGER REFERENCES product_reviews(id) ON DELETE CASCADE,
+    user_id    INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    image_uuid UUID NOT NULL UNIQUE,
+    extension  VARCHAR(10) NOT NULL,
+    position   INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_product_review_images_review_id ON product_review_images(review_id);
+
+-- kept in sync with approved reviews so listings can sort by rating cheaply
+ALTER TABLE products
+    ADD COLUMN rating_avg   DECIMAL(3, 2),
+    ADD COLUMN rating_count INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX idx_products_rating ON products(rating_avg DESC NULLS LAST, rating_count DESC);
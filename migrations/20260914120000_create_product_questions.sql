@@ -0,0 +1,18 @@
+CREATE TABLE product_questions (
+    id                  SERIAL PRIMARY KEY,
+    product_id          TEXT NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    user_id             INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    author_name         TEXT NOT NULL,
+    email               TEXT NOT NULL,
+    question            TEXT NOT NULL,
+    answer              TEXT,
+    answered_by_user_id INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    answered_at         TIMESTAMPTZ,
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_product_questions_product ON product_questions(product_id, answered_at DESC)
+    WHERE answer IS NOT NULL;
+CREATE INDEX idx_product_questions_unanswered ON product_questions(created_at)
+    WHERE answer IS NULL;
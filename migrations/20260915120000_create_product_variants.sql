@@ -0,0 +1,100 @@
+CREATE TABLE product_variants (
+    id SERIAL PRIMARY KEY,
+    product_id TEXT NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    sku TEXT NOT NULL UNIQUE,
+    attributes JSONB NOT NULL DEFAULT '{}'::jsonb,
+    color TEXT GENERATED ALWAYS AS (attributes->>'color') STORED,
+    price_override DECIMAL(10, 2) CHECK (price_override IS NULL OR price_override >= 0),
+    stock INTEGER NOT NULL DEFAULT 0 CHECK (stock >= 0),
+    barcode TEXT UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (product_id, attributes)
+);
+
+CREATE INDEX idx_product_variants_product_id ON product_variants(product_id);
+CREATE INDEX idx_product_variants_color ON product_variants(color) WHERE color IS NOT NULL;
+
+-- one variant per color that was previously modelled as image rows
+INSERT INTO product_variants (product_id, sku, attributes, stock)
+SELECT product_id,
+       product_id || '-' || ROW_NUMBER() OVER (PARTITION BY product_id ORDER BY MIN(created_at)),
+       jsonb_strip_nulls(jsonb_build_object('color', NULLIF(color, ''))),
+       GREATEST(SUM(quantity), 0)
+FROM product_images
+GROUP BY product_id, NULLIF(color, '');
+
+-- products without images get a single default variant holding their stock
+INSERT INTO product_variants (product_id, sku, attributes, stock)
+SELECT p.id, p.id || '-1', '{}'::jsonb, GREATEST(p.quantity, 0)
+FROM products p
+WHERE NOT EXISTS (SELECT 1 FROM product_variants pv WHERE pv.product_id = p.id);
+
+ALTER TABLE product_images
+    ADD COLUMN variant_id INTEGER REFERENCES product_variants(id) ON DELETE SET NULL;
+
+UPDATE product_images pi SET variant_id = pv.id
+FROM product_variants pv
+WHERE pv.product_id = pi.product_id
+  AND pv.color IS NOT DISTINCT FROM NULLIF(pi.color, '');
+
+CREATE INDEX idx_product_images_variant_id ON product_images(variant_id);
+
+-- stock lives on variants now
+DROP TRIGGER trg_sync_product_quantity ON product_images;
+ALTER TABLE product_images DROP COLUMN quantity;
+
+CREATE OR REPLACE FUNCTION sync_product_quantity()
+RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE products
+    SET quantity = (
+        SELECT COALESCE(SUM(stock), 0)
+        FROM product_variants
+        WHERE product_id = COALESCE(NEW.product_id, OLD.product_id)
+    )
+    WHERE id = COALESCE(NEW.product_id, OLD.product_id);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_sync_product_quantity
+AFTER INSERT OR UPDATE OF stock OR DELETE ON product_variants
+FOR EACH ROW EXECUTE FUNCTION sync_product_quantity();
+
+UPDATE products p
+SET quantity = (
+    SELECT COALESCE(SUM(pv.stock), 0)
+    FROM product_variants pv
+    WHERE pv.product_id = p.id
+);
+
+-- lines remember which variant they took stock from
+ALTER TABLE order_items
+    ADD COLUMN variant_id INTEGER REFERENCES product_variants(id) ON DELETE SET NULL;
+
+UPDATE order_items oi SET variant_id = pv.id
+FROM product_variants pv
+WHERE pv.product_id = oi.product_id
+  AND pv.color IS NOT DISTINCT FROM NULLIF(oi.color, '');
+
+ALTER TABLE stock_reservations
+    ADD COLUMN variant_id INTEGER REFERENCES product_variants(id) ON DELETE CASCADE;
+
+UPDATE stock_reservations sr SET variant_id = pv.id
+FROM product_variants pv
+WHERE pv.product_id = sr.product_id
+  AND pv.color IS NOT DISTINCT FROM NULLIF(sr.color, '');
+
+DELETE FROM stock_reservations WHERE variant_id IS NULL;
+ALTER TABLE stock_reservations ALTER COLUMN variant_id SET NOT NULL;
+CREATE INDEX idx_stock_reservations_variant_id ON stock_reservations(variant_id);
+
+ALTER TABLE cart_items
+    ADD COLUMN variant_id INTEGER REFERENCES product_variants(id) ON DELETE CASCADE;
+
+DROP INDEX idx_cart_items_line;
+CREATE UNIQUE INDEX idx_cart_items_line ON cart_items(
+    cart_id, product_id, COALESCE(variant_id, 0), COALESCE(color, ''),
+    COALESCE(cable_watts, 0), COALESCE(cable_length_cm, 0)
+);
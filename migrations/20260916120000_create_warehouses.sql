@@ -0,0 +1,77 @@
+CREATE TABLE warehouses (
+    id SERIAL PRIMARY KEY,
+    code TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL DEFAULT 'warehouse' CHECK (kind IN ('warehouse', 'store')),
+    address TEXT,
+    fulfills_shipping BOOLEAN NOT NULL DEFAULT false,
+    fulfills_pickup BOOLEAN NOT NULL DEFAULT false,
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- at most one location ships orders and one serves store pickups
+CREATE UNIQUE INDEX idx_warehouses_fulfills_shipping ON warehouses(fulfills_shipping) WHERE fulfills_shipping;
+CREATE UNIQUE INDEX idx_warehouses_fulfills_pickup ON warehouses(fulfills_pickup) WHERE fulfills_pickup;
+
+CREATE TABLE warehouse_stock (
+    warehouse_id INTEGER NOT NULL REFERENCES warehouses(id),
+    variant_id INTEGER NOT NULL REFERENCES product_variants(id) ON DELETE CASCADE,
+    quantity INTEGER NOT NULL DEFAULT 0 CHECK (quantity >= 0),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (warehouse_id, variant_id)
+);
+
+CREATE INDEX idx_warehouse_stock_variant_id ON warehouse_stock(variant_id);
+
+-- append-only; every change to warehouse_stock has a row here
+CREATE TABLE stock_movements (
+    id BIGSERIAL PRIMARY KEY,
+    variant_id INTEGER NOT NULL REFERENCES product_variants(id) ON DELETE CASCADE,
+    warehouse_id INTEGER NOT NULL REFERENCES warehouses(id),
+    quantity INTEGER NOT NULL CHECK (quantity <> 0),
+    kind TEXT NOT NULL CHECK (kind IN ('receipt', 'sale', 'return', 'adjustment', 'transfer')),
+    reason TEXT,
+    order_id INTEGER REFERENCES orders(id) ON DELETE SET NULL,
+    transfer_id UUID,
+    created_by_user_id INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_stock_movements_variant_id ON stock_movements(variant_id, created_at);
+CREATE INDEX idx_stock_movements_warehouse_id ON stock_movements(warehouse_id, created_at);
+CREATE INDEX idx_stock_movements_order_id ON stock_movements(order_id) WHERE order_id IS NOT NULL;
+
+-- variant stock is the total over all locations
+CREATE OR REPLACE FUNCTION sync_variant_stock()
+RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE product_variants
+    SET stock = (
+        SELECT COALESCE(SUM(quantity), 0)
+        FROM warehouse_stock
+        WHERE variant_id = COALESCE(NEW.variant_id, OLD.variant_id)
+    ),
+    updated_at = NOW()
+    WHERE id = COALESCE(NEW.variant_id, OLD.variant_id);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_sync_variant_stock
+AFTER INSERT OR UPDATE OF quantity OR DELETE ON warehouse_stock
+FOR EACH ROW EXECUTE FUNCTION sync_variant_stock();
+
+-- existing stock moves into a single main warehouse
+INSERT INTO warehouses (code, name, fulfills_shipping, fulfills_pickup)
+VALUES ('main', 'მთავარი საწყობი', true, true);
+
+INSERT INTO warehouse_stock (warehouse_id, variant_id, quantity)
+SELECT w.id, pv.id, pv.stock
+FROM product_variants pv, warehouses w
+WHERE w.code = 'main' AND pv.stock > 0;
+
+INSERT INTO stock_movements (variant_id, warehouse_id, quantity, kind, reason)
+SELECT variant_id, warehouse_id, quantity, 'receipt', 'opening balance'
+FROM warehouse_stock;
@@ -0,0 +1,11 @@
+-- NULL falls back to the configured default threshold
+ALTER TABLE products ADD COLUMN low_stock_threshold INTEGER CHECK (low_stock_threshold >= 0);
+
+-- one row per day a digest went out, so restarts and multiple instances don't resend
+CREATE TABLE low_stock_digests (
+    digest_date DATE PRIMARY KEY,
+    product_count INTEGER NOT NULL,
+    sent_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_orders_stock_deducted_at ON orders(stock_deducted_at) WHERE stock_deducted_at IS NOT NULL;
@@ -0,0 +1,21 @@
+-- bulk product imports and exports, processed in the background
+CREATE TABLE catalog_jobs (
+    id                  SERIAL PRIMARY KEY,
+    kind                TEXT NOT NULL CHECK (kind IN ('import', 'export')),
+    format              TEXT NOT NULL CHECK (format IN ('xlsx', 'csv')),
+    status              TEXT NOT NULL DEFAULT 'pending'
+                        CHECK (status IN ('pending', 'running', 'completed', 'failed')),
+    dry_run             BOOLEAN NOT NULL DEFAULT false,
+    file_name           TEXT,
+    input               BYTEA,
+    output              BYTEA,
+    report              JSONB,
+    error               TEXT,
+    created_by_user_id  INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    started_at          TIMESTAMPTZ,
+    finished_at         TIMESTAMPTZ
+);
+
+CREATE INDEX idx_catalog_jobs_created_at ON catalog_jobs(created_at DESC);
+CREATE INDEX idx_catalog_jobs_pending ON catalog_jobs(id) WHERE status = 'pending';
@@ -0,0 +1,39 @@
+CREATE TABLE scheduled_price_changes (
+    id                  SERIAL PRIMARY KEY,
+    product_id          TEXT NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    price               DECIMAL(10, 2),
+    discount            DECIMAL(5, 2),
+    discounted_price    DECIMAL(10, 2),
+    apply_at            TIMESTAMPTZ NOT NULL,
+    status              TEXT NOT NULL DEFAULT 'pending'
+                        CHECK (status IN ('pending', 'applied', 'cancelled', 'failed')),
+    error               TEXT,
+    created_by_user_id  INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    applied_at          TIMESTAMPTZ,
+    cancelled_at        TIMESTAMPTZ,
+    CHECK (price IS NOT NULL OR discount IS NOT NULL OR discounted_price IS NOT NULL)
+);
+
+CREATE INDEX idx_scheduled_price_changes_due ON scheduled_price_changes(apply_at) WHERE status = 'pending';
+CREATE INDEX idx_scheduled_price_changes_product_id ON scheduled_price_changes(product_id);
+
+-- one row per distinct price state; a row holds the prices in effect from created_at
+CREATE TABLE product_price_history (
+    id                  BIGSERIAL PRIMARY KEY,
+    product_id          TEXT NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    price               DECIMAL(10, 2) NOT NULL,
+    discount            DECIMAL(5, 2) NOT NULL,
+    discounted_price    DECIMAL(10, 2),
+    source              TEXT NOT NULL CHECK (source IN ('admin', 'import', 'schedule')),
+    schedule_id         INTEGER REFERENCES scheduled_price_changes(id) ON DELETE SET NULL,
+    changed_by_user_id  INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_product_price_history_product_id ON product_price_history(product_id, id DESC);
+
+-- current prices are only known to hold since the product was last edited
+INSERT INTO product_price_history (product_id, price, discount, discounted_price, source, created_at)
+SELECT id, price, COALESCE(discount, 0), discounted_price, 'admin', updated_at
+FROM products;
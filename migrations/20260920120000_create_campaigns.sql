@@ -0,0 +1,59 @@
+CREATE TABLE campaigns (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    discount_percent DECIMAL(5, 2) NOT NULL CHECK (discount_percent > 0 AND discount_percent < 100),
+    -- a product is covered when it is in one of the categories (or their
+    -- subcategories) or from one of the brands; both empty covers everything
+    category_ids INTEGER[] NOT NULL DEFAULT '{}',
+    brand_ids INTEGER[] NOT NULL DEFAULT '{}',
+    priority INTEGER NOT NULL DEFAULT 0,
+    starts_at TIMESTAMPTZ NOT NULL,
+    ends_at TIMESTAMPTZ NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (ends_at > starts_at)
+);
+
+CREATE INDEX idx_campaigns_window ON campaigns(starts_at, ends_at) WHERE enabled;
+
+-- the campaign a line was priced by and how much it took off each unit
+ALTER TABLE order_items
+    ADD COLUMN campaign_id INTEGER REFERENCES campaigns(id) ON DELETE SET NULL,
+    ADD COLUMN campaign_discount DECIMAL(10, 2);
+
+CREATE INDEX idx_order_items_campaign_id ON order_items(campaign_id) WHERE campaign_id IS NOT NULL;
+
+-- one row per product a running campaign currently discounts. Overlaps go to
+-- the highest priority (then the oldest campaign); the campaign price is taken
+-- off the base price and only applies when it beats the product's own discount.
+CREATE VIEW active_campaign_prices AS
+WITH RECURSIVE active AS (
+    SELECT * FROM campaigns
+    WHERE enabled AND starts_at <= NOW() AND ends_at > NOW()
+), scope(campaign_id, category_id) AS (
+    SELECT a.id, unnest(a.category_ids) FROM active a
+    UNION
+    SELECT s.campaign_id, c.id FROM categories c JOIN scope s ON c.parent_id = s.category_id
+), ranked AS (
+    SELECT DISTINCT ON (p.id)
+        p.id AS product_id,
+        a.id AS campaign_id,
+        a.name AS campaign_name,
+        ROUND(p.price * (100 - a.discount_percent) / 100, 2) AS campaign_price,
+        a.ends_at AS campaign_ends_at,
+        CASE WHEN p.discounted_price < p.price THEN p.discounted_price ELSE p.price END AS own_price
+    FROM active a
+    JOIN products p ON (cardinality(a.category_ids) = 0 AND cardinality(a.brand_ids) = 0)
+        OR p.brand_id = ANY(a.brand_ids)
+        OR EXISTS (
+            SELECT 1 FROM product_categories pc
+            JOIN scope s ON s.category_id = pc.category_id
+            WHERE s.campaign_id = a.id AND pc.product_id = p.id
+        )
+    ORDER BY p.id, a.priority DESC, a.id
+)
+SELECT product_id, campaign_id, campaign_name, campaign_price, campaign_ends_at
+FROM ranked
+WHERE campaign_price < own_price;
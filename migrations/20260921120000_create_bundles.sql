@@ -0,0 +1,55 @@
+-- a product with components is a bundle: it holds no stock of its own and is
+-- priced like any other product
+CREATE TABLE bundle_components (
+    bundle_id TEXT NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    variant_id INTEGER NOT NULL REFERENCES product_variants(id),
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    PRIMARY KEY (bundle_id, variant_id)
+);
+
+CREATE INDEX idx_bundle_components_variant_id ON bundle_components(variant_id);
+
+-- what one unit of a bundle line took from stock, fixed at checkout
+ALTER TABLE order_items ADD COLUMN bundle_items JSONB;
+
+-- a bundle's quantity is how many complete sets its components' stock makes up
+CREATE OR REPLACE FUNCTION refresh_bundle_quantity(bundle_ids TEXT[])
+RETURNS VOID AS $$
+BEGIN
+    UPDATE products p
+    SET quantity = COALESCE((
+        SELECT MIN(pv.stock / bc.quantity)
+        FROM bundle_components bc
+        JOIN product_variants pv ON pv.id = bc.variant_id
+        WHERE bc.bundle_id = p.id
+    ), 0)
+    WHERE p.id = ANY(bundle_ids);
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION sync_bundle_quantity_from_variant()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM refresh_bundle_quantity(ARRAY(
+        SELECT bundle_id FROM bundle_components
+        WHERE variant_id = COALESCE(NEW.id, OLD.id)
+    ));
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_sync_bundle_quantity
+AFTER UPDATE OF stock ON product_variants
+FOR EACH ROW EXECUTE FUNCTION sync_bundle_quantity_from_variant();
+
+CREATE OR REPLACE FUNCTION sync_bundle_quantity_from_component()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM refresh_bundle_quantity(ARRAY[COALESCE(NEW.bundle_id, OLD.bundle_id)]);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_sync_bundle_quantity
+AFTER INSERT OR UPDATE OR DELETE ON bundle_components
+FOR EACH ROW EXECUTE FUNCTION sync_bundle_quantity_from_component();
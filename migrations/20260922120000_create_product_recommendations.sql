@@ -0,0 +1,20 @@
+-- Client-generated session id so anonymous browsing can be grouped per visitor.
+ALTER TABLE product_views ADD COLUMN session_id UUID;
+
+CREATE INDEX idx_product_views_session_id ON product_views(session_id)
+    WHERE session_id IS NOT NULL;
+
+-- Precomputed by the recommendation job; rebuilt wholesale on every run.
+CREATE TABLE product_recommendations (
+    product_id         TEXT NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    related_product_id TEXT NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    co_views           INTEGER NOT NULL DEFAULT 0,
+    co_purchases       INTEGER NOT NULL DEFAULT 0,
+    score              DOUBLE PRECISION NOT NULL,
+    computed_at        TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (product_id, related_product_id),
+    CHECK (product_id <> related_product_id)
+);
+
+CREATE INDEX idx_product_recommendations_score
+    ON product_recommendations(product_id, score DESC);
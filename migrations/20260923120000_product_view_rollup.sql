@@ -0,0 +1,67 @@
+-- who a view is attributed to for de-duplication: the user, else the client
+-- session, else the client address
+ALTER TABLE product_views ADD COLUMN viewer_key TEXT;
+ALTER TABLE product_views ADD COLUMN ip_address TEXT;
+
+CREATE INDEX idx_product_views_viewer_dedup
+    ON product_views(viewer_key, product_id, viewed_at)
+    WHERE viewer_key IS NOT NULL;
+CREATE INDEX idx_product_views_ip_recent
+    ON product_views(ip_address, viewed_at)
+    WHERE ip_address IS NOT NULL;
+
+-- per-product daily view counts read by the admin analytics; hourly_views has
+-- one entry per hour of the day, starting at 00
+CREATE TABLE product_view_daily (
+    product_id TEXT NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    day DATE NOT NULL,
+    views BIGINT NOT NULL,
+    anonymous_views BIGINT NOT NULL,
+    logged_in_viewers BIGINT NOT NULL,
+    hourly_views BIGINT[] NOT NULL,
+    PRIMARY KEY (product_id, day)
+);
+
+CREATE INDEX idx_product_view_daily_day ON product_view_daily(day);
+
+-- recomputes every day from from_day on out of the raw rows
+CREATE OR REPLACE FUNCTION refresh_product_view_daily(from_day DATE)
+RETURNS VOID AS $$
+BEGIN
+    DELETE FROM product_view_daily WHERE day >= from_day;
+
+    INSERT INTO product_view_daily
+        (product_id, day, views, anonymous_views, logged_in_viewers, hourly_views)
+    WITH hourly AS (
+        SELECT product_id,
+               viewed_at::date AS day,
+               EXTRACT(HOUR FROM viewed_at)::int AS hour,
+               COUNT(*) AS views
+        FROM product_views
+        WHERE viewed_at >= from_day
+        GROUP BY 1, 2, 3
+    ),
+    daily AS (
+        SELECT product_id,
+               viewed_at::date AS day,
+               COUNT(*) AS views,
+               COUNT(*) FILTER (WHERE user_id IS NULL) AS anonymous_views,
+               COUNT(DISTINCT user_id) AS logged_in_viewers
+        FROM product_views
+        WHERE viewed_at >= from_day
+        GROUP BY 1, 2
+    )
+    SELECT d.product_id, d.day, d.views, d.anonymous_views, d.logged_in_viewers,
+           ARRAY(
+               SELECT COALESCE(h.views, 0)
+               FROM generate_series(0, 23) AS g(hour)
+               LEFT JOIN hourly h
+                   ON h.product_id = d.product_id AND h.day = d.day AND h.hour = g.hour
+               ORDER BY g.hour
+           )
+    FROM daily d;
+END;
+$$ LANGUAGE plpgsql;
+
+SELECT refresh_product_view_daily(COALESCE(MIN(viewed_at)::date, CURRENT_DATE))
+FROM product_views;
@@ -0,0 +1,186 @@
+-- Folds Georgian script and the usual Latin spellings of it onto one Latin
+-- form, so "კაბელი", "kabeli" and "qabeli" end up comparable. Applied to both
+-- the indexed text and the query.
+CREATE OR REPLACE FUNCTION search_normalize(input TEXT)
+RETURNS TEXT AS $$
+DECLARE
+    s TEXT := lower(COALESCE(input, ''));
+BEGIN
+    s := replace(s, 'შ', 'sh');
+    s := replace(s, 'ჩ', 'ch');
+    s := replace(s, 'ჭ', 'ch');
+    s := replace(s, 'ც', 'ts');
+    s := replace(s, 'წ', 'ts');
+    s := replace(s, 'ძ', 'dz');
+    s := replace(s, 'ხ', 'kh');
+    s := translate(s, 'აბგდევზთიკლმნოპჟრსტუფქღყჯჰ', 'abgdevztiklmnopjrstupkgkjh');
+
+    s := replace(s, 'zh', 'j');
+    s := replace(s, 'gh', 'g');
+    s := replace(s, 'ph', 'p');
+    s := replace(s, 'th', 't');
+    s := replace(s, 'x', 'kh');
+    s := replace(s, 'w', 'ts');
+    s := replace(s, 'q', 'k');
+    s := regexp_replace(s, 'c(?!h)', 'ts', 'g');
+
+    RETURN s;
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+-- one row per product; document is what full-text search matches against and
+-- search_text the same fields as plain text for typo-tolerant trigram matching
+CREATE TABLE product_search_documents (
+    product_id TEXT PRIMARY KEY REFERENCES products(id) ON DELETE CASCADE,
+    document TSVECTOR NOT NULL,
+    search_text TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_product_search_documents_document
+    ON product_search_documents USING GIN (document);
+
+CREATE OR REPLACE FUNCTION refresh_product_search(product_ids TEXT[])
+RETURNS VOID AS $$
+BEGIN
+    INSERT INTO product_search_documents (product_id, document, search_text, updated_at)
+    SELECT p.id,
+           setweight(to_tsvector('simple', search_normalize(p.name)), 'A')
+           || setweight(to_tsvector('simple', search_normalize(b.name)), 'A')
+           || setweight(to_tsvector('simple', search_normalize(seo.terms)), 'A')
+           || setweight(to_tsvector('simple', search_normalize(cats.names)), 'B')
+           || setweight(to_tsvector('simple', search_normalize(specs.vals)), 'C')
+           || setweight(to_tsvector('simple', search_normalize(p.description)), 'D'),
+           search_normalize(concat_ws(' ', p.name, b.name, seo.terms, cats.names, specs.vals)),
+           NOW()
+    FROM products p
+    LEFT JOIN brands b ON b.id = p.brand_id
+    LEFT JOIN LATERAL (
+        SELECT array_to_string(s.search_terms, ' ') AS terms
+        FROM product_seo s
+        WHERE s.product_id = p.id
+    ) seo ON true
+    LEFT JOIN LATERAL (
+        SELECT string_agg(c.name, ' ') AS names
+        FROM product_categories pc
+        JOIN categories c ON c.id = pc.category_id
+        WHERE pc.product_id = p.id
+    ) cats ON true
+    LEFT JOIN LATERAL (
+        SELECT string_agg(v #>> '{}', ' ') AS vals
+        FROM jsonb_path_query(
+            COALESCE(p.specifications, '{}'::jsonb),
+            'strict $.** ? (@.type() == "string" || @.type() == "number")'
+        ) AS v
+    ) specs ON true
+    WHERE p.id = ANY(product_ids)
+    ON CONFLICT (product_id) DO UPDATE
+    SET document = EXCLUDED.document,
+        search_text = EXCLUDED.search_text,
+        updated_at = NOW();
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION sync_product_search_from_product()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM refresh_product_search(ARRAY[NEW.id]);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_sync_product_search
+AFTER INSERT OR UPDATE OF name, description, brand_id, specifications ON products
+FOR EACH ROW EXECUTE FUNCTION sync_product_search_from_product();
+
+-- product_seo and product_categories rows both carry product_id
+CREATE OR REPLACE FUNCTION sync_product_search_from_link()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP IN ('UPDATE', 'DELETE') THEN
+        PERFORM refresh_product_search(ARRAY[OLD.product_id]);
+    END IF;
+    IF TG_OP IN ('INSERT', 'UPDATE') THEN
+        PERFORM refresh_product_search(ARRAY[NEW.product_id]);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_sync_product_search
+AFTER INSERT OR UPDATE OF search_terms OR DELETE ON product_seo
+FOR EACH ROW EXECUTE FUNCTION sync_product_search_from_link();
+
+CREATE TRIGGER trg_sync_product_search
+AFTER INSERT OR UPDATE OR DELETE ON product_categories
+FOR EACH ROW EXECUTE FUNCTION sync_product_search_from_link();
+
+CREATE OR REPLACE FUNCTION sync_product_search_from_brand()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM refresh_product_search(ARRAY(SELECT id FROM products WHERE brand_id = NEW.id));
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_sync_product_search
+AFTER UPDATE OF name ON brands
+FOR EACH ROW EXECUTE FUNCTION sync_product_search_from_brand();
+
+CREATE OR REPLACE FUNCTION sync_product_search_from_category()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM refresh_product_search(
+        ARRAY(SELECT product_id FROM product_categories WHERE category_id = NEW.id)
+    );
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_sync_product_search
+AFTER UPDATE OF name ON categories
+FOR EACH ROW EXECUTE FUNCTION sync_product_search_from_category();
+
+SELECT refresh_product_search(ARRAY(SELECT id FROM products));
+
+-- admin-managed; a query word matching the term or any synonym also matches
+-- the rest of the group. Multi-word entries match as phrases.
+CREATE TABLE search_synonyms (
+    id SERIAL PRIMARY KEY,
+    term TEXT NOT NULL,
+    synonyms TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX idx_search_synonyms_term ON search_synonyms (search_normalize(term));
+
+-- every query word becomes a prefix match OR'd with its synonyms; the words
+-- are AND'ed together
+CREATE OR REPLACE FUNCTION product_search_query(q TEXT)
+RETURNS TSQUERY AS $$
+    WITH words AS (
+        SELECT DISTINCT w
+        FROM regexp_split_to_table(search_normalize(q), '[^a-z0-9]+') AS w
+        WHERE w <> ''
+    ),
+    phrases AS (
+        SELECT s.id, trim(regexp_replace(search_normalize(t), '[^a-z0-9]+', ' ', 'g')) AS phrase
+        FROM search_synonyms s, unnest(array_prepend(s.term, s.synonyms)) AS t
+    ),
+    alternatives AS (
+        SELECT w, w || ':*' AS alt FROM words
+        UNION
+        SELECT words.w, replace(other.phrase, ' ', ' <-> ')
+        FROM words
+        JOIN phrases own ON own.phrase = words.w
+        JOIN phrases other
+            ON other.id = own.id AND other.phrase <> own.phrase AND other.phrase <> ''
+    )
+    SELECT to_tsquery('simple', string_agg('(' || alts || ')', ' & '))
+    FROM (
+        SELECT w, string_agg(alt, ' | ') AS alts
+        FROM alternatives
+        GROUP BY w
+    ) grouped;
+$$ LANGUAGE sql STABLE;
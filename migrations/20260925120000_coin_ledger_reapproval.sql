@@ -0,0 +1,10 @@
+-- a declined or expired order that still gets paid debits its coins again, so
+-- an order can carry more than one redeem/return entry; those hooks now check
+-- the order's running total instead. Earning stays once per order.
+DROP INDEX idx_coin_ledger_order_reason;
+
+CREATE UNIQUE INDEX idx_coin_ledger_order_earned
+    ON coin_ledger(order_id)
+    WHERE order_id IS NOT NULL AND reason = 'earned';
+
+CREATE INDEX idx_coin_ledger_order ON coin_ledger(order_id) WHERE order_id IS NOT NULL;
@@ -0,0 +1,2 @@
+-- hash of the request body; a key reused for a different request is refused
+ALTER TABLE idempotency_keys ADD COLUMN request_fingerprint TEXT;
@@ -0,0 +1,33 @@
+-- the percentage is also applied to variant and cable prices, which the
+-- view's campaign_price doesn't cover
+CREATE OR REPLACE VIEW active_campaign_prices AS
+WITH RECURSIVE active AS (
+    SELECT * FROM campaigns
+    WHERE enabled AND starts_at <= NOW() AND ends_at > NOW()
+), scope(campaign_id, category_id) AS (
+    SELECT a.id, unnest(a.category_ids) FROM active a
+    UNION
+    SELECT s.campaign_id, c.id FROM categories c JOIN scope s ON c.parent_id = s.category_id
+), ranked AS (
+    SELECT DISTINCT ON (p.id)
+        p.id AS product_id,
+        a.id AS campaign_id,
+        a.name AS campaign_name,
+        ROUND(p.price * (100 - a.discount_percent) / 100, 2) AS campaign_price,
+        a.ends_at AS campaign_ends_at,
+        a.discount_percent AS campaign_discount_percent,
+        CASE WHEN p.discounted_price < p.price THEN p.discounted_price ELSE p.price END AS own_price
+    FROM active a
+    JOIN products p ON (cardinality(a.category_ids) = 0 AND cardinality(a.brand_ids) = 0)
+        OR p.brand_id = ANY(a.brand_ids)
+        OR EXISTS (
+            SELECT 1 FROM product_categories pc
+            JOIN scope s ON s.category_id = pc.category_id
+            WHERE s.campaign_id = a.id AND pc.product_id = p.id
+        )
+    ORDER BY p.id, a.priority DESC, a.id
+)
+SELECT product_id, campaign_id, campaign_name, campaign_price, campaign_ends_at,
+       campaign_discount_percent
+FROM ranked
+WHERE campaign_price < own_price;
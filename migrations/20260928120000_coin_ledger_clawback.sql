@@ -0,0 +1,5 @@
+-- coins earned on an order that is cancelled, returned or refunded are taken
+-- back; partial refunds take back their share
+ALTER TABLE coin_ledger DROP CONSTRAINT coin_ledger_reason_check;
+ALTER TABLE coin_ledger ADD CONSTRAINT coin_ledger_reason_check
+    CHECK (reason IN ('earned', 'redeemed', 'returned', 'adjustment', 'clawback'));
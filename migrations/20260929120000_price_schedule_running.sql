@@ -0,0 +1,6 @@
+-- a claimed schedule stays 'running' until the price change that applies it
+-- commits; one left running by a crashed worker is picked up again
+ALTER TABLE scheduled_price_changes ADD COLUMN claimed_at TIMESTAMPTZ;
+ALTER TABLE scheduled_price_changes DROP CONSTRAINT scheduled_price_changes_status_check;
+ALTER TABLE scheduled_price_changes ADD CONSTRAINT scheduled_price_changes_status_check
+    CHECK (status IN ('pending', 'running', 'applied', 'cancelled', 'failed'));
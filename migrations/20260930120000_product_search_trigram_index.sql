@@ -0,0 +1,4 @@
+-- lets the typo-tolerant `<%` match on search_text use an index instead of
+-- scoring every document
+CREATE INDEX idx_product_search_documents_search_text_trgm
+    ON product_search_documents USING GIN (search_text gin_trgm_ops);
@@ -0,0 +1,5 @@
+-- redemptions are part of the orders' history; a used coupon is disabled, not
+-- deleted
+ALTER TABLE coupon_redemptions DROP CONSTRAINT coupon_redemptions_coupon_id_fkey;
+ALTER TABLE coupon_redemptions ADD CONSTRAINT coupon_redemptions_coupon_id_fkey
+    FOREIGN KEY (coupon_id) REFERENCES coupons(id) ON DELETE RESTRICT;
@@ -0,0 +1,3 @@
+-- a request that failed after its order was created keeps its key, answered
+-- with the same error status
+ALTER TABLE idempotency_keys ADD COLUMN response_status SMALLINT NOT NULL DEFAULT 200;
@@ -0,0 +1,42 @@
+-- bucket views by the UTC day and hour, the same boundary the rollup job picks
+-- its from_day with, whatever the session time zone is
+CREATE OR REPLACE FUNCTION refresh_product_view_daily(from_day DATE)
+RETURNS VOID AS $$
+BEGIN
+    DELETE FROM product_view_daily WHERE day >= from_day;
+
+    INSERT INTO product_view_daily
+        (product_id, day, views, anonymous_views, logged_in_viewers, hourly_views)
+    WITH hourly AS (
+        SELECT product_id,
+               (viewed_at AT TIME ZONE 'UTC')::date AS day,
+               EXTRACT(HOUR FROM viewed_at AT TIME ZONE 'UTC')::int AS hour,
+               COUNT(*) AS views
+        FROM product_views
+        WHERE viewed_at >= from_day::timestamp AT TIME ZONE 'UTC'
+        GROUP BY 1, 2, 3
+    ),
+    daily AS (
+        SELECT product_id,
+               (viewed_at AT TIME ZONE 'UTC')::date AS day,
+               COUNT(*) AS views,
+               COUNT(*) FILTER (WHERE user_id IS NULL) AS anonymous_views,
+               COUNT(DISTINCT user_id) AS logged_in_viewers
+        FROM product_views
+        WHERE viewed_at >= from_day::timestamp AT TIME ZONE 'UTC'
+        GROUP BY 1, 2
+    )
+    SELECT d.product_id, d.day, d.views, d.anonymous_views, d.logged_in_viewers,
+           ARRAY(
+               SELECT COALESCE(h.views, 0)
+               FROM generate_series(0, 23) AS g(hour)
+               LEFT JOIN hourly h
+                   ON h.product_id = d.product_id AND h.day = d.day AND h.hour = g.hour
+               ORDER BY g.hour
+           )
+    FROM daily d;
+END;
+$$ LANGUAGE plpgsql;
+
+SELECT refresh_product_view_daily(COALESCE(MIN((viewed_at AT TIME ZONE 'UTC')::date), CURRENT_DATE))
+FROM product_views;
@@ -0,0 +1,35 @@
+-- only campaigns that undercut the product's own price compete on priority,
+-- so a higher-priority campaign that doesn't can't hide one that does
+CREATE OR REPLACE VIEW active_campaign_prices AS
+WITH RECURSIVE active AS (
+    SELECT * FROM campaigns
+    WHERE enabled AND starts_at <= NOW() AND ends_at > NOW()
+), scope(campaign_id, category_id) AS (
+    SELECT a.id, unnest(a.category_ids) FROM active a
+    UNION
+    SELECT s.campaign_id, c.id FROM categories c JOIN scope s ON c.parent_id = s.category_id
+), eligible AS (
+    SELECT
+        p.id AS product_id,
+        a.id AS campaign_id,
+        a.name AS campaign_name,
+        ROUND(p.price * (100 - a.discount_percent) / 100, 2) AS campaign_price,
+        a.ends_at AS campaign_ends_at,
+        a.discount_percent AS campaign_discount_percent,
+        a.priority,
+        CASE WHEN p.discounted_price < p.price THEN p.discounted_price ELSE p.price END AS own_price
+    FROM active a
+    JOIN products p ON (cardinality(a.category_ids) = 0 AND cardinality(a.brand_ids) = 0)
+        OR p.brand_id = ANY(a.brand_ids)
+        OR EXISTS (
+            SELECT 1 FROM product_categories pc
+            JOIN scope s ON s.category_id = pc.category_id
+            WHERE s.campaign_id = a.id AND pc.product_id = p.id
+        )
+)
+SELECT DISTINCT ON (product_id)
+       product_id, campaign_id, campaign_name, campaign_price, campaign_ends_at,
+       campaign_discount_percent
+FROM eligible
+WHERE campaign_price < own_price
+ORDER BY product_id, priority DESC, campaign_id;
@@ -0,0 +1,51 @@
+-- variant stock only counts enabled locations, the ones sales deduct from
+CREATE OR REPLACE FUNCTION sync_variant_stock()
+RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE product_variants
+    SET stock = (
+        SELECT COALESCE(SUM(ws.quantity), 0)
+        FROM warehouse_stock ws
+        JOIN warehouses w ON w.id = ws.warehouse_id
+        WHERE ws.variant_id = COALESCE(NEW.variant_id, OLD.variant_id) AND w.enabled
+    ),
+    updated_at = NOW()
+    WHERE id = COALESCE(NEW.variant_id, OLD.variant_id);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- enabling or disabling a location changes the stock of everything it holds
+CREATE OR REPLACE FUNCTION sync_warehouse_variants_stock()
+RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE product_variants pv
+    SET stock = (
+        SELECT COALESCE(SUM(ws.quantity), 0)
+        FROM warehouse_stock ws
+        JOIN warehouses w ON w.id = ws.warehouse_id
+        WHERE ws.variant_id = pv.id AND w.enabled
+    ),
+    updated_at = NOW()
+    WHERE pv.id IN (SELECT variant_id FROM warehouse_stock WHERE warehouse_id = NEW.id);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_sync_warehouse_variants_stock
+AFTER UPDATE OF enabled ON warehouses
+FOR EACH ROW WHEN (OLD.enabled IS DISTINCT FROM NEW.enabled)
+EXECUTE FUNCTION sync_warehouse_variants_stock();
+
+UPDATE product_variants pv
+SET stock = s.stock, updated_at = NOW()
+FROM (
+    SELECT v.id,
+           (SELECT COALESCE(SUM(ws.quantity), 0)
+            FROM warehouse_stock ws
+            JOIN warehouses w ON w.id = ws.warehouse_id
+            WHERE ws.variant_id = v.id AND w.enabled) AS stock
+    FROM product_variants v
+    WHERE EXISTS (SELECT 1 FROM warehouse_stock ws WHERE ws.variant_id = v.id)
+) s
+WHERE pv.id = s.id AND pv.stock <> s.stock;
@@ -0,0 +1,10 @@
+-- a delivery that ran out of attempts is given up on, so it no longer holds
+-- the subscription's queued slot and a later event can queue a new email
+ALTER TABLE product_alert_deliveries ADD COLUMN failed_at TIMESTAMPTZ;
+
+UPDATE product_alert_deliveries SET failed_at = NOW()
+WHERE sent_at IS NULL AND attempts >= 5;
+
+DROP INDEX idx_product_alert_deliveries_pending;
+CREATE UNIQUE INDEX idx_product_alert_deliveries_pending
+    ON product_alert_deliveries(alert_id) WHERE sent_at IS NULL AND failed_at IS NULL;
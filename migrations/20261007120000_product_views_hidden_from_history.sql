@@ -0,0 +1,3 @@
+-- clearing recently viewed hides the rows from the user's history but keeps
+-- who viewed, so analytics and view de-duplication are unaffected
+ALTER TABLE product_views ADD COLUMN hidden_from_history_at TIMESTAMPTZ;
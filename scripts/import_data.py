@@ -3,7 +3,7 @@
 Import categories, brands, and products from old project CSVs into the new Tene backend.
 
 Key improvements over old script:
-- Products grouped by `code`: color variants become images on one product, not separate products
+- Products grouped by `code`: each color becomes a variant with its stock in the shipping warehouse, plus its images
 - Product ID is TEXT (the `code` field from old DB)
 - Category mapping uses link_cat with fallback to middle/parent fields
 - Brands imported from brands.csv directly
@@ -15,6 +15,7 @@ Usage:
 import csv
 import argparse
 import asyncio
+import json
 import math
 import os
 import re
@@ -430,22 +431,24 @@ async def import_products(pool, products_csv_path, link_cat_to_new, base_url, to
                 'category_id': new_cat_id,
                 'specifications': specs_by_code.get(code, {}),
                 'images': [],
+                'stock': {},
             }
 
+        # Each color becomes a variant holding that color's stock
+        stock_by_color = products[code]['stock']
+        stock_by_color[color_name] = stock_by_color.get(color_name, 0) + stock
+
         # Add image for this variant
         if photo:
             products[code]['images'].append({
                 'photo': photo,
                 'color': color_name,
-                'quantity': stock,
             })
 
     print(f"Unique products (by code): {len(products)}")
     print(f"Total images: {sum(len(p['images']) for p in products.values())}")
 
     # ── Bulk insert products ──
-    import json
-
     product_records = []
     category_records = []
     specs_count = 0
@@ -456,7 +459,7 @@ async def import_products(pool, products_csv_path, link_cat_to_new, base_url, to
             specs_count += 1
         product_records.append((
             prod['id'], prod['name'], prod['description'],
-            prod['price'], prod['discount'], 0,
+            prod['price'], prod['discount'],
             spec_json,
             prod['brand_id'], prod['warranty'], prod['enabled']
         ))
@@ -472,8 +475,8 @@ async def import_products(pool, products_csv_path, link_cat_to_new, base_url, to
             batch = product_records[i:i + BATCH_SIZE]
             try:
                 await conn.executemany(
-                    """INSERT INTO products (id, name, description, price, discount, quantity, specifications, brand_id, warranty, enabled)
-                       VALUES ($1, $2, $3, $4, $5, $6, $7::jsonb, $8, $9, $10)
+                    """INSERT INTO products (id, name, description, price, discount, specifications, brand_id, warranty, enabled)
+                       VALUES ($1, $2, $3, $4, $5, $6::jsonb, $7, $8, $9)
                        ON CONFLICT (id) DO UPDATE SET
                            name = EXCLUDED.name,
                            description = COALESCE(EXCLUDED.description, products.description),
@@ -507,6 +510,8 @@ async def import_products(pool, products_csv_path, link_cat_to_new, base_url, to
     print(f"Products with category: {len(category_records)}/{len(product_records)}")
     print(f"Products with specifications: {specs_count}")
 
+    variant_ids = await import_variants(pool, products)
+
     if skip_images:
         return
 
@@ -529,7 +534,7 @@ async def import_products(pool, products_csv_path, link_cat_to_new, base_url, to
                 tasks.append((
                     session, sem, base_url, headers, code,
                     img['photo'], images_dir, img['color'], is_primary,
-                    img['quantity']
+                    variant_ids.get((code, img['color']))
                 ))
 
         progress = {'done': 0, 'total': len(tasks)}
@@ -542,6 +547,60 @@ async def import_products(pool, products_csv_path, link_cat_to_new, base_url, to
     print(f"Uploaded {images_uploaded} product images")
 
 
+async def import_variants(pool, products):
+    """Stock lives on variants, one per color, held in the shipping warehouse.
+    Opening stock is only booked for variants that have none yet, so re-runs
+    don't add it twice."""
+    print("\n=== Importing variants and stock ===")
+
+    variant_ids = {}
+    stocked = 0
+
+    async with pool.acquire() as conn:
+        warehouse_id = await conn.fetchval(
+            "SELECT id FROM warehouses WHERE fulfills_shipping ORDER BY id LIMIT 1"
+        )
+        if warehouse_id is None:
+            print("  FAIL: no warehouse fulfills shipping, stock not imported")
+
+        for code, prod in products.items():
+            for idx, (color, stock) in enumerate(prod['stock'].items()):
+                attributes = json.dumps({'color': color} if color else {}, ensure_ascii=False)
+                try:
+                    variant_id = await conn.fetchval(
+                        """INSERT INTO product_variants (product_id, sku, attributes)
+                           VALUES ($1, $2, $3::jsonb)
+                           ON CONFLICT (product_id, attributes) DO UPDATE SET updated_at = NOW()
+                           RETURNING id""",
+                        code, f"{code}-{idx + 1}", attributes
+                    )
+                except Exception as e:
+                    print(f"  FAIL variant {code} {color}: {e}")
+                    continue
+                variant_ids[(code, color)] = variant_id
+
+                if warehouse_id is None or stock <= 0:
+                    continue
+                booked = await conn.fetchval(
+                    """WITH ws AS (
+                           INSERT INTO warehouse_stock (warehouse_id, variant_id, quantity)
+                           VALUES ($1, $2, $3)
+                           ON CONFLICT (warehouse_id, variant_id) DO NOTHING
+                           RETURNING warehouse_id, variant_id, quantity
+                       )
+                       INSERT INTO stock_movements (variant_id, warehouse_id, quantity, kind, reason)
+                       SELECT variant_id, warehouse_id, quantity, 'receipt', 'import opening balance'
+                       FROM ws
+                       RETURNING id""",
+                    warehouse_id, variant_id, stock
+                )
+                if booked is not None:
+                    stocked += 1
+
+    print(f"Imported {len(variant_ids)} variants, opening stock for {stocked}")
+    return variant_ids
+
+
 def parse_warranty(amount, gtype):
     amount = amount.strip()
     gtype = gtype.strip()
@@ -552,7 +611,7 @@ def parse_warranty(amount, gtype):
     return f"{amount} {unit}{suffix}"
 
 
-async def upload_product_image(session, sem, base_url, headers, product_id, photo_filename, images_dir, color, is_primary, quantity, progress):
+async def upload_product_image(session, sem, base_url, headers, product_id, photo_filename, images_dir, color, is_primary, variant_id, progress):
     async with sem:
         ext = photo_filename.rsplit('.', 1)[-1].lower() if '.' in photo_filename else 'jpg'
         content_type_map = {'jpg': 'image/jpeg', 'jpeg': 'image/jpeg', 'png': 'image/png', 'webp': 'image/webp'}
@@ -566,7 +625,7 @@ async def upload_product_image(session, sem, base_url, headers, product_id, phot
         with open(image_path, 'rb') as f:
             image_data = f.read()
 
-        payload = {"images": [{"content_type": content_type, "is_primary": is_primary, "color": color, "quantity": quantity}]}
+        payload = {"images": [{"content_type": content_type, "is_primary": is_primary, "color": color, "variant_id": variant_id}]}
         async with session.put(
             f"{base_url}/admin/products/{quote(product_id, safe='')}/images",
             headers=headers,
@@ -8,7 +8,10 @@ use axum::{
 use sqlx::PgPool;
 use tower_http::cors::CorsLayer;
 
-use crate::{config, config::AppConfig, database, error::Result, routes};
+use crate::{
+    config, config::AppConfig, database, error::Result, jobs, routes,
+    utils::extractors::IDEMPOTENCY_KEY_HEADER,
+};
 
 #[derive(Clone)]
 pub struct AppState {
@@ -18,10 +21,12 @@ pub struct AppState {
     pub assets_url: String,
     pub environment: config::Environment,
     pub ses_client: SesClient,
-    pub flitt_merchant_id: i32,
-    pub flitt_secret_key: String,
+    pub flitt: config::FlittConfig,
     pub frontend_url: String,
     pub backend_url: String,
+    pub reservation_ttl_minutes: i32,
+    pub low_stock: config::LowStockConfig,
+    pub trusted_proxies: usize,
 }
 
 pub async fn build(config: &AppConfig) -> Result<Router> {
@@ -36,8 +41,7 @@ pub async fn build(config: &AppConfig) -> Result<Router> {
         assets_url: config.s3.assets_url.clone(),
         environment: config.environment.clone(),
         ses_client,
-        flitt_merchant_id: config.flitt.merchant_id,
-        flitt_secret_key: config.flitt.secret_key.clone(),
+        flitt: config.flitt.clone(),
         frontend_url: config
             .cors
             .allowed_origins
@@ -45,7 +49,13 @@ pub async fn build(config: &AppConfig) -> Result<Router> {
             .cloned()
             .unwrap_or_default(),
         backend_url: config.flitt.backend_url.clone(),
+        reservation_ttl_minutes: config.reservations.ttl_minutes,
+        low_stock: config.low_stock.clone(),
+        trusted_proxies: config.server.trusted_proxies,
     };
+
+    jobs::spawn(&state, config);
+
     let allowed_origins: Vec<HeaderValue> = config
         .cors
         .allowed_origins
@@ -66,7 +76,11 @@ pub async fn build(config: &AppConfig) -> Result<Router> {
             Method::OPTIONS,
             Method::DELETE,
         ])
-        .allow_headers([http::header::CONTENT_TYPE, http::header::AUTHORIZATION])
+        .allow_headers([
+            http::header::CONTENT_TYPE,
+            http::header::AUTHORIZATION,
+            http::HeaderName::from_static(IDEMPOTENCY_KEY_HEADER),
+        ])
         .allow_origin(allowed_origins);
 
     let app = routes::create_router()
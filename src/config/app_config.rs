@@ -6,6 +6,39 @@ pub struct FlittConfig {
     pub merchant_id: i32,
     pub secret_key: String,
     pub backend_url: String,
+    pub api_url: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReservationConfig {
+    pub ttl_minutes: i32,
+    pub sweep_interval_secs: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReconciliationConfig {
+    pub poll_interval_secs: u64,
+    pub stale_after_minutes: i32,
+    pub lookback_hours: i32,
+}
+
+#[derive(Debug, Clone)]
+pub struct AbandonedCartConfig {
+    pub poll_interval_secs: u64,
+    pub idle_minutes: i32,
+    pub lookback_hours: i32,
+    pub max_emails_per_address: i64,
+    pub cap_window_days: i32,
+}
+
+/// Products at or below their threshold show up in the low-stock report and the
+/// daily operator digest.
+#[derive(Debug, Clone)]
+pub struct LowStockConfig {
+    pub default_threshold: i32,
+    pub velocity_days: i32,
+    /// Hour of the day (Tbilisi time) after which the digest goes out.
+    pub digest_hour: u32,
 }
 
 #[derive(Debug, Clone)]
@@ -16,6 +49,10 @@ pub struct AppConfig {
     pub s3: S3Config,
     pub environment: Environment,
     pub flitt: FlittConfig,
+    pub reservations: ReservationConfig,
+    pub reconciliation: ReconciliationConfig,
+    pub abandoned_carts: AbandonedCartConfig,
+    pub low_stock: LowStockConfig,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -48,6 +85,9 @@ pub struct ServerConfig {
     pub host: String,
     pub port: u16,
     pub max_body_size: usize,
+    /// Reverse proxies in front of the server, each appending a hop to
+    /// `X-Forwarded-For`. With none, the header is ignored.
+    pub trusted_proxies: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -80,6 +120,12 @@ impl AppConfig {
                     .map_err(|_| {
                         AppError::ConfigError("Invalid MAX_BODY_SIZE value".to_string())
                     })?,
+                trusted_proxies: env::var("TRUSTED_PROXIES")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .map_err(|_| {
+                        AppError::ConfigError("Invalid TRUSTED_PROXIES value".to_string())
+                    })?,
             },
             database: DatabaseConfig {
                 url: env::var("DB_URL")?,
@@ -113,6 +159,116 @@ impl AppConfig {
                     .map_err(|_| AppError::ConfigError("FLITT_SECRET_KEY not set".to_string()))?,
                 backend_url: env::var("BACKEND_URL")
                     .map_err(|_| AppError::ConfigError("BACKEND_URL not set".to_string()))?,
+                api_url: env::var("FLITT_API_URL")
+                    .unwrap_or_else(|_| "https://pay.flitt.com/api".to_string()),
+            },
+            reservations: ReservationConfig {
+                ttl_minutes: env::var("STOCK_RESERVATION_TTL_MINUTES")
+                    .unwrap_or_else(|_| "30".to_string())
+                    .parse()
+                    .map_err(|_| {
+                        AppError::ConfigError(
+                            "Invalid STOCK_RESERVATION_TTL_MINUTES value".to_string(),
+                        )
+                    })?,
+                sweep_interval_secs: env::var("STOCK_RESERVATION_SWEEP_INTERVAL_SECS")
+                    .unwrap_or_else(|_| "60".to_string())
+                    .parse()
+                    .map_err(|_| {
+                        AppError::ConfigError(
+                            "Invalid STOCK_RESERVATION_SWEEP_INTERVAL_SECS value".to_string(),
+                        )
+                    })?,
+            },
+            reconciliation: ReconciliationConfig {
+                poll_interval_secs: env::var("FLITT_RECONCILE_INTERVAL_SECS")
+                    .unwrap_or_else(|_| "300".to_string())
+                    .parse()
+                    .map_err(|_| {
+                        AppError::ConfigError(
+                            "Invalid FLITT_RECONCILE_INTERVAL_SECS value".to_string(),
+                        )
+                    })?,
+                stale_after_minutes: env::var("FLITT_RECONCILE_STALE_MINUTES")
+                    .unwrap_or_else(|_| "15".to_string())
+                    .parse()
+                    .map_err(|_| {
+                        AppError::ConfigError(
+                            "Invalid FLITT_RECONCILE_STALE_MINUTES value".to_string(),
+                        )
+                    })?,
+                lookback_hours: env::var("FLITT_RECONCILE_LOOKBACK_HOURS")
+                    .unwrap_or_else(|_| "48".to_string())
+                    .parse()
+                    .map_err(|_| {
+                        AppError::ConfigError(
+                            "Invalid FLITT_RECONCILE_LOOKBACK_HOURS value".to_string(),
+                        )
+                    })?,
+            },
+            abandoned_carts: AbandonedCartConfig {
+                poll_interval_secs: env::var("ABANDONED_CART_INTERVAL_SECS")
+                    .unwrap_or_else(|_| "900".to_string())
+                    .parse()
+                    .map_err(|_| {
+                        AppError::ConfigError(
+                            "Invalid ABANDONED_CART_INTERVAL_SECS value".to_string(),
+                        )
+                    })?,
+                idle_minutes: env::var("ABANDONED_CART_IDLE_MINUTES")
+                    .unwrap_or_else(|_| "60".to_string())
+                    .parse()
+                    .map_err(|_| {
+                        AppError::ConfigError(
+                            "Invalid ABANDONED_CART_IDLE_MINUTES value".to_string(),
+                        )
+                    })?,
+                lookback_hours: env::var("ABANDONED_CART_LOOKBACK_HOURS")
+                    .unwrap_or_else(|_| "72".to_string())
+                    .parse()
+                    .map_err(|_| {
+                        AppError::ConfigError(
+                            "Invalid ABANDONED_CART_LOOKBACK_HOURS value".to_string(),
+                        )
+                    })?,
+                max_emails_per_address: env::var("ABANDONED_CART_MAX_EMAILS")
+                    .unwrap_or_else(|_| "3".to_string())
+                    .parse()
+                    .map_err(|_| {
+                        AppError::ConfigError("Invalid ABANDONED_CART_MAX_EMAILS value".to_string())
+                    })?,
+                cap_window_days: env::var("ABANDONED_CART_CAP_WINDOW_DAYS")
+                    .unwrap_or_else(|_| "30".to_string())
+                    .parse()
+                    .map_err(|_| {
+                        AppError::ConfigError(
+                            "Invalid ABANDONED_CART_CAP_WINDOW_DAYS value".to_string(),
+                        )
+                    })?,
+            },
+            low_stock: LowStockConfig {
+                default_threshold: env::var("LOW_STOCK_DEFAULT_THRESHOLD")
+                    .unwrap_or_else(|_| "5".to_string())
+                    .parse()
+                    .map_err(|_| {
+                        AppError::ConfigError(
+                            "Invalid LOW_STOCK_DEFAULT_THRESHOLD value".to_string(),
+                        )
+                    })?,
+                velocity_days: env::var("LOW_STOCK_VELOCITY_DAYS")
+                    .unwrap_or_else(|_| "30".to_string())
+                    .parse()
+                    .map_err(|_| {
+                        AppError::ConfigError("Invalid LOW_STOCK_VELOCITY_DAYS value".to_string())
+                    })?,
+                digest_hour: env::var("LOW_STOCK_DIGEST_HOUR")
+                    .unwrap_or_else(|_| "9".to_string())
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|hour| *hour < 24)
+                    .ok_or_else(|| {
+                        AppError::ConfigError("Invalid LOW_STOCK_DIGEST_HOUR value".to_string())
+                    })?,
             },
             environment,
         })
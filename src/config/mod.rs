@@ -3,7 +3,8 @@ mod s3_config;
 mod ses_config;
 
 pub use app_config::{
-    AppConfig, CorsConfig, DatabaseConfig, Environment, FlittConfig, S3Config, ServerConfig,
+    AbandonedCartConfig, AppConfig, CorsConfig, DatabaseConfig, Environment, FlittConfig,
+    LowStockConfig, ReconciliationConfig, ReservationConfig, S3Config, ServerConfig,
 };
 pub use s3_config::*;
 pub use ses_config::*;
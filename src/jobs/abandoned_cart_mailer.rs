@@ -0,0 +1,78 @@
+use std::{collections::HashSet, time::Duration};
+
+use tokio::time::MissedTickBehavior;
+
+use crate::{
+    AppState, config::AbandonedCartConfig, error::Result, queries::cart_queries,
+    services::email_service,
+};
+
+const BATCH_SIZE: i64 = 100;
+
+pub fn spawn(state: AppState, config: AbandonedCartConfig) {
+    tokio::spawn(async move {
+        let mut ticker =
+            tokio::time::interval(Duration::from_secs(config.poll_interval_secs.max(1)));
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+
+            match send_reminders(&state, &config).await {
+                Ok(0) => {}
+                Ok(sent) => tracing::info!("sent {} abandoned cart reminders", sent),
+                Err(e) => tracing::error!("abandoned cart reminders failed: {e}"),
+            }
+        }
+    });
+}
+
+async fn send_reminders(state: &AppState, config: &AbandonedCartConfig) -> Result<usize> {
+    let checkouts = cart_queries::find_abandoned_checkouts(&state.db, config, BATCH_SIZE).await?;
+
+    // the cap is checked per run, so one address gets at most one email per batch
+    let mut emailed: HashSet<String> = HashSet::new();
+    let mut sent = 0;
+    for checkout in &checkouts {
+        if !emailed.insert(checkout.email.clone()) {
+            continue;
+        }
+        let Some(record) = cart_queries::record_abandoned_cart_email(&state.db, checkout).await?
+        else {
+            continue;
+        };
+
+        // frontend pages that POST the token to the matching /cart/restore and
+        // /cart/unsubscribe endpoints; the links themselves must not act, since
+        // mail scanners open them and a restore token works only once
+        let restore_url = format!(
+            "{}/cart/restore/{}",
+            state.frontend_url, record.restore_token
+        );
+        let unsubscribe_url = format!(
+            "{}/cart/unsubscribe/{}",
+            state.frontend_url, record.unsubscribe_token
+        );
+
+        if let Err(e) = email_service::send_abandoned_cart_email(
+            &state.ses_client,
+            &checkout.email,
+            &checkout.cart,
+            &restore_url,
+            &unsubscribe_url,
+        )
+        .await
+        {
+            tracing::error!(
+                "Failed to send abandoned cart email for session {}: {:?}",
+                checkout.session_id,
+                e
+            );
+            cart_queries::delete_abandoned_cart_email(&state.db, record.id).await?;
+            continue;
+        }
+        sent += 1;
+    }
+
+    Ok(sent)
+}
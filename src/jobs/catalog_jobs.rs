@@ -0,0 +1,30 @@
+use std::time::Duration;
+
+use tokio::time::MissedTickBehavior;
+
+use crate::{AppState, error::Result, queries::catalog_queries, services::catalog_service};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+pub fn spawn(state: AppState) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(POLL_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+
+            if let Err(e) = run_pending(&state).await {
+                tracing::error!("catalog jobs failed: {e}");
+            }
+        }
+    });
+}
+
+async fn run_pending(state: &AppState) -> Result<()> {
+    while let Some(job) = catalog_queries::claim_next_job(&state.db).await? {
+        tracing::info!("running catalog {:?} job {}", job.kind, job.id);
+        catalog_service::run_job(&state.db, &job).await?;
+    }
+    Ok(())
+}
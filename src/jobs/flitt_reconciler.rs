@@ -0,0 +1,254 @@
+use std::time::Duration;
+
+use chrono::{Days, Utc};
+use tokio::time::MissedTickBehavior;
+
+use crate::{
+    AppState,
+    config::ReconciliationConfig,
+    error::Result,
+    models::{
+        Order, OrderStatus, ReconciliationIssue, ReconciliationMismatch, RefundStatus,
+        UnsettledRefund,
+    },
+    queries::{reconciliation_queries, refund_queries},
+    routes::orders,
+    services::flitt_service::{self, FlittOrderStatus},
+};
+
+const BATCH_SIZE: i64 = 50;
+const REPORT_CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+
+pub fn spawn(state: AppState, config: ReconciliationConfig) {
+    let poll_state = state.clone();
+    tokio::spawn(async move {
+        let mut ticker =
+            tokio::time::interval(Duration::from_secs(config.poll_interval_secs.max(1)));
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+
+            match poll_unsettled_orders(&poll_state, &config).await {
+                Ok(0) => {}
+                Ok(settled) => tracing::info!("reconciled {} orders with Flitt", settled),
+                Err(e) => tracing::error!("Flitt reconciliation failed: {e}"),
+            }
+
+            match poll_unsettled_refunds(&poll_state, &config).await {
+                Ok(0) => {}
+                Ok(settled) => tracing::info!("settled {} refunds with Flitt", settled),
+                Err(e) => tracing::error!("Flitt refund reconciliation failed: {e}"),
+            }
+        }
+    });
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(REPORT_CHECK_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+
+            if let Err(e) = build_daily_report(&state).await {
+                tracing::error!("Flitt reconciliation report failed: {e}");
+            }
+        }
+    });
+}
+
+/// Asks Flitt about card orders whose callback never arrived and applies any
+/// final status through the same path as the callback.
+async fn poll_unsettled_orders(state: &AppState, config: &ReconciliationConfig) -> Result<usize> {
+    let orders = reconciliation_queries::find_unsettled_card_orders(
+        &state.db,
+        config.stale_after_minutes,
+        config.lookback_hours,
+        BATCH_SIZE,
+    )
+    .await?;
+
+    let mut settled = 0;
+    for order in &orders {
+        reconciliation_queries::mark_payment_checked(&state.db, order.id).await?;
+
+        let flitt = match flitt_service::get_order_status(&state.flitt, &order.order_id).await {
+            Ok(flitt) => flitt,
+            Err(e) => {
+                tracing::warn!("Flitt status lookup for {} failed: {e}", order.order_id);
+                continue;
+            }
+        };
+
+        let status = match OrderStatus::from_flitt(&flitt.order_status) {
+            Some(
+                status @ (OrderStatus::Approved
+                | OrderStatus::Declined
+                | OrderStatus::Expired
+                | OrderStatus::Reversed),
+            ) => status,
+            // still in progress on Flitt's side, check again later
+            _ => continue,
+        };
+
+        let applied = match orders::apply_flitt_status(
+            state,
+            &order.order_id,
+            status,
+            flitt.payment_id,
+        )
+        .await
+        {
+            Ok(applied) => applied,
+            Err(e) => {
+                tracing::error!("failed to settle order {}: {e}", order.order_id);
+                continue;
+            }
+        };
+
+        if applied.is_some() {
+            tracing::info!(
+                "Flitt reconciliation: order {} {} -> {}",
+                order.order_id,
+                order.status.as_str(),
+                status.as_str()
+            );
+            settled += 1;
+        }
+    }
+
+    Ok(settled)
+}
+
+/// Finalises refunds Flitt hasn't given a final answer for. One is approved
+/// once the order's refunded total on Flitt's side covers it; otherwise a
+/// `pending` one never reached Flitt and failed, and a `processing` one is
+/// declined when Flitt reports the payment declined or never settles it.
+async fn poll_unsettled_refunds(state: &AppState, config: &ReconciliationConfig) -> Result<usize> {
+    let refunds = refund_queries::find_unsettled_refunds(
+        &state.db,
+        config.stale_after_minutes,
+        config.lookback_hours,
+        BATCH_SIZE,
+    )
+    .await?;
+
+    let mut settled = 0;
+    for refund in &refunds {
+        refund_queries::mark_refund_checked(&state.db, refund.id).await?;
+
+        let flitt = match flitt_service::get_order_status(&state.flitt, &refund.order_id).await {
+            Ok(flitt) => flitt,
+            Err(e) => {
+                tracing::warn!("Flitt status lookup for {} failed: {e}", refund.order_id);
+                continue;
+            }
+        };
+
+        let Some(status) = settled_refund_status(refund, &flitt) else {
+            continue;
+        };
+
+        if let Err(e) = refund_queries::finish_refund(&state.db, refund.id, status).await {
+            tracing::error!("failed to settle refund {}: {e}", refund.id);
+            continue;
+        }
+        tracing::info!(
+            "Flitt reconciliation: refund {} of order {} -> {:?}",
+            refund.id,
+            refund.order_id,
+            status
+        );
+        settled += 1;
+    }
+
+    Ok(settled)
+}
+
+fn settled_refund_status(
+    refund: &UnsettledRefund,
+    flitt: &FlittOrderStatus,
+) -> Option<RefundStatus> {
+    if flitt
+        .reversal_amount
+        .is_some_and(|reversed| i64::from(reversed) >= refund.settles_at)
+    {
+        return Some(RefundStatus::Approved);
+    }
+    if refund.status == RefundStatus::Pending {
+        return Some(RefundStatus::Failed);
+    }
+    match OrderStatus::from_flitt(&flitt.order_status) {
+        Some(OrderStatus::Declined | OrderStatus::Expired) => Some(RefundStatus::Declined),
+        _ if refund.overdue => Some(RefundStatus::Declined),
+        _ => None,
+    }
+}
+
+/// Compares yesterday's card orders with Flitt once per day.
+async fn build_daily_report(state: &AppState) -> Result<()> {
+    let Some(date) = Utc::now().date_naive().checked_sub_days(Days::new(1)) else {
+        return Ok(());
+    };
+    if reconciliation_queries::report_exists(&state.db, date).await? {
+        return Ok(());
+    }
+
+    let orders = reconciliation_queries::find_card_orders_created_on(&state.db, date).await?;
+
+    let mut mismatches = Vec::new();
+    for order in &orders {
+        let flitt = flitt_service::get_order_status(&state.flitt, &order.order_id).await;
+        if let Some(mismatch) = compare(order, flitt.ok()) {
+            mismatches.push(mismatch);
+        }
+    }
+
+    if let Some(report) =
+        reconciliation_queries::insert_report(&state.db, date, orders.len() as i32, &mismatches)
+            .await?
+    {
+        tracing::info!(
+            "Flitt reconciliation report for {}: {} orders, {} mismatches",
+            report.report_date,
+            report.orders_checked,
+            report.mismatch_count
+        );
+    }
+
+    Ok(())
+}
+
+fn compare(order: &Order, flitt: Option<FlittOrderStatus>) -> Option<ReconciliationMismatch> {
+    let mismatch = |issue, flitt: Option<&FlittOrderStatus>| ReconciliationMismatch {
+        order_id: order.order_id.clone(),
+        issue,
+        status: order.status,
+        flitt_status: flitt.map(|f| f.order_status.clone()),
+        amount: order.amount,
+        flitt_amount: flitt.and_then(|f| f.amount),
+    };
+
+    let Some(flitt) = flitt else {
+        return Some(mismatch(ReconciliationIssue::Lookup, None));
+    };
+
+    let flitt_paid = flitt.order_status == "approved";
+    let unpaid = matches!(
+        order.status,
+        OrderStatus::Pending
+            | OrderStatus::Created
+            | OrderStatus::Processing
+            | OrderStatus::Declined
+            | OrderStatus::Expired
+    );
+
+    if (flitt_paid && unpaid) || (!flitt_paid && order.status.holds_stock()) {
+        return Some(mismatch(ReconciliationIssue::Status, Some(&flitt)));
+    }
+    if flitt_paid && flitt.amount.is_some_and(|amount| amount != order.amount) {
+        return Some(mismatch(ReconciliationIssue::Amount, Some(&flitt)));
+    }
+
+    None
+}
@@ -0,0 +1,25 @@
+use std::time::Duration;
+
+use sqlx::PgPool;
+use tokio::time::MissedTickBehavior;
+
+use crate::queries::idempotency_queries;
+
+/// Clients retry within minutes; a day leaves plenty of margin.
+const KEY_TTL_HOURS: i32 = 24;
+const SWEEP_INTERVAL: Duration = Duration::from_secs(600);
+
+pub fn spawn(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(SWEEP_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+
+            if let Err(e) = idempotency_queries::delete_expired(&pool, KEY_TTL_HOURS).await {
+                tracing::error!("idempotency key sweep failed: {e}");
+            }
+        }
+    });
+}
@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use chrono::{FixedOffset, NaiveDate, Timelike, Utc};
+use tokio::time::MissedTickBehavior;
+
+use crate::{
+    AppState,
+    error::Result,
+    queries::{admin_queries, inventory_queries},
+    services::email_service,
+};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+pub fn spawn(state: AppState) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        // saves a report query on every tick once today's digest is settled
+        let mut done_for: Option<NaiveDate> = None;
+        loop {
+            ticker.tick().await;
+
+            let now = Utc::now().with_timezone(&FixedOffset::east_opt(4 * 3600).unwrap());
+            let today = now.date_naive();
+            if done_for == Some(today) || now.hour() < state.low_stock.digest_hour {
+                continue;
+            }
+
+            match send_digest(&state, today).await {
+                Ok(None) => {}
+                Ok(Some(sent)) => {
+                    done_for = Some(today);
+                    if sent > 0 {
+                        tracing::info!("sent low-stock digest to {} operators", sent);
+                    }
+                }
+                Err(e) => tracing::error!("low-stock digest failed: {e}"),
+            }
+        }
+    });
+}
+
+/// `None` means there is nothing to report yet and the job should look again
+/// later today.
+async fn send_digest(state: &AppState, today: NaiveDate) -> Result<Option<usize>> {
+    let config = &state.low_stock;
+    let days = config.velocity_days.max(1);
+    let variants =
+        inventory_queries::get_low_stock(&state.db, config.default_threshold, days).await?;
+    if variants.is_empty() {
+        return Ok(None);
+    }
+    let operators = admin_queries::get_operator_emails(&state.db).await?;
+    if operators.is_empty() {
+        return Ok(None);
+    }
+
+    // another instance may have sent it already
+    if !inventory_queries::claim_low_stock_digest(&state.db, today, variants.len() as i32).await? {
+        return Ok(Some(0));
+    }
+
+    let sent = email_service::send_low_stock_digest(
+        &state.ses_client,
+        &operators,
+        &variants,
+        days,
+        &state.frontend_url,
+    )
+    .await?;
+    if sent == 0 {
+        inventory_queries::release_low_stock_digest(&state.db, today).await?;
+        return Ok(None);
+    }
+
+    Ok(Some(sent))
+}
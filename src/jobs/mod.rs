@@ -0,0 +1,32 @@
+mod abandoned_cart_mailer;
+mod catalog_jobs;
+mod flitt_reconciler;
+mod idempotency_sweeper;
+mod low_stock_digest;
+mod price_scheduler;
+mod product_alert_mailer;
+mod recommendation_builder;
+mod reservation_sweeper;
+mod view_rollup;
+
+use std::time::Duration;
+
+use crate::{AppState, config::AppConfig};
+
+/// Starts the periodic background jobs. Each one runs on its own task for the
+/// lifetime of the process.
+pub fn spawn(state: &AppState, config: &AppConfig) {
+    reservation_sweeper::spawn(
+        state.db.clone(),
+        Duration::from_secs(config.reservations.sweep_interval_secs.max(1)),
+    );
+    flitt_reconciler::spawn(state.clone(), config.reconciliation.clone());
+    idempotency_sweeper::spawn(state.db.clone());
+    product_alert_mailer::spawn(state.clone());
+    abandoned_cart_mailer::spawn(state.clone(), config.abandoned_carts.clone());
+    low_stock_digest::spawn(state.clone());
+    catalog_jobs::spawn(state.clone());
+    price_scheduler::spawn(state.clone());
+    recommendation_builder::spawn(state.db.clone());
+    view_rollup::spawn(state.db.clone());
+}
@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use tokio::time::MissedTickBehavior;
+
+use crate::{
+    AppState,
+    error::{AppError, Result},
+    models::{PriceChangeOrigin, ScheduledPriceChange},
+    queries::{admin_queries, price_queries, products_queries},
+    services::{product_alert_service, product_service},
+};
+
+const BATCH_SIZE: i64 = 100;
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+pub fn spawn(state: AppState) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+
+            match apply_due(&state).await {
+                Ok(0) => {}
+                Ok(applied) => tracing::info!("applied {} scheduled price changes", applied),
+                Err(e) => tracing::error!("scheduled price changes failed: {e}"),
+            }
+        }
+    });
+}
+
+async fn apply_due(state: &AppState) -> Result<usize> {
+    let schedules = price_queries::claim_due_schedules(&state.db, BATCH_SIZE).await?;
+
+    let mut applied = 0;
+    for schedule in &schedules {
+        match apply(state, schedule).await {
+            Ok(()) => applied += 1,
+            Err(e) => {
+                tracing::error!("failed to apply price schedule {}: {e}", schedule.id);
+                price_queries::fail_schedule(&state.db, schedule.id, &e.to_string()).await?;
+            }
+        }
+    }
+
+    Ok(applied)
+}
+
+async fn apply(state: &AppState, schedule: &ScheduledPriceChange) -> Result<()> {
+    let existing = products_queries::find_by_id(&state.db, &schedule.product_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("პროდუქტი ვერ მოიძებნა".to_string()))?;
+
+    let mut request = product_service::price_change_request(
+        schedule.price,
+        schedule.discount,
+        schedule.discounted_price,
+    );
+    product_service::prepare_update(&state.db, &existing, &mut request).await?;
+
+    let product = admin_queries::update_product(
+        &state.db,
+        &existing.id,
+        &request,
+        None,
+        PriceChangeOrigin::schedule(schedule),
+    )
+    .await?;
+
+    if let Err(e) =
+        product_alert_service::queue_price_drop_alerts(&state.db, &existing, &product).await
+    {
+        tracing::error!("failed to queue price-drop alerts for {}: {e}", product.id);
+    }
+
+    Ok(())
+}
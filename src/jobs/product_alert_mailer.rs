@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use tokio::time::MissedTickBehavior;
+
+use crate::{AppState, error::Result, queries::product_alert_queries, services::email_service};
+
+const BATCH_SIZE: i64 = 100;
+const SEND_INTERVAL: Duration = Duration::from_secs(60);
+
+pub fn spawn(state: AppState) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(SEND_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+
+            match send_pending(&state).await {
+                Ok(0) => {}
+                Ok(sent) => tracing::info!("sent {} product alerts", sent),
+                Err(e) => tracing::error!("product alert delivery failed: {e}"),
+            }
+        }
+    });
+}
+
+async fn send_pending(state: &AppState) -> Result<usize> {
+    let alerts = product_alert_queries::find_pending(&state.db, BATCH_SIZE).await?;
+
+    let mut sent = 0;
+    for alert in &alerts {
+        let product_url = format!("{}/products/{}", state.frontend_url, alert.product_id);
+        let unsubscribe_url = format!(
+            "{}/product-alerts/unsubscribe/{}",
+            state.frontend_url, alert.unsubscribe_token
+        );
+
+        match email_service::send_product_alert_email(
+            &state.ses_client,
+            alert,
+            &product_url,
+            &unsubscribe_url,
+        )
+        .await
+        {
+            Ok(()) => {
+                product_alert_queries::mark_sent(&state.db, alert).await?;
+                sent += 1;
+            }
+            Err(e) => {
+                tracing::error!(
+                    "Failed to send product alert {} to {}: {:?}",
+                    alert.delivery_id,
+                    alert.email,
+                    e
+                );
+                product_alert_queries::mark_failed(&state.db, alert.delivery_id).await?;
+            }
+        }
+    }
+
+    Ok(sent)
+}
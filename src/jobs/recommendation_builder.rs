@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+use sqlx::PgPool;
+use tokio::time::MissedTickBehavior;
+
+use crate::queries::recommendation_queries;
+
+const REBUILD_INTERVAL: Duration = Duration::from_secs(3600);
+const VIEW_WINDOW_DAYS: i32 = 90;
+const PURCHASE_WINDOW_DAYS: i32 = 365;
+/// A shared order says far more than a shared browsing session.
+const PURCHASE_WEIGHT: f64 = 5.0;
+const PER_PRODUCT: i64 = 24;
+
+pub fn spawn(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(REBUILD_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+
+            match recommendation_queries::rebuild(
+                &pool,
+                VIEW_WINDOW_DAYS,
+                PURCHASE_WINDOW_DAYS,
+                PURCHASE_WEIGHT,
+                PER_PRODUCT,
+            )
+            .await
+            {
+                Ok(pairs) => tracing::info!("rebuilt product recommendations: {} pairs", pairs),
+                Err(e) => tracing::error!("product recommendation rebuild failed: {e}"),
+            }
+        }
+    });
+}
@@ -0,0 +1,25 @@
+use std::time::Duration;
+
+use sqlx::PgPool;
+use tokio::time::MissedTickBehavior;
+
+use crate::queries::reservation_queries;
+
+pub fn spawn(pool: PgPool, every: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(every);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+
+            match reservation_queries::expire_abandoned_orders(&pool).await {
+                Ok(orders) if !orders.is_empty() => {
+                    tracing::info!("expired {} abandoned orders", orders.len());
+                }
+                Ok(_) => {}
+                Err(e) => tracing::error!("stock reservation sweep failed: {e}"),
+            }
+        }
+    });
+}
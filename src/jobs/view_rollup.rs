@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+use chrono::{Days, Utc};
+use sqlx::PgPool;
+use tokio::time::MissedTickBehavior;
+
+use crate::queries::products_queries;
+
+/// How stale today's numbers in the admin analytics can get.
+const ROLLUP_INTERVAL: Duration = Duration::from_secs(600);
+
+pub fn spawn(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(ROLLUP_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+
+            // yesterday too, so views landing around midnight are not lost
+            let from_day = Utc::now().date_naive() - Days::new(1);
+            if let Err(e) = products_queries::refresh_view_rollup(&pool, from_day).await {
+                tracing::error!("product view rollup failed: {e}");
+            }
+        }
+    });
+}
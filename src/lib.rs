@@ -2,6 +2,7 @@ pub mod app;
 pub mod config;
 pub mod database;
 pub mod error;
+pub mod jobs;
 pub mod middleware;
 pub mod models;
 pub mod queries;
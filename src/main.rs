@@ -1,6 +1,8 @@
 #[global_allocator]
 static ALLOC: jemallocator::Jemalloc = jemallocator::Jemalloc;
 
+use std::net::SocketAddr;
+
 use tene_back::{app, config::AppConfig};
 use tracing::Level;
 
@@ -40,9 +42,12 @@ async fn main() {
         }
     };
 
-    if let Err(e) = axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await
+    if let Err(e) = axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await
     {
         tracing::error!("Server error: {}", e);
         std::process::exit(1);
@@ -16,7 +16,7 @@ where
     Deserialize::deserialize(deserializer).map(Some)
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct ProductRequest {
     pub id: Option<String>,
     pub name: Option<String>,
@@ -31,6 +31,9 @@ pub struct ProductRequest {
     pub cable_type_id: Option<Option<i32>>,
     pub warranty: Option<String>,
     pub enabled: Option<bool>,
+    pub coins_eligible: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_double_option")]
+    pub low_stock_threshold: Option<Option<i32>>,
     pub videos: Option<Vec<String>>,
     pub seo: Option<ProductSeoRequest>,
 }
@@ -40,7 +43,7 @@ pub struct ImageUploadRequest {
     pub color: Option<String>,
     pub is_primary: bool,
     pub content_type: String,
-    pub quantity: Option<i32>,
+    pub variant_id: Option<i32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -64,7 +67,7 @@ pub struct ProductImageUrlResponse {
 pub struct ImageMetadataUpdate {
     pub color: Option<String>,
     pub is_primary: Option<bool>,
-    pub quantity: Option<i32>,
+    pub variant_id: Option<i32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -161,7 +164,8 @@ pub struct CableTypeWithVariants {
 
 #[derive(Debug, Deserialize)]
 pub struct OrderStatusUpdate {
-    pub status: String,
+    pub status: crate::models::OrderStatus,
+    pub comment: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -195,6 +199,13 @@ pub struct MostViewedProduct {
     pub views: i64,
 }
 
+#[derive(Debug, Serialize, FromRow)]
+pub struct MostFavoritedProduct {
+    pub product_id: String,
+    pub product_name: String,
+    pub favorites: i64,
+}
+
 #[derive(Debug, Serialize, FromRow)]
 pub struct TrendingProduct {
     pub product_id: String,
@@ -234,6 +245,17 @@ pub struct ConversionRate {
     pub conversion_pct: Decimal,
 }
 
+#[derive(Debug, Serialize, FromRow)]
+pub struct CampaignResult {
+    pub campaign_id: i32,
+    pub campaign_name: String,
+    pub orders: i64,
+    pub units_sold: i64,
+    pub revenue: Decimal,
+    /// Taken off the products' own prices.
+    pub discount_given: Decimal,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum AnalyticsPeriod {
@@ -253,11 +275,13 @@ pub struct AnalyticsQuery {
 #[derive(Debug, Serialize)]
 pub struct AnalyticsResponse {
     pub most_viewed: Vec<MostViewedProduct>,
+    pub most_favorited: Vec<MostFavoritedProduct>,
     pub trending_this_week: Vec<TrendingProduct>,
     pub unique_viewers: Vec<UniqueViewersProduct>,
     pub views_by_hour: Vec<ViewsByHour>,
     pub high_views_low_sales: Vec<HighViewsLowSales>,
     pub conversion_rates: Vec<ConversionRate>,
+    pub campaign_results: Vec<CampaignResult>,
 }
 
 #[derive(Debug, Deserialize)]
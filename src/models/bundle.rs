@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// A component of a bundle, with the stock it currently has.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct BundleComponent {
+    #[serde(skip)]
+    pub bundle_id: String,
+    pub variant_id: i32,
+    pub product_id: String,
+    pub product_name: String,
+    pub sku: String,
+    pub color: Option<String>,
+    /// Units of this variant in one bundle.
+    pub quantity: i32,
+    pub stock: i32,
+}
+
+/// What one unit of a bundle line takes from stock. Stored on the order line
+/// so later changes to the bundle don't affect orders already placed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleItem {
+    pub variant_id: i32,
+    pub product_id: String,
+    pub color: Option<String>,
+    pub quantity: i32,
+}
+
+impl From<&BundleComponent> for BundleItem {
+    fn from(component: &BundleComponent) -> Self {
+        BundleItem {
+            variant_id: component.variant_id,
+            product_id: component.product_id.clone(),
+            color: component.color.clone(),
+            quantity: component.quantity,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BundleComponentRequest {
+    pub variant_id: i32,
+    pub quantity: i32,
+}
+
+/// Replaces the bundle's components. Setting any removes the product's own
+/// variants, which must be out of stock; an empty list stops it being a bundle.
+#[derive(Debug, Deserialize)]
+pub struct SetBundleRequest {
+    pub components: Vec<BundleComponentRequest>,
+}
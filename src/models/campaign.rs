@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// A time-boxed percentage off everything in its categories and brands. When
+/// campaigns overlap, the highest `priority` one that undercuts the product's
+/// own price prices it.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Campaign {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub discount_percent: Decimal,
+    pub category_ids: Vec<i32>,
+    pub brand_ids: Vec<i32>,
+    pub priority: i32,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CampaignRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub discount_percent: Decimal,
+    #[serde(default)]
+    pub category_ids: Vec<i32>,
+    #[serde(default)]
+    pub brand_ids: Vec<i32>,
+    pub priority: Option<i32>,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CampaignState {
+    Upcoming,
+    Active,
+    Ended,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CampaignQuery {
+    pub search: Option<String>,
+    pub state: Option<CampaignState>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CampaignSearchResponse {
+    pub campaigns: Vec<Campaign>,
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
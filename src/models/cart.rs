@@ -0,0 +1,95 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, types::Json};
+
+use uuid::Uuid;
+
+use crate::models::{CableConfig, CartItem, CartSnapshotItem};
+
+#[derive(Debug, Clone, FromRow)]
+pub struct CartItemRow {
+    pub id: i32,
+    pub product_id: String,
+    pub variant_id: Option<i32>,
+    pub color: Option<String>,
+    pub cable_watts: Option<i32>,
+    pub cable_length_cm: Option<i32>,
+    pub quantity: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl CartItemRow {
+    pub fn to_cart_item(&self) -> CartItem {
+        CartItem {
+            product_id: self.product_id.clone(),
+            variant_id: self.variant_id,
+            color: self.color.clone(),
+            quantity: self.quantity,
+            cable_config: self.cable_config(),
+        }
+    }
+
+    pub fn cable_config(&self) -> Option<CableConfig> {
+        match (self.cable_watts, self.cable_length_cm) {
+            (Some(watts), Some(length_cm)) => Some(CableConfig { watts, length_cm }),
+            _ => None,
+        }
+    }
+}
+
+/// A cart line priced against the current catalog. Lines that can no longer
+/// be bought keep their place in the cart with `error` set and no price.
+#[derive(Debug, Serialize)]
+pub struct CartLine {
+    pub id: i32,
+    pub product_id: String,
+    pub variant_id: Option<i32>,
+    pub color: Option<String>,
+    pub quantity: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cable_config: Option<CableConfig>,
+    pub product_name: Option<String>,
+    pub image: Option<serde_json::Value>,
+    pub price: Option<Decimal>,
+    pub line_total: Option<Decimal>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CartResponse {
+    pub items: Vec<CartLine>,
+    pub subtotal: Decimal,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateCartItemRequest {
+    pub quantity: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MergeCartRequest {
+    pub items: Vec<CartItem>,
+}
+
+/// A checkout session that collected an email but never turned into a purchase.
+#[derive(Debug, FromRow)]
+pub struct AbandonedCheckout {
+    pub session_id: Uuid,
+    pub email: String,
+    pub cart: Json<Vec<CartSnapshotItem>>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct AbandonedCartEmail {
+    pub id: i32,
+    pub restore_token: Uuid,
+    pub unsubscribe_token: Uuid,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RestoredCartResponse {
+    pub email: String,
+    pub items: Vec<CartSnapshotItem>,
+}
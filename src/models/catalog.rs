@@ -0,0 +1,113 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, types::Json};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum CatalogJobKind {
+    Import,
+    Export,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum CatalogFileFormat {
+    Xlsx,
+    Csv,
+}
+
+impl CatalogFileFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            CatalogFileFormat::Xlsx => {
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+            }
+            CatalogFileFormat::Csv => "text/csv; charset=utf-8",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            CatalogFileFormat::Xlsx => "xlsx",
+            CatalogFileFormat::Csv => "csv",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum CatalogJobStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogRowError {
+    /// Spreadsheet row number; the header is row 1.
+    pub row: usize,
+    pub product_id: Option<String>,
+    pub message: String,
+}
+
+/// For dry runs `created` and `updated` count what the import would do.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CatalogReport {
+    pub rows: usize,
+    pub created: usize,
+    pub updated: usize,
+    pub brands_created: Vec<String>,
+    pub categories_created: Vec<String>,
+    pub errors: Vec<CatalogRowError>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct CatalogJob {
+    pub id: i32,
+    pub kind: CatalogJobKind,
+    pub format: CatalogFileFormat,
+    pub status: CatalogJobStatus,
+    pub dry_run: bool,
+    pub file_name: Option<String>,
+    pub report: Option<Json<CatalogReport>>,
+    pub error: Option<String>,
+    pub has_output: bool,
+    pub created_by_user_id: i32,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// The format is detected from the upload when it isn't given. Imports are
+/// dry runs unless `dry_run=false` is passed.
+#[derive(Debug, Deserialize)]
+pub struct CatalogImportQuery {
+    pub format: Option<CatalogFileFormat>,
+    pub dry_run: Option<bool>,
+    pub file_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CatalogExportQuery {
+    pub format: Option<CatalogFileFormat>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CatalogJobQuery {
+    pub kind: Option<CatalogJobKind>,
+    pub status: Option<CatalogJobStatus>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CatalogJobSearchResponse {
+    pub jobs: Vec<CatalogJob>,
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
@@ -0,0 +1,44 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum CoinReason {
+    Earned,
+    Redeemed,
+    Returned,
+    Adjustment,
+    Clawback,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct CoinLedgerEntry {
+    pub id: i64,
+    pub user_id: i32,
+    pub amount: i32,
+    pub reason: CoinReason,
+    pub order_id: Option<i32>,
+    pub created_by_user_id: Option<i32>,
+    pub comment: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CoinBalanceResponse {
+    pub balance: i64,
+    pub entries: Vec<CoinLedgerEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CoinLedgerQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CoinAdjustmentRequest {
+    pub amount: i32,
+    pub comment: Option<String>,
+}
@@ -0,0 +1,87 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum CouponType {
+    /// `value` percent off the eligible items.
+    Percentage,
+    /// `value` GEL off the eligible items.
+    FixedAmount,
+    FreeDelivery,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Coupon {
+    pub id: i32,
+    pub code: String,
+    pub description: Option<String>,
+    pub discount_type: CouponType,
+    pub value: Decimal,
+    pub min_subtotal: Option<Decimal>,
+    pub category_ids: Vec<i32>,
+    pub brand_ids: Vec<i32>,
+    pub usage_limit: Option<i32>,
+    pub per_user_limit: Option<i32>,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct CouponWithUsage {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub coupon: Coupon,
+    pub times_used: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CouponRequest {
+    pub code: String,
+    pub description: Option<String>,
+    pub discount_type: CouponType,
+    #[serde(default)]
+    pub value: Decimal,
+    pub min_subtotal: Option<Decimal>,
+    #[serde(default)]
+    pub category_ids: Vec<i32>,
+    #[serde(default)]
+    pub brand_ids: Vec<i32>,
+    pub usage_limit: Option<i32>,
+    pub per_user_limit: Option<i32>,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CouponQuery {
+    pub search: Option<String>,
+    pub enabled: Option<bool>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CouponSearchResponse {
+    pub coupons: Vec<CouponWithUsage>,
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+/// A coupon that passed validation for a particular checkout.
+#[derive(Debug, Clone)]
+pub struct AppliedCoupon {
+    pub coupon_id: i32,
+    pub code: String,
+    pub discount_type: CouponType,
+    /// In tetri.
+    pub discount: i32,
+}
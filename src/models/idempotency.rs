@@ -0,0 +1,15 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum IdempotencyScope {
+    Checkout,
+    AdminOrder,
+}
+
+pub enum IdempotencyClaim {
+    /// First request with this key; the caller should do the work.
+    Acquired,
+    /// The key already finished with this HTTP status and response body.
+    Completed(u16, serde_json::Value),
+    /// Another request with the same key is still running.
+    InProgress,
+}
@@ -0,0 +1,168 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum WarehouseKind {
+    Warehouse,
+    Store,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Warehouse {
+    pub id: i32,
+    pub code: String,
+    pub name: String,
+    pub kind: WarehouseKind,
+    pub address: Option<String>,
+    pub fulfills_shipping: bool,
+    pub fulfills_pickup: bool,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateWarehouseRequest {
+    pub code: String,
+    pub name: String,
+    pub kind: WarehouseKind,
+    pub address: Option<String>,
+    #[serde(default)]
+    pub fulfills_shipping: bool,
+    #[serde(default)]
+    pub fulfills_pickup: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateWarehouseRequest {
+    pub name: Option<String>,
+    pub kind: Option<WarehouseKind>,
+    pub address: Option<String>,
+    pub fulfills_shipping: Option<bool>,
+    pub fulfills_pickup: Option<bool>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum StockMovementKind {
+    Receipt,
+    Sale,
+    Return,
+    Adjustment,
+    Transfer,
+}
+
+/// One entry of the stock ledger. `quantity` is signed: positive when units
+/// arrive at the location, negative when they leave it.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct StockMovement {
+    pub id: i64,
+    pub variant_id: i32,
+    pub warehouse_id: i32,
+    pub quantity: i32,
+    pub kind: StockMovementKind,
+    pub reason: Option<String>,
+    pub order_id: Option<i32>,
+    pub transfer_id: Option<Uuid>,
+    pub created_by_user_id: Option<i32>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct WarehouseStockLine {
+    pub variant_id: i32,
+    pub sku: String,
+    pub product_id: String,
+    pub product_name: String,
+    pub color: Option<String>,
+    pub quantity: i32,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StockReceiptRequest {
+    pub warehouse_id: i32,
+    pub variant_id: i32,
+    pub quantity: i32,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StockTransferRequest {
+    pub variant_id: i32,
+    pub from_warehouse_id: i32,
+    pub to_warehouse_id: i32,
+    pub quantity: i32,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StockCount {
+    pub variant_id: i32,
+    pub quantity: i32,
+}
+
+/// Physical count for a location. Every listed variant is set to its counted
+/// quantity; variants that aren't listed are left alone.
+#[derive(Debug, Deserialize)]
+pub struct StockTakeRequest {
+    pub counts: Vec<StockCount>,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StockMovementQuery {
+    pub warehouse_id: Option<i32>,
+    pub variant_id: Option<i32>,
+    pub product_id: Option<String>,
+    pub order_id: Option<i32>,
+    pub kind: Option<StockMovementKind>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StockMovementSearchResponse {
+    pub movements: Vec<StockMovement>,
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+/// A variant at or below its product's low-stock threshold, with its sales
+/// over the report window. `days_of_cover` is empty when nothing sold in that
+/// window.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct LowStockVariant {
+    pub variant_id: i32,
+    pub sku: String,
+    pub color: Option<String>,
+    pub product_id: String,
+    pub name: String,
+    pub stock: i32,
+    pub threshold: i32,
+    pub units_sold: i64,
+    pub daily_velocity: Decimal,
+    pub days_of_cover: Option<Decimal>,
+    /// Enough to cover another window of the same demand and get back above
+    /// the threshold.
+    pub suggested_reorder: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LowStockQuery {
+    pub days: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LowStockReport {
+    pub days: i32,
+    pub variants: Vec<LowStockVariant>,
+}
@@ -1,17 +1,49 @@
 mod admin;
 mod blog;
+mod bundle;
+mod campaign;
+mod cart;
+mod catalog;
 mod category;
+mod coins;
+mod coupon;
 mod email;
+mod idempotency;
+mod inventory;
 mod order;
+mod price;
+mod product_alert;
 mod products;
+mod question;
+mod reconciliation;
+mod refund;
+mod review;
+mod search;
 mod task;
 mod user;
+mod variant;
 
 pub use admin::*;
 pub use blog::*;
+pub use bundle::*;
+pub use campaign::*;
+pub use cart::*;
+pub use catalog::*;
 pub use category::*;
+pub use coins::*;
+pub use coupon::*;
 pub use email::*;
+pub use idempotency::*;
+pub use inventory::*;
 pub use order::*;
+pub use price::*;
+pub use product_alert::*;
 pub use products::*;
+pub use question::*;
+pub use reconciliation::*;
+pub use refund::*;
+pub use review::*;
+pub use search::*;
 pub use task::*;
 pub use user::*;
+pub use variant::*;
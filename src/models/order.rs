@@ -1,8 +1,11 @@
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
 use uuid::Uuid;
 
+use crate::models::BundleItem;
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct CheckoutAnalyticsEvent {
     pub session_id: Uuid,
@@ -21,6 +24,8 @@ pub struct CheckoutAnalyticsEvent {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CartSnapshotItem {
     pub product_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variant_id: Option<i32>,
     pub quantity: i32,
     pub color: Option<String>,
     pub cable_config: Option<CableConfig>,
@@ -88,7 +93,7 @@ pub struct Order {
     pub id: i32,
     pub user_id: Option<i32>,
     pub order_id: String,
-    pub status: String,
+    pub status: OrderStatus,
     pub payment_id: Option<i32>,
     pub amount: i32,
     pub currency: String,
@@ -116,15 +121,31 @@ pub struct Order {
     pub source_comment: Option<String>,
     pub is_installment_sale: bool,
     pub is_product_exchange: bool,
+    pub coins_redeemed: i32,
+    pub stock_deducted_at: Option<DateTime<Utc>>,
+    pub stock_restored_at: Option<DateTime<Utc>>,
+    pub payment_checked_at: Option<DateTime<Utc>>,
+    pub coupon_code: Option<String>,
+    pub coupon_discount: i32,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+impl Order {
+    /// Checkout orders pick up with `delivery_type = "pickup"`, operator orders
+    /// with the store pickup fulfillment method.
+    pub fn is_store_pickup(&self) -> bool {
+        self.delivery_type == "pickup"
+            || self.fulfillment_method.as_deref() == Some(FulfillmentMethod::StorePickup.as_str())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct OrderItem {
     pub id: i32,
     pub order_id: i32,
     pub product_id: Option<String>,
+    pub variant_id: Option<i32>,
     pub color: Option<String>,
     pub quantity: i32,
     pub price_at_purchase: Decimal,
@@ -132,6 +153,8 @@ pub struct OrderItem {
     pub product_image: Option<serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cable_config: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bundle_items: Option<Json<Vec<BundleItem>>>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -213,6 +236,7 @@ pub enum CustomerInfo {
 #[derive(Debug, Deserialize)]
 pub struct CartItem {
     pub product_id: String,
+    pub variant_id: Option<i32>,
     pub color: Option<String>,
     pub quantity: i32,
     pub cable_config: Option<CableConfig>,
@@ -236,6 +260,9 @@ pub struct CheckoutRequest {
     #[serde(default)]
     pub comment_image_uuids: Vec<Uuid>,
     pub payment_method: CheckoutPaymentMethod,
+    #[serde(default)]
+    pub coins_to_redeem: i32,
+    pub coupon_code: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -256,12 +283,17 @@ impl CheckoutPaymentMethod {
 
 pub struct OrderItemData {
     pub product_id: Option<String>,
+    pub variant_id: Option<i32>,
     pub color: Option<String>,
     pub quantity: i32,
     pub price: Decimal,
     pub product_name: String,
     pub image: serde_json::Value,
     pub cable_config: Option<serde_json::Value>,
+    pub campaign_id: Option<i32>,
+    /// Per unit, against the product's own price.
+    pub campaign_discount: Option<Decimal>,
+    pub bundle_items: Option<Vec<BundleItem>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -320,6 +352,125 @@ impl FulfillmentMethod {
     }
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    Pending,
+    Created,
+    Processing,
+    Approved,
+    Shipped,
+    Delivered,
+    Declined,
+    Expired,
+    Reversed,
+    Cancelled,
+    Returned,
+    Refunded,
+    PartiallyRefunded,
+}
+
+impl OrderStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderStatus::Pending => "pending",
+            OrderStatus::Created => "created",
+            OrderStatus::Processing => "processing",
+            OrderStatus::Approved => "approved",
+            OrderStatus::Shipped => "shipped",
+            OrderStatus::Delivered => "delivered",
+            OrderStatus::Declined => "declined",
+            OrderStatus::Expired => "expired",
+            OrderStatus::Reversed => "reversed",
+            OrderStatus::Cancelled => "cancelled",
+            OrderStatus::Returned => "returned",
+            OrderStatus::Refunded => "refunded",
+            OrderStatus::PartiallyRefunded => "partially_refunded",
+        }
+    }
+
+    /// Maps an `order_status` reported by Flitt.
+    pub fn from_flitt(status: &str) -> Option<Self> {
+        match status {
+            "created" => Some(OrderStatus::Created),
+            "processing" => Some(OrderStatus::Processing),
+            "approved" => Some(OrderStatus::Approved),
+            "declined" => Some(OrderStatus::Declined),
+            "expired" => Some(OrderStatus::Expired),
+            "reversed" => Some(OrderStatus::Reversed),
+            _ => None,
+        }
+    }
+
+    /// Whether stock has been taken out of `product_variants` for this order.
+    pub fn holds_stock(&self) -> bool {
+        matches!(
+            self,
+            OrderStatus::Approved
+                | OrderStatus::Shipped
+                | OrderStatus::Delivered
+                | OrderStatus::PartiallyRefunded
+        )
+    }
+
+    pub fn can_transition_to(&self, next: OrderStatus) -> bool {
+        use OrderStatus::*;
+
+        match self {
+            Pending | Created => {
+                matches!(
+                    next,
+                    Created | Processing | Approved | Declined | Expired | Cancelled
+                ) && next != *self
+            }
+            Processing => matches!(next, Approved | Declined | Expired | Reversed | Cancelled),
+            // Flitt can still approve a payment after the hold lapsed or a retry
+            Declined | Expired => matches!(next, Approved | Cancelled),
+            Approved => matches!(
+                next,
+                Shipped
+                    | Delivered
+                    | Cancelled
+                    | Returned
+                    | Reversed
+                    | Refunded
+                    | PartiallyRefunded
+            ),
+            Shipped => matches!(next, Delivered | Returned | Refunded | PartiallyRefunded),
+            Delivered => matches!(next, Returned | Refunded | PartiallyRefunded),
+            PartiallyRefunded => matches!(
+                next,
+                Shipped | Delivered | Cancelled | Returned | Refunded | PartiallyRefunded
+            ),
+            Reversed | Cancelled | Returned | Refunded => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatusSource {
+    Checkout,
+    Flitt,
+    Operator,
+    System,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct OrderStatusHistoryEntry {
+    pub id: i64,
+    pub order_id: i32,
+    pub from_status: Option<String>,
+    pub to_status: String,
+    pub source: OrderStatusSource,
+    pub changed_by_user_id: Option<i32>,
+    pub changed_by_name: Option<String>,
+    pub comment: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum OrderSource {
@@ -341,6 +492,8 @@ pub struct AdminOrderItemRequest {
     #[serde(default)]
     pub product_id: Option<String>,
     #[serde(default)]
+    pub variant_id: Option<i32>,
+    #[serde(default)]
     pub color: Option<String>,
     #[serde(default)]
     pub quantity: Option<i32>,
@@ -355,7 +508,7 @@ pub struct AdminOrderItemRequest {
 #[derive(Debug, Deserialize)]
 pub struct AdminOrderRequest {
     #[serde(default)]
-    pub status: Option<String>,
+    pub status: Option<OrderStatus>,
     #[serde(default)]
     pub amount: Option<Decimal>,
     #[serde(default)]
@@ -421,3 +574,84 @@ pub struct PaymentLinkRequest {
     #[serde(default)]
     pub comment: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::OrderStatus::{self, *};
+
+    const ALL: [OrderStatus; 13] = [
+        Pending,
+        Created,
+        Processing,
+        Approved,
+        Shipped,
+        Delivered,
+        Declined,
+        Expired,
+        Reversed,
+        Cancelled,
+        Returned,
+        Refunded,
+        PartiallyRefunded,
+    ];
+
+    #[test]
+    fn payment_flow_moves_forward() {
+        assert!(Pending.can_transition_to(Created));
+        assert!(Created.can_transition_to(Processing));
+        assert!(Processing.can_transition_to(Approved));
+        assert!(Approved.can_transition_to(Shipped));
+        assert!(Shipped.can_transition_to(Delivered));
+        assert!(Delivered.can_transition_to(Returned));
+    }
+
+    #[test]
+    fn unpaid_orders_do_not_repeat_or_skip_to_fulfilment() {
+        assert!(!Pending.can_transition_to(Pending));
+        assert!(!Created.can_transition_to(Created));
+        assert!(!Created.can_transition_to(Shipped));
+        assert!(!Processing.can_transition_to(Delivered));
+        assert!(!Processing.can_transition_to(Refunded));
+    }
+
+    #[test]
+    fn released_orders_can_be_approved_again() {
+        assert!(Declined.can_transition_to(Approved));
+        assert!(Expired.can_transition_to(Approved));
+        assert!(Declined.can_transition_to(Cancelled));
+        assert!(!Declined.can_transition_to(Shipped));
+        assert!(!Expired.can_transition_to(Refunded));
+    }
+
+    #[test]
+    fn shipped_orders_cannot_be_cancelled() {
+        assert!(Approved.can_transition_to(Cancelled));
+        assert!(PartiallyRefunded.can_transition_to(Cancelled));
+        assert!(!Shipped.can_transition_to(Cancelled));
+        assert!(!Delivered.can_transition_to(Cancelled));
+        assert!(!Shipped.can_transition_to(Approved));
+    }
+
+    #[test]
+    fn partial_refunds_can_repeat_and_complete() {
+        assert!(Approved.can_transition_to(PartiallyRefunded));
+        assert!(PartiallyRefunded.can_transition_to(PartiallyRefunded));
+        assert!(PartiallyRefunded.can_transition_to(Refunded));
+        assert!(!PartiallyRefunded.can_transition_to(Approved));
+    }
+
+    #[test]
+    fn final_statuses_are_terminal() {
+        for from in [Reversed, Cancelled, Returned, Refunded] {
+            for next in ALL {
+                assert!(!from.can_transition_to(next), "{from:?} -> {next:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn only_paid_and_unreturned_orders_hold_stock() {
+        let holding: Vec<_> = ALL.into_iter().filter(OrderStatus::holds_stock).collect();
+        assert_eq!(holding, [Approved, Shipped, Delivered, PartiallyRefunded]);
+    }
+}
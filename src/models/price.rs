@@ -0,0 +1,116 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum PriceChangeSource {
+    Admin,
+    Import,
+    Schedule,
+}
+
+/// Who a price change is recorded against in the price history.
+#[derive(Debug, Clone, Copy)]
+pub struct PriceChangeOrigin {
+    pub source: PriceChangeSource,
+    pub changed_by_user_id: Option<i32>,
+    pub schedule_id: Option<i32>,
+}
+
+impl PriceChangeOrigin {
+    pub fn admin(user_id: i32) -> Self {
+        PriceChangeOrigin {
+            source: PriceChangeSource::Admin,
+            changed_by_user_id: Some(user_id),
+            schedule_id: None,
+        }
+    }
+
+    pub fn import(user_id: i32) -> Self {
+        PriceChangeOrigin {
+            source: PriceChangeSource::Import,
+            changed_by_user_id: Some(user_id),
+            schedule_id: None,
+        }
+    }
+
+    pub fn schedule(schedule: &ScheduledPriceChange) -> Self {
+        PriceChangeOrigin {
+            source: PriceChangeSource::Schedule,
+            changed_by_user_id: schedule.created_by_user_id,
+            schedule_id: Some(schedule.id),
+        }
+    }
+}
+
+/// Prices in effect from `created_at` until the next entry.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct PriceHistoryEntry {
+    pub id: i64,
+    pub product_id: String,
+    pub price: Decimal,
+    pub discount: Decimal,
+    pub discounted_price: Option<Decimal>,
+    pub source: PriceChangeSource,
+    pub schedule_id: Option<i32>,
+    pub changed_by_user_id: Option<i32>,
+    pub changed_by_name: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum PriceScheduleStatus {
+    Pending,
+    Running,
+    Applied,
+    Cancelled,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ScheduledPriceChange {
+    pub id: i32,
+    pub product_id: String,
+    pub price: Option<Decimal>,
+    pub discount: Option<Decimal>,
+    pub discounted_price: Option<Decimal>,
+    pub apply_at: DateTime<Utc>,
+    pub status: PriceScheduleStatus,
+    pub error: Option<String>,
+    pub created_by_user_id: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub claimed_at: Option<DateTime<Utc>>,
+    pub applied_at: Option<DateTime<Utc>>,
+    pub cancelled_at: Option<DateTime<Utc>>,
+}
+
+/// Fields work like the ones on a product update; a `discount` of 0 ends a
+/// sale.
+#[derive(Debug, Deserialize)]
+pub struct CreatePriceScheduleRequest {
+    pub price: Option<Decimal>,
+    pub discount: Option<Decimal>,
+    pub discounted_price: Option<Decimal>,
+    pub apply_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PriceScheduleQuery {
+    pub product_id: Option<String>,
+    pub status: Option<PriceScheduleStatus>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PriceScheduleSearchResponse {
+    pub schedules: Vec<ScheduledPriceChange>,
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
@@ -0,0 +1,34 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum ProductAlertKind {
+    BackInStock,
+    PriceDrop,
+}
+
+/// Guests must give an email; logged-in users default to their account email.
+#[derive(Debug, Deserialize)]
+pub struct ProductAlertRequest {
+    pub kind: ProductAlertKind,
+    pub color: Option<String>,
+    pub email: Option<String>,
+}
+
+/// A queued email together with what is needed to render it.
+#[derive(Debug, FromRow)]
+pub struct PendingProductAlert {
+    pub delivery_id: i32,
+    pub alert_id: i32,
+    pub kind: ProductAlertKind,
+    pub email: String,
+    pub color: Option<String>,
+    pub price: Option<Decimal>,
+    pub unsubscribe_token: Uuid,
+    pub product_id: String,
+    pub product_name: String,
+}
@@ -3,7 +3,9 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::models::{Category, CategoryFacetValue};
+use crate::models::{
+    BundleComponent, Category, CategoryFacetValue, ProductVariant, PublicProductQuestion,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Product {
@@ -22,8 +24,33 @@ pub struct Product {
     #[serde(skip)]
     pub videos: serde_json::Value,
     pub enabled: bool,
+    pub coins_eligible: bool,
+    pub low_stock_threshold: Option<i32>,
+    #[serde(skip)]
+    pub rating_avg: Option<Decimal>,
+    #[serde(skip)]
+    pub rating_count: i32,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Set when a running campaign prices the product below its own
+    /// discount. Only storefront queries load these.
+    #[sqlx(default)]
+    pub campaign_id: Option<i32>,
+    #[sqlx(default)]
+    pub campaign_name: Option<String>,
+    #[sqlx(default)]
+    pub campaign_price: Option<Decimal>,
+    #[sqlx(default)]
+    pub campaign_ends_at: Option<DateTime<Utc>>,
+    #[sqlx(default)]
+    pub campaign_discount_percent: Option<Decimal>,
+}
+
+/// Average of approved reviews.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProductRating {
+    pub average: Option<Decimal>,
+    pub count: i32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -46,7 +73,7 @@ pub struct ProductImage {
     pub color: Option<String>,
     pub is_primary: bool,
     pub extension: String,
-    pub quantity: i32,
+    pub variant_id: Option<i32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -88,6 +115,7 @@ pub struct ProductResponse {
     pub data: Product,
     pub images: Vec<ProductImage>,
     pub videos: Vec<ProductVideo>,
+    pub rating: ProductRating,
     pub categories: Vec<Category>,
     pub seo: Option<ProductSeo>,
 }
@@ -96,6 +124,65 @@ impl ProductResponse {
     pub fn videos_from(product: &Product) -> Vec<ProductVideo> {
         serde_json::from_value(product.videos.clone()).unwrap_or_default()
     }
+
+    pub fn rating_from(product: &Product) -> ProductRating {
+        ProductRating {
+            average: product.rating_avg,
+            count: product.rating_count,
+        }
+    }
+}
+
+/// Single product page: the product plus its variants and answered customer
+/// questions.
+#[derive(Debug, Serialize)]
+pub struct ProductDetailResponse {
+    #[serde(flatten)]
+    pub product: ProductResponse,
+    pub variants: Vec<ProductVariant>,
+    /// Empty unless the product is a bundle.
+    pub bundle: Vec<BundleComponent>,
+    pub questions: Vec<PublicProductQuestion>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelatedStrategy {
+    /// Precomputed co-view/co-purchase scores, topped up with `Similar`.
+    #[default]
+    Recommended,
+    /// Products sharing the most categories.
+    Similar,
+    /// Products most often ordered together with this one.
+    FrequentlyBoughtTogether,
+    /// Products most often viewed by the same visitors.
+    AlsoViewed,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RelatedProductsQuery {
+    #[serde(default, alias = "mode")]
+    pub strategy: RelatedStrategy,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RecommendationsQuery {
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ProductViewRequest {
+    pub session_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RecentlyViewedQuery {
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MergeViewsRequest {
+    pub session_id: Uuid,
 }
 
 #[derive(Debug, Serialize)]
@@ -112,6 +199,7 @@ pub enum SortBy {
     PriceAsc,
     PriceDesc,
     ViewsDesc,
+    RatingDesc,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
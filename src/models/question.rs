@@ -0,0 +1,56 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ProductQuestion {
+    pub id: i32,
+    pub product_id: String,
+    pub user_id: Option<i32>,
+    pub author_name: String,
+    pub email: String,
+    pub question: String,
+    pub answer: Option<String>,
+    pub answered_by_user_id: Option<i32>,
+    pub answered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// What the storefront shows; the asker's email stays private.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct PublicProductQuestion {
+    pub id: i32,
+    pub author_name: String,
+    pub question: String,
+    pub answer: String,
+    pub created_at: DateTime<Utc>,
+    pub answered_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateQuestionRequest {
+    pub product_id: String,
+    pub question: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AnswerQuestionRequest {
+    pub answer: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QuestionQuery {
+    pub product_id: Option<String>,
+    pub answered: Option<bool>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QuestionSearchResponse {
+    pub questions: Vec<ProductQuestion>,
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
@@ -0,0 +1,42 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, types::Json};
+
+use crate::models::OrderStatus;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReconciliationIssue {
+    /// Flitt and our order disagree on whether the order is paid.
+    Status,
+    /// Both sides agree the order is paid, but not on how much.
+    Amount,
+    /// Flitt's status API could not be queried for the order.
+    Lookup,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconciliationMismatch {
+    pub order_id: String,
+    pub issue: ReconciliationIssue,
+    pub status: OrderStatus,
+    pub flitt_status: Option<String>,
+    pub amount: i32,
+    pub flitt_amount: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ReconciliationReport {
+    pub id: i32,
+    pub report_date: NaiveDate,
+    pub orders_checked: i32,
+    pub mismatch_count: i32,
+    pub mismatches: Json<Vec<ReconciliationMismatch>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReconciliationReportQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
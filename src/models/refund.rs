@@ -0,0 +1,69 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum RefundStatus {
+    Pending,
+    Approved,
+    Processing,
+    Declined,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Refund {
+    pub id: i32,
+    pub order_id: i32,
+    pub amount: i32,
+    pub status: RefundStatus,
+    pub comment: Option<String>,
+    pub created_by_user_id: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct RefundItem {
+    pub id: i32,
+    pub refund_id: i32,
+    pub order_item_id: i32,
+    pub quantity: i32,
+}
+
+/// A refund still `pending` or `processing` on our side. `settles_at` is the
+/// order's total refunded amount once this one and every earlier one went
+/// through; `overdue` means Flitt had the whole lookback window to settle it.
+#[derive(Debug, Clone, FromRow)]
+pub struct UnsettledRefund {
+    pub id: i32,
+    pub order_id: String,
+    pub status: RefundStatus,
+    pub settles_at: i64,
+    pub overdue: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RefundResponse {
+    #[serde(flatten)]
+    pub refund: Refund,
+    pub items: Vec<RefundItem>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RefundItemRequest {
+    pub order_item_id: i32,
+    pub quantity: i32,
+}
+
+/// Leaving both `items` and `amount` empty refunds everything still refundable.
+/// With `items` only, the amount is derived from their purchase prices.
+#[derive(Debug, Deserialize)]
+pub struct RefundRequest {
+    #[serde(default)]
+    pub items: Vec<RefundItemRequest>,
+    pub amount: Option<i32>,
+    pub comment: Option<String>,
+}
@@ -0,0 +1,115 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::models::CommentImage;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum ReviewStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ProductReview {
+    pub id: i32,
+    pub product_id: String,
+    pub user_id: i32,
+    pub rating: i32,
+    pub body: Option<String>,
+    pub status: ReviewStatus,
+    pub moderated_by_user_id: Option<i32>,
+    pub moderated_at: Option<DateTime<Utc>>,
+    pub moderation_comment: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct ProductReviewImage {
+    pub id: i32,
+    pub review_id: Option<i32>,
+    pub user_id: i32,
+    pub image_uuid: Uuid,
+    pub extension: String,
+    pub position: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProductReviewResponse {
+    #[serde(flatten)]
+    pub review: ProductReview,
+    pub author_name: String,
+    pub images: Vec<CommentImage>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReviewSearchResponse {
+    pub reviews: Vec<ProductReviewResponse>,
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+/// Storefront view of an approved review, without moderation details or the
+/// author's account id.
+#[derive(Debug, Serialize)]
+pub struct PublicReview {
+    pub id: i32,
+    pub product_id: String,
+    pub rating: i32,
+    pub body: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub author_name: String,
+    pub images: Vec<CommentImage>,
+}
+
+impl From<ProductReviewResponse> for PublicReview {
+    fn from(response: ProductReviewResponse) -> Self {
+        Self {
+            id: response.review.id,
+            product_id: response.review.product_id,
+            rating: response.review.rating,
+            body: response.review.body,
+            created_at: response.review.created_at,
+            author_name: response.author_name,
+            images: response.images,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PublicReviewSearchResponse {
+    pub reviews: Vec<PublicReview>,
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateReviewRequest {
+    pub product_id: String,
+    pub rating: i32,
+    pub body: Option<String>,
+    #[serde(default)]
+    pub image_uuids: Vec<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReviewQuery {
+    pub status: Option<ReviewStatus>,
+    pub product_id: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReviewModerationRequest {
+    pub status: ReviewStatus,
+    pub comment: Option<String>,
+}
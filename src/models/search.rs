@@ -0,0 +1,20 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// A group of interchangeable search words. A query word matching the term or
+/// any synonym also finds products matching the rest of the group.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct SearchSynonym {
+    pub id: i32,
+    pub term: String,
+    pub synonyms: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchSynonymRequest {
+    pub term: String,
+    pub synonyms: Vec<String>,
+}
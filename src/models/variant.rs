@@ -0,0 +1,46 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, types::Json};
+
+/// A purchasable configuration of a product. `color` mirrors
+/// `attributes.color` and is what carts, alerts and filters match on.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ProductVariant {
+    pub id: i32,
+    pub product_id: String,
+    pub sku: String,
+    pub attributes: Json<BTreeMap<String, String>>,
+    pub color: Option<String>,
+    pub price_override: Option<Decimal>,
+    pub stock: i32,
+    pub barcode: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// `stock` is booked as a receipt into the shipping warehouse.
+#[derive(Debug, Deserialize)]
+pub struct CreateVariantRequest {
+    pub sku: String,
+    #[serde(default)]
+    pub attributes: BTreeMap<String, String>,
+    pub price_override: Option<Decimal>,
+    #[serde(default)]
+    pub stock: i32,
+    pub barcode: Option<String>,
+}
+
+/// Omitted fields are left unchanged; `clear_price_override` drops the override.
+/// Stock changes go through receipts, transfers and stock-takes.
+#[derive(Debug, Deserialize)]
+pub struct UpdateVariantRequest {
+    pub sku: Option<String>,
+    pub attributes: Option<BTreeMap<String, String>>,
+    pub price_override: Option<Decimal>,
+    #[serde(default)]
+    pub clear_price_override: bool,
+    pub barcode: Option<String>,
+}
@@ -1,30 +1,37 @@
 use sqlx::PgPool;
 
 use crate::{
-    error::Result,
+    error::{AppError, Result},
     models::{
         AnalyticsPeriod, AnalyticsQuery, AnalyticsResponse, Brand, CableType, CableTypeRequest,
-        CableVariant, CableVariantRequest, CableVariantUpdate, CartSnapshotItem, CheckoutEventRow,
-        CheckoutSessionQuery, CheckoutSessionSummary, CheckoutSessionsResponse, ConversionRate,
-        HighViewsLowSales, MostViewedProduct, Order, OrderCreator, OrderQuery, OrderSearchResponse,
-        OrderSource, Product, ProductImage, ProductRequest, ProductSeo, ProductSeoRequest,
+        CableVariant, CableVariantRequest, CableVariantUpdate, CampaignResult, CartSnapshotItem,
+        CheckoutEventRow, CheckoutSessionQuery, CheckoutSessionSummary, CheckoutSessionsResponse,
+        ConversionRate, HighViewsLowSales, MostFavoritedProduct, MostViewedProduct, Order,
+        OrderCreator, OrderQuery, OrderSearchResponse, OrderSource, OrderStatus, OrderStatusSource,
+        PriceChangeOrigin, Product, ProductImage, ProductRequest, ProductSeo, ProductSeoRequest,
         TrendingProduct, UniqueViewersProduct, UserQuery, UserRequest, UserResponse,
         UserSearchResponse, ViewsByHour,
     },
+    queries::{inventory_queries, order_queries, price_queries},
 };
 
 pub async fn create_product(
     pool: &PgPool,
     req: &ProductRequest,
     videos: &serde_json::Value,
+    created_by_user_id: i32,
+    origin: PriceChangeOrigin,
 ) -> Result<Product> {
+    let mut tx = pool.begin().await?;
+
     let product = sqlx::query_as::<_, Product>(
         r#"
         INSERT INTO products (
             id, name, description, price, discount, discounted_price, quantity,
-            specifications, brand_id, cable_type_id, warranty, videos, enabled
+            specifications, brand_id, cable_type_id, warranty, videos, enabled, coins_eligible,
+            low_stock_threshold
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
         RETURNING *, (SELECT name FROM brands WHERE id = brand_id) as brand_name
         "#,
     )
@@ -45,9 +52,28 @@ pub async fn create_product(
     .bind(&req.warranty)
     .bind(videos)
     .bind(req.enabled.unwrap_or(true))
-    .fetch_one(pool)
+    .bind(req.coins_eligible.unwrap_or(false))
+    .bind(req.low_stock_threshold.flatten())
+    .fetch_one(&mut *tx)
     .await?;
 
+    // new products start with one default variant holding their stock
+    let variant_id = sqlx::query_scalar::<_, i32>(
+        "INSERT INTO product_variants (product_id, sku) VALUES ($1, $1 || '-1') RETURNING id",
+    )
+    .bind(&product.id)
+    .fetch_one(&mut *tx)
+    .await?;
+    inventory_queries::receive_opening_stock(
+        &mut tx,
+        variant_id,
+        product.quantity,
+        created_by_user_id,
+    )
+    .await?;
+    price_queries::record_price(&mut tx, &product.id, origin).await?;
+
+    tx.commit().await?;
     Ok(product)
 }
 
@@ -56,7 +82,10 @@ pub async fn update_product(
     id: &str,
     req: &ProductRequest,
     videos: Option<&serde_json::Value>,
+    origin: PriceChangeOrigin,
 ) -> Result<Product> {
+    let mut tx = pool.begin().await?;
+
     let product = sqlx::query_as::<_, Product>(
         r#"
         UPDATE products
@@ -72,8 +101,10 @@ pub async fn update_product(
             warranty = COALESCE($11, warranty),
             videos = COALESCE($12, videos),
             enabled = COALESCE($13, enabled),
+            coins_eligible = COALESCE($14, coins_eligible),
+            low_stock_threshold = CASE WHEN $15 THEN $16 ELSE low_stock_threshold END,
             updated_at = NOW()
-        WHERE id = $14
+        WHERE id = $17
         RETURNING *, (SELECT name FROM brands WHERE id = brand_id) as brand_name
         "#,
     )
@@ -90,10 +121,18 @@ pub async fn update_product(
     .bind(&req.warranty)
     .bind(videos)
     .bind(&req.enabled)
+    .bind(req.coins_eligible)
+    .bind(req.low_stock_threshold.is_some())
+    .bind(req.low_stock_threshold.flatten())
     .bind(id)
-    .fetch_one(pool)
+    .fetch_one(&mut *tx)
     .await?;
+    price_queries::record_price(&mut tx, id, origin).await?;
+    if let Some(schedule_id) = origin.schedule_id {
+        price_queries::mark_schedule_applied(&mut tx, schedule_id).await?;
+    }
 
+    tx.commit().await?;
     Ok(product)
 }
 
@@ -162,12 +201,12 @@ pub async fn add_product_image(
     color: Option<String>,
     is_primary: bool,
     extension: &str,
-    quantity: i32,
+    variant_id: Option<i32>,
 ) -> Result<()> {
     sqlx::query(
         r#"
-        INSERT INTO product_images(product_id, image_uuid, color, is_primary, extension, quantity)
-        VALUES ($1, $2, $3, $4, $5, $6)
+        INSERT INTO product_images(product_id, image_uuid, color, is_primary, extension, variant_id)
+        VALUES ($1, $2, COALESCE($3, (SELECT color FROM product_variants WHERE id = $6)), $4, $5, $6)
         "#,
     )
     .bind(product_id)
@@ -175,7 +214,7 @@ pub async fn add_product_image(
     .bind(color)
     .bind(is_primary)
     .bind(extension)
-    .bind(quantity)
+    .bind(variant_id)
     .execute(pool)
     .await?;
 
@@ -204,15 +243,15 @@ pub async fn update_product_image_metadata(
     image_uuid: uuid::Uuid,
     color: Option<String>,
     is_primary: Option<bool>,
-    quantity: Option<i32>,
+    variant_id: Option<i32>,
 ) -> Result<Option<ProductImage>> {
     let updated_image = sqlx::query_as::<_, ProductImage>(
         r#"
         UPDATE product_images
         SET
-            color = COALESCE($3, color),
+            color = COALESCE($3, (SELECT color FROM product_variants WHERE id = $5), color),
             is_primary = COALESCE($4, is_primary),
-            quantity = COALESCE($5, quantity)
+            variant_id = COALESCE($5, variant_id)
         WHERE product_id = $1 AND image_uuid = $2
         RETURNING *
         "#,
@@ -221,7 +260,7 @@ pub async fn update_product_image_metadata(
     .bind(image_uuid)
     .bind(color)
     .bind(is_primary)
-    .bind(quantity)
+    .bind(variant_id)
     .fetch_optional(pool)
     .await?;
 
@@ -319,15 +358,74 @@ pub async fn delete_user(pool: &PgPool, id: i32) -> Result<u64> {
     Ok(result.rows_affected())
 }
 
-pub async fn update_order_status(pool: &PgPool, id: i32, status: &str) -> Result<Option<Order>> {
-    let order = sqlx::query_as::<_, Order>(
-        "UPDATE orders SET status = $1, updated_at = NOW() WHERE id = $2 RETURNING *",
-    )
-    .bind(status)
-    .bind(id)
-    .fetch_optional(pool)
-    .await?;
-    Ok(order)
+pub async fn update_order_status(
+    pool: &PgPool,
+    id: i32,
+    status: OrderStatus,
+    changed_by_user_id: i32,
+    comment: Option<&str>,
+) -> Result<Option<Order>> {
+    let mut tx = pool.begin().await?;
+
+    let order = sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE id = $1 FOR UPDATE")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+    let Some(order) = order else {
+        return Ok(None);
+    };
+
+    if !order.status.can_transition_to(status) {
+        return Err(AppError::BadRequest(format!(
+            "სტატუსის შეცვლა {}-დან {}-ზე დაუშვებელია",
+            order.status.as_str(),
+            status.as_str()
+        )));
+    }
+
+    // refunds move money, so they only go through the refund flow
+    if matches!(
+        status,
+        OrderStatus::Refunded | OrderStatus::PartiallyRefunded
+    ) {
+        return Err(AppError::BadRequest(
+            "თანხის დაბრუნება შესაძლებელია მხოლოდ დაბრუნების მოთხოვნით".to_string(),
+        ));
+    }
+
+    if status == OrderStatus::Approved
+        && matches!(order.status, OrderStatus::Declined | OrderStatus::Expired)
+    {
+        order_queries::reclaim_released(&mut tx, &order).await?;
+    }
+
+    let change = order_queries::StatusChange {
+        source: OrderStatusSource::Operator,
+        changed_by_user_id: Some(changed_by_user_id),
+        comment,
+    };
+    let mut order = order_queries::set_status(&mut tx, &order, status, &change).await?;
+
+    if status == OrderStatus::Approved {
+        if !order_queries::apply_approval(&mut tx, &mut order).await? {
+            return Err(AppError::BadRequest(
+                "პროდუქტი აღარ არის მარაგში".to_string(),
+            ));
+        }
+    } else if matches!(
+        status,
+        OrderStatus::Declined
+            | OrderStatus::Expired
+            | OrderStatus::Reversed
+            | OrderStatus::Cancelled
+            | OrderStatus::Returned
+    ) {
+        order_queries::apply_release(&mut tx, &order).await?;
+    }
+
+    tx.commit().await?;
+    Ok(Some(order))
 }
 
 pub async fn get_order_creators(
@@ -488,64 +586,86 @@ pub async fn get_orders(pool: &PgPool, params: OrderQuery) -> Result<OrderSearch
 }
 
 pub async fn get_analytics(pool: &PgPool, params: AnalyticsQuery) -> Result<AnalyticsResponse> {
-    // these fragments come from a fixed enum so no injection risk
-    let (where_pv, where_bare, join_pv) = match params.period {
-        Some(AnalyticsPeriod::Today) => (
-            "WHERE pv.viewed_at >= CURRENT_DATE",
-            "WHERE viewed_at >= CURRENT_DATE",
-            "AND pv.viewed_at >= CURRENT_DATE",
-        ),
+    // these fragments come from a fixed enum so no injection risk; views are
+    // read from the daily rollup, so periods are whole days
+    let (where_views, join_views) = match params.period {
+        Some(AnalyticsPeriod::Today) => ("WHERE d.day = CURRENT_DATE", "AND d.day = CURRENT_DATE"),
         Some(AnalyticsPeriod::Yesterday) => (
-            "WHERE pv.viewed_at >= CURRENT_DATE - INTERVAL '1 day' AND pv.viewed_at < CURRENT_DATE",
-            "WHERE viewed_at >= CURRENT_DATE - INTERVAL '1 day' AND viewed_at < CURRENT_DATE",
-            "AND pv.viewed_at >= CURRENT_DATE - INTERVAL '1 day' AND pv.viewed_at < CURRENT_DATE",
+            "WHERE d.day = CURRENT_DATE - 1",
+            "AND d.day = CURRENT_DATE - 1",
         ),
         Some(AnalyticsPeriod::Last7Days) => (
-            "WHERE pv.viewed_at >= CURRENT_DATE - INTERVAL '7 days'",
-            "WHERE viewed_at >= CURRENT_DATE - INTERVAL '7 days'",
-            "AND pv.viewed_at >= CURRENT_DATE - INTERVAL '7 days'",
+            "WHERE d.day >= CURRENT_DATE - 7",
+            "AND d.day >= CURRENT_DATE - 7",
         ),
         Some(AnalyticsPeriod::Last30Days) => (
-            "WHERE pv.viewed_at >= CURRENT_DATE - INTERVAL '30 days'",
-            "WHERE viewed_at >= CURRENT_DATE - INTERVAL '30 days'",
-            "AND pv.viewed_at >= CURRENT_DATE - INTERVAL '30 days'",
+            "WHERE d.day >= CURRENT_DATE - 30",
+            "AND d.day >= CURRENT_DATE - 30",
         ),
-        None => ("", "", ""),
+        None => ("", ""),
     };
 
     let most_viewed = sqlx::query_as::<_, MostViewedProduct>(&format!(
-        "SELECT pv.product_id, p.name as product_name, COUNT(*) as views
-             FROM product_views pv
-             JOIN products p ON p.id = pv.product_id
-             {where_pv}
-             GROUP BY pv.product_id, p.name
+        "SELECT d.product_id, p.name as product_name, SUM(d.views)::bigint as views
+             FROM product_view_daily d
+             JOIN products p ON p.id = d.product_id
+             {where_views}
+             GROUP BY d.product_id, p.name
              ORDER BY views DESC
              LIMIT 10"
     ))
     .fetch_all(pool)
     .await?;
 
-    let trending_this_week = sqlx::query_as::<_, TrendingProduct>(&format!(
-        "SELECT pv.product_id, p.name as product_name, COUNT(*) as views
-             FROM product_views pv
-             JOIN products p ON p.id = pv.product_id
-             WHERE pv.viewed_at >= CURRENT_DATE - INTERVAL '7 days'
-             GROUP BY pv.product_id, p.name
-             ORDER BY views DESC
+    // favorites saved within the period, so the ranking tracks the same window as views
+    let where_wishlist = match params.period {
+        Some(AnalyticsPeriod::Today) => "WHERE w.created_at >= CURRENT_DATE",
+        Some(AnalyticsPeriod::Yesterday) => {
+            "WHERE w.created_at >= CURRENT_DATE - INTERVAL '1 day' AND w.created_at < CURRENT_DATE"
+        }
+        Some(AnalyticsPeriod::Last7Days) => {
+            "WHERE w.created_at >= CURRENT_DATE - INTERVAL '7 days'"
+        }
+        Some(AnalyticsPeriod::Last30Days) => {
+            "WHERE w.created_at >= CURRENT_DATE - INTERVAL '30 days'"
+        }
+        None => "",
+    };
+
+    let most_favorited = sqlx::query_as::<_, MostFavoritedProduct>(&format!(
+        "SELECT w.product_id, p.name as product_name, COUNT(*) as favorites
+             FROM wishlists w
+             JOIN products p ON p.id = w.product_id
+             {where_wishlist}
+             GROUP BY w.product_id, p.name
+             ORDER BY favorites DESC
              LIMIT 10"
     ))
     .fetch_all(pool)
     .await?;
 
+    let trending_this_week = sqlx::query_as::<_, TrendingProduct>(
+        "SELECT d.product_id, p.name as product_name, SUM(d.views)::bigint as views
+             FROM product_view_daily d
+             JOIN products p ON p.id = d.product_id
+             WHERE d.day >= CURRENT_DATE - 7
+             GROUP BY d.product_id, p.name
+             ORDER BY views DESC
+             LIMIT 10",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    // logged-in viewers are distinct per day and summed over the period
     let unique_viewers = sqlx::query_as::<_, UniqueViewersProduct>(&format!(
-        "SELECT pv.product_id, p.name as product_name,
-                    COUNT(DISTINCT pv.user_id) as logged_in_viewers,
-                    COUNT(*) FILTER (WHERE pv.user_id IS NULL) as anonymous_views,
-                    COUNT(*) as total_views
-             FROM product_views pv
-             JOIN products p ON p.id = pv.product_id
-             {where_pv}
-             GROUP BY pv.product_id, p.name
+        "SELECT d.product_id, p.name as product_name,
+                    SUM(d.logged_in_viewers)::bigint as logged_in_viewers,
+                    SUM(d.anonymous_views)::bigint as anonymous_views,
+                    SUM(d.views)::bigint as total_views
+             FROM product_view_daily d
+             JOIN products p ON p.id = d.product_id
+             {where_views}
+             GROUP BY d.product_id, p.name
              ORDER BY total_views DESC
              LIMIT 10"
     ))
@@ -553,24 +673,26 @@ pub async fn get_analytics(pool: &PgPool, params: AnalyticsQuery) -> Result<Anal
     .await?;
 
     let views_by_hour = sqlx::query_as::<_, ViewsByHour>(&format!(
-        "SELECT EXTRACT(HOUR FROM viewed_at) as hour, COUNT(*) as views
-             FROM product_views
-             {where_bare}
-             GROUP BY hour
-             ORDER BY hour"
+        "SELECT (h.ord - 1)::numeric as hour, SUM(h.views)::bigint as views
+             FROM product_view_daily d
+             CROSS JOIN LATERAL unnest(d.hourly_views) WITH ORDINALITY AS h(views, ord)
+             {where_views}
+             GROUP BY h.ord
+             HAVING SUM(h.views) > 0
+             ORDER BY h.ord"
     ))
     .fetch_all(pool)
     .await?;
 
     let high_views_low_sales = sqlx::query_as::<_, HighViewsLowSales>(&format!(
         "SELECT p.id as product_id, p.name as product_name,
-                    COUNT(pv.id) as views,
+                    COALESCE(SUM(d.views), 0)::bigint as views,
                     COALESCE(SUM(oi.quantity), 0) as sold
              FROM products p
-             LEFT JOIN product_views pv ON pv.product_id = p.id {join_pv}
+             LEFT JOIN product_view_daily d ON d.product_id = p.id {join_views}
              LEFT JOIN order_items oi ON oi.product_id = p.id
              GROUP BY p.id, p.name
-             HAVING COUNT(pv.id) > 0 AND COALESCE(SUM(oi.quantity), 0) = 0
+             HAVING COALESCE(SUM(d.views), 0) > 0 AND COALESCE(SUM(oi.quantity), 0) = 0
              ORDER BY views DESC
              LIMIT 10"
     ))
@@ -591,12 +713,18 @@ pub async fn get_analytics(pool: &PgPool, params: AnalyticsQuery) -> Result<Anal
         None => "",
     };
 
+    let and_approved = if where_orders.is_empty() {
+        "WHERE o.status IN ('approved', 'shipped', 'delivered', 'partially_refunded')"
+    } else {
+        "AND o.status IN ('approved', 'shipped', 'delivered', 'partially_refunded')"
+    };
+
     let conversion_rates = sqlx::query_as::<_, ConversionRate>(&format!(
         "WITH view_counts AS (
-                 SELECT product_id, COUNT(*) as views
-                 FROM product_views
-                 {where_bare}
-                 GROUP BY product_id
+                 SELECT d.product_id, SUM(d.views)::bigint as views
+                 FROM product_view_daily d
+                 {where_views}
+                 GROUP BY d.product_id
              ),
              purchase_counts AS (
                  SELECT oi.product_id, COUNT(DISTINCT o.id) as purchases
@@ -616,23 +744,37 @@ pub async fn get_analytics(pool: &PgPool, params: AnalyticsQuery) -> Result<Anal
              LEFT JOIN purchase_counts pc ON pc.product_id = vc.product_id
              WHERE vc.views > 0
              ORDER BY conversion_pct DESC, viewers DESC
-             LIMIT 10",
-        and_approved = if where_orders.is_empty() {
-            "WHERE o.status = 'approved'"
-        } else {
-            "AND o.status = 'approved'"
-        },
+             LIMIT 10"
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    // orders placed in the period, by the campaign that priced their lines
+    let campaign_results = sqlx::query_as::<_, CampaignResult>(&format!(
+        "SELECT c.id as campaign_id, c.name as campaign_name,
+                COUNT(DISTINCT o.id) as orders,
+                SUM(oi.quantity)::bigint as units_sold,
+                SUM(oi.price_at_purchase * oi.quantity) as revenue,
+                SUM(COALESCE(oi.campaign_discount, 0) * oi.quantity) as discount_given
+             FROM order_items oi
+             JOIN orders o ON o.id = oi.order_id
+             JOIN campaigns c ON c.id = oi.campaign_id
+             {where_orders} {and_approved}
+             GROUP BY c.id, c.name
+             ORDER BY revenue DESC"
     ))
     .fetch_all(pool)
     .await?;
 
     Ok(AnalyticsResponse {
         most_viewed,
+        most_favorited,
         trending_this_week,
         unique_viewers,
         views_by_hour,
         high_views_low_sales,
         conversion_rates,
+        campaign_results,
     })
 }
 
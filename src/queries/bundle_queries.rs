@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+
+use sqlx::PgPool;
+
+use crate::{
+    error::{AppError, Result},
+    models::{BundleComponent, BundleComponentRequest},
+    queries::inventory_queries,
+};
+
+const COMPONENT_COLUMNS: &str =
+    "bc.bundle_id, bc.variant_id, pv.product_id, p.name AS product_name,
+     pv.sku, pv.color, bc.quantity, pv.stock";
+
+pub async fn find_by_bundle_id(pool: &PgPool, bundle_id: &str) -> Result<Vec<BundleComponent>> {
+    let components = sqlx::query_as::<_, BundleComponent>(&format!(
+        "SELECT {COMPONENT_COLUMNS}
+         FROM bundle_components bc
+         JOIN product_variants pv ON pv.id = bc.variant_id
+         JOIN products p ON p.id = pv.product_id
+         WHERE bc.bundle_id = $1
+         ORDER BY pv.product_id, pv.id"
+    ))
+    .bind(bundle_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(components)
+}
+
+/// Components of whichever of `product_ids` are bundles, keyed by bundle.
+pub async fn find_by_bundle_ids(
+    pool: &PgPool,
+    product_ids: &[String],
+) -> Result<HashMap<String, Vec<BundleComponent>>> {
+    let components = sqlx::query_as::<_, BundleComponent>(&format!(
+        "SELECT {COMPONENT_COLUMNS}
+         FROM bundle_components bc
+         JOIN product_variants pv ON pv.id = bc.variant_id
+         JOIN products p ON p.id = pv.product_id
+         WHERE bc.bundle_id = ANY($1)
+         ORDER BY bc.bundle_id, pv.product_id, pv.id"
+    ))
+    .bind(product_ids)
+    .fetch_all(pool)
+    .await?;
+
+    let mut groups: HashMap<String, Vec<BundleComponent>> = HashMap::new();
+    for component in components {
+        groups
+            .entry(component.bundle_id.clone())
+            .or_default()
+            .push(component);
+    }
+    Ok(groups)
+}
+
+pub async fn is_bundle(pool: &PgPool, product_id: &str) -> Result<bool> {
+    let exists = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM bundle_components WHERE bundle_id = $1)",
+    )
+    .bind(product_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(exists)
+}
+
+pub async fn is_component(pool: &PgPool, variant_id: i32) -> Result<bool> {
+    let exists = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM bundle_components WHERE variant_id = $1)",
+    )
+    .bind(variant_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(exists)
+}
+
+/// Whether any variant of `product_id` is part of a bundle.
+pub async fn has_component_variants(pool: &PgPool, product_id: &str) -> Result<bool> {
+    let exists = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (
+             SELECT 1 FROM bundle_components bc
+             JOIN product_variants pv ON pv.id = bc.variant_id
+             WHERE pv.product_id = $1
+         )",
+    )
+    .bind(product_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(exists)
+}
+
+pub async fn set_components(
+    pool: &PgPool,
+    bundle_id: &str,
+    components: &[BundleComponentRequest],
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("SELECT 1 FROM products WHERE id = $1 FOR UPDATE")
+        .bind(bundle_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let variant_ids: Vec<i32> = components.iter().map(|c| c.variant_id).collect();
+    let quantities: Vec<i32> = components.iter().map(|c| c.quantity).collect();
+    inventory_queries::ensure_variants_exist(&mut tx, &variant_ids).await?;
+
+    if !components.is_empty() {
+        let own = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (
+                 SELECT 1 FROM product_variants WHERE id = ANY($1) AND product_id = $2
+             )",
+        )
+        .bind(&variant_ids)
+        .bind(bundle_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        if own {
+            return Err(AppError::BadRequest(
+                "ნაკრები საკუთარ ვარიანტს ვერ შეიცავს".to_string(),
+            ));
+        }
+
+        #[derive(sqlx::FromRow)]
+        struct OwnVariants {
+            stock: i64,
+            in_other_bundle: bool,
+            has_movements: bool,
+        }
+
+        let variants = sqlx::query_as::<_, OwnVariants>(
+            "SELECT COALESCE(SUM(pv.stock), 0)::bigint AS stock,
+                    EXISTS (
+                        SELECT 1 FROM bundle_components bc
+                        JOIN product_variants v ON v.id = bc.variant_id
+                        WHERE v.product_id = $1
+                    ) AS in_other_bundle,
+                    EXISTS (
+                        SELECT 1 FROM stock_movements sm
+                        JOIN product_variants v ON v.id = sm.variant_id
+                        WHERE v.product_id = $1
+                    ) AS has_movements
+             FROM product_variants pv
+             WHERE pv.product_id = $1",
+        )
+        .bind(bundle_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        if variants.in_other_bundle {
+            return Err(AppError::Conflict(
+                "პროდუქტი სხვა ნაკრების შემადგენელია".to_string(),
+            ));
+        }
+        if variants.stock > 0 {
+            return Err(AppError::Conflict(
+                "ნაკრებად გადაქცევამდე პროდუქტის მარაგი უნდა ჩამოიწეროს".to_string(),
+            ));
+        }
+        // deleting the variants would take their stock history with them
+        if variants.has_movements {
+            return Err(AppError::Conflict(
+                "მარაგის ისტორიის მქონე პროდუქტი ნაკრებად ვერ გადაიქცევა".to_string(),
+            ));
+        }
+
+        sqlx::query("DELETE FROM product_variants WHERE product_id = $1")
+            .bind(bundle_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    sqlx::query("DELETE FROM bundle_components WHERE bundle_id = $1")
+        .bind(bundle_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        "INSERT INTO bundle_components (bundle_id, variant_id, quantity)
+         SELECT $1, unnest($2::int[]), unnest($3::int[])",
+    )
+    .bind(bundle_id)
+    .bind(&variant_ids)
+    .bind(&quantities)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
@@ -0,0 +1,132 @@
+use sqlx::PgPool;
+
+use crate::{
+    error::Result,
+    models::{Campaign, CampaignQuery, CampaignRequest, CampaignState},
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 20;
+const MAX_PAGE_SIZE: i64 = 100;
+
+pub async fn find_by_id(pool: &PgPool, id: i32) -> Result<Option<Campaign>> {
+    let campaign = sqlx::query_as::<_, Campaign>("SELECT * FROM campaigns WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(campaign)
+}
+
+pub async fn search_campaigns(
+    pool: &PgPool,
+    params: &CampaignQuery,
+) -> Result<(Vec<Campaign>, i64, i64, i64)> {
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let offset = params.offset.unwrap_or(0).max(0);
+
+    let mut qb = sqlx::QueryBuilder::<sqlx::Postgres>::new(
+        "SELECT c.*, COUNT(*) OVER() AS total_count FROM campaigns c WHERE 1=1",
+    );
+
+    if let Some(search) = params
+        .search
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        let like = format!("%{}%", search);
+        qb.push(" AND (c.name ILIKE ");
+        qb.push_bind(like.clone());
+        qb.push(" OR c.description ILIKE ");
+        qb.push_bind(like);
+        qb.push(")");
+    }
+
+    match params.state {
+        Some(CampaignState::Upcoming) => {
+            qb.push(" AND c.starts_at > NOW()");
+        }
+        Some(CampaignState::Active) => {
+            qb.push(" AND c.enabled AND c.starts_at <= NOW() AND c.ends_at > NOW()");
+        }
+        Some(CampaignState::Ended) => {
+            qb.push(" AND c.ends_at <= NOW()");
+        }
+        None => {}
+    }
+
+    qb.push(" ORDER BY c.starts_at DESC, c.id DESC LIMIT ");
+    qb.push_bind(limit);
+    qb.push(" OFFSET ");
+    qb.push_bind(offset);
+
+    #[derive(sqlx::FromRow)]
+    struct Row {
+        #[sqlx(flatten)]
+        campaign: Campaign,
+        total_count: i64,
+    }
+
+    let rows = qb.build_query_as::<Row>().fetch_all(pool).await?;
+    let total = rows.first().map(|r| r.total_count).unwrap_or(0);
+    let campaigns = rows.into_iter().map(|r| r.campaign).collect();
+
+    Ok((campaigns, total, limit, offset))
+}
+
+pub async fn create_campaign(pool: &PgPool, req: &CampaignRequest) -> Result<Campaign> {
+    let campaign = sqlx::query_as::<_, Campaign>(
+        "INSERT INTO campaigns (name, description, discount_percent, category_ids, brand_ids,
+             priority, starts_at, ends_at, enabled)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+         RETURNING *",
+    )
+    .bind(req.name.trim())
+    .bind(&req.description)
+    .bind(req.discount_percent)
+    .bind(&req.category_ids)
+    .bind(&req.brand_ids)
+    .bind(req.priority.unwrap_or(0))
+    .bind(req.starts_at)
+    .bind(req.ends_at)
+    .bind(req.enabled.unwrap_or(true))
+    .fetch_one(pool)
+    .await?;
+
+    Ok(campaign)
+}
+
+pub async fn update_campaign(pool: &PgPool, id: i32, req: &CampaignRequest) -> Result<Campaign> {
+    let campaign = sqlx::query_as::<_, Campaign>(
+        "UPDATE campaigns SET
+             name = $1, description = $2, discount_percent = $3, category_ids = $4,
+             brand_ids = $5, priority = $6, starts_at = $7, ends_at = $8, enabled = $9,
+             updated_at = NOW()
+         WHERE id = $10
+         RETURNING *",
+    )
+    .bind(req.name.trim())
+    .bind(&req.description)
+    .bind(req.discount_percent)
+    .bind(&req.category_ids)
+    .bind(&req.brand_ids)
+    .bind(req.priority.unwrap_or(0))
+    .bind(req.starts_at)
+    .bind(req.ends_at)
+    .bind(req.enabled.unwrap_or(true))
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(campaign)
+}
+
+pub async fn delete_campaign(pool: &PgPool, id: i32) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM campaigns WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
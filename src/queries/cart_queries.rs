@@ -0,0 +1,308 @@
+use sqlx::{PgConnection, PgPool, types::Json};
+use uuid::Uuid;
+
+use crate::{
+    config::AbandonedCartConfig,
+    error::Result,
+    models::{
+        AbandonedCartEmail, AbandonedCheckout, CartItem, CartItemRow, CartSnapshotItem, Order,
+        RestoredCartResponse,
+    },
+};
+
+/// Repeated adds stop growing a line past this.
+pub const MAX_LINE_QUANTITY: i32 = 99;
+
+const LINE_CONFLICT: &str =
+    "ON CONFLICT (cart_id, product_id, (COALESCE(variant_id, 0)), (COALESCE(color, '')),
+                 (COALESCE(cable_watts, 0)), (COALESCE(cable_length_cm, 0)))";
+
+async fn cart_id(conn: &mut PgConnection, user_id: i32) -> Result<i32> {
+    let id = sqlx::query_scalar::<_, i32>(
+        "INSERT INTO carts (user_id) VALUES ($1)
+         ON CONFLICT (user_id) DO UPDATE SET updated_at = NOW()
+         RETURNING id",
+    )
+    .bind(user_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(id)
+}
+
+/// Inserts a line, or combines it with the existing line for the same product
+/// configuration using `combine` (an expression over `cart_items.quantity`
+/// and `EXCLUDED.quantity`). Unknown products and variants are skipped.
+async fn upsert_item(
+    conn: &mut PgConnection,
+    cart_id: i32,
+    item: &CartItem,
+    combine: &str,
+) -> Result<()> {
+    sqlx::query(&format!(
+        "INSERT INTO cart_items (cart_id, product_id, color, cable_watts, cable_length_cm, quantity, variant_id)
+         SELECT $1, $2, $3, $4, $5, LEAST($6, $7), $8
+         WHERE EXISTS (SELECT 1 FROM products WHERE id = $2)
+           AND ($8::int IS NULL OR EXISTS (
+               SELECT 1 FROM product_variants WHERE id = $8 AND product_id = $2
+           ))
+         {LINE_CONFLICT}
+         DO UPDATE SET quantity = LEAST({combine}, $7), updated_at = NOW()"
+    ))
+    .bind(cart_id)
+    .bind(&item.product_id)
+    .bind(&item.color)
+    .bind(item.cable_config.as_ref().map(|c| c.watts))
+    .bind(item.cable_config.as_ref().map(|c| c.length_cm))
+    .bind(item.quantity)
+    .bind(MAX_LINE_QUANTITY)
+    .bind(item.variant_id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_items(pool: &PgPool, user_id: i32) -> Result<Vec<CartItemRow>> {
+    let items = sqlx::query_as::<_, CartItemRow>(
+        "SELECT ci.* FROM cart_items ci
+         JOIN carts c ON c.id = ci.cart_id
+         WHERE c.user_id = $1
+         ORDER BY ci.created_at, ci.id",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(items)
+}
+
+pub async fn add_item(pool: &PgPool, user_id: i32, item: &CartItem) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    let cart_id = cart_id(&mut tx, user_id).await?;
+    upsert_item(
+        &mut tx,
+        cart_id,
+        item,
+        "cart_items.quantity + EXCLUDED.quantity",
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Folds a guest cart into the user's cart. When both have the same line the
+/// larger quantity wins, so merging the same cart twice changes nothing.
+pub async fn merge(pool: &PgPool, user_id: i32, items: &[CartItem]) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    let cart_id = cart_id(&mut tx, user_id).await?;
+    for item in items {
+        upsert_item(
+            &mut tx,
+            cart_id,
+            item,
+            "GREATEST(cart_items.quantity, EXCLUDED.quantity)",
+        )
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn set_quantity(pool: &PgPool, user_id: i32, item_id: i32, quantity: i32) -> Result<u64> {
+    let result = sqlx::query(
+        "UPDATE cart_items ci SET quantity = $3, updated_at = NOW()
+         FROM carts c
+         WHERE ci.cart_id = c.id AND c.user_id = $1 AND ci.id = $2",
+    )
+    .bind(user_id)
+    .bind(item_id)
+    .bind(quantity)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+pub async fn remove_item(pool: &PgPool, user_id: i32, item_id: i32) -> Result<u64> {
+    let result = sqlx::query(
+        "DELETE FROM cart_items ci USING carts c
+         WHERE ci.cart_id = c.id AND c.user_id = $1 AND ci.id = $2",
+    )
+    .bind(user_id)
+    .bind(item_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+pub async fn clear(pool: &PgPool, user_id: i32) -> Result<()> {
+    sqlx::query(
+        "DELETE FROM cart_items ci USING carts c
+         WHERE ci.cart_id = c.id AND c.user_id = $1",
+    )
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Drops the lines a paid order bought from its customer's cart. Lines picked
+/// by variant match on it alone, since order lines take their color from the
+/// variant while the cart line may not have one.
+pub async fn remove_ordered_items(conn: &mut PgConnection, order: &Order) -> Result<()> {
+    let Some(user_id) = order.user_id else {
+        return Ok(());
+    };
+
+    sqlx::query(
+        "DELETE FROM cart_items ci USING carts c, order_items oi
+         WHERE ci.cart_id = c.id AND c.user_id = $1
+           AND oi.order_id = $2
+           AND CASE WHEN ci.variant_id IS NOT NULL THEN ci.variant_id = oi.variant_id
+                    ELSE ci.product_id = oi.product_id
+                         AND ci.color IS NOT DISTINCT FROM oi.color
+               END",
+    )
+    .bind(user_id)
+    .bind(order.id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Restore links stop working after this long.
+const RESTORE_LINK_TTL_DAYS: i32 = 7;
+
+/// Checkout sessions idle for `idle_minutes` that have an email and a cart
+/// snapshot but no purchase, skipping opted-out addresses, addresses that
+/// ordered since the session started and addresses already at the email cap.
+pub async fn find_abandoned_checkouts(
+    pool: &PgPool,
+    config: &AbandonedCartConfig,
+    limit: i64,
+) -> Result<Vec<AbandonedCheckout>> {
+    let checkouts = sqlx::query_as::<_, AbandonedCheckout>(
+        "WITH sessions AS (
+             SELECT session_id,
+                    MIN(created_at) AS started_at,
+                    MAX(created_at) AS last_activity_at,
+                    BOOL_OR(type = 'purchase' OR order_id IS NOT NULL) AS purchased,
+                    (ARRAY_AGG(LOWER(TRIM(value)) ORDER BY created_at DESC)
+                        FILTER (WHERE field = 'email' AND value LIKE '%_@_%'))[1] AS email
+             FROM checkout_analytics
+             WHERE created_at > NOW() - make_interval(hours => $2)
+             GROUP BY session_id
+         )
+         SELECT s.session_id, s.email, c.cart
+         FROM sessions s
+         JOIN checkout_cart_snapshots c ON c.session_id = s.session_id
+         WHERE NOT s.purchased
+           AND s.email IS NOT NULL
+           AND jsonb_array_length(c.cart) > 0
+           AND s.last_activity_at < NOW() - make_interval(mins => $1)
+           AND NOT EXISTS (SELECT 1 FROM abandoned_cart_emails a WHERE a.session_id = s.session_id)
+           AND NOT EXISTS (SELECT 1 FROM email_opt_outs o WHERE o.email = s.email)
+           AND NOT EXISTS (
+               SELECT 1 FROM orders o
+               WHERE LOWER(o.email) = s.email
+                 AND o.created_at >= s.started_at
+                 AND o.status NOT IN ('declined', 'expired', 'cancelled', 'reversed')
+           )
+           AND (
+               SELECT COUNT(*) FROM abandoned_cart_emails a
+               WHERE a.email = s.email AND a.sent_at > NOW() - make_interval(days => $3)
+           ) < $4
+         ORDER BY s.last_activity_at
+         LIMIT $5",
+    )
+    .bind(config.idle_minutes)
+    .bind(config.lookback_hours)
+    .bind(config.cap_window_days)
+    .bind(config.max_emails_per_address)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(checkouts)
+}
+
+/// Records the reminder before it is sent so a session is never emailed
+/// twice. Returns `None` if another run already claimed the session.
+pub async fn record_abandoned_cart_email(
+    pool: &PgPool,
+    checkout: &AbandonedCheckout,
+) -> Result<Option<AbandonedCartEmail>> {
+    let email = sqlx::query_as::<_, AbandonedCartEmail>(
+        "INSERT INTO abandoned_cart_emails (session_id, email, cart)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (session_id) DO NOTHING
+         RETURNING id, restore_token, unsubscribe_token",
+    )
+    .bind(checkout.session_id)
+    .bind(&checkout.email)
+    .bind(&checkout.cart)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(email)
+}
+
+/// Forgets a reminder that could not be delivered so the next run retries it.
+pub async fn delete_abandoned_cart_email(pool: &PgPool, id: i32) -> Result<()> {
+    sqlx::query("DELETE FROM abandoned_cart_emails WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Redeems a restore link. Each link works once.
+pub async fn redeem_restore_token(
+    pool: &PgPool,
+    token: Uuid,
+) -> Result<Option<RestoredCartResponse>> {
+    let row = sqlx::query_as::<_, (String, Json<Vec<CartSnapshotItem>>)>(
+        "UPDATE abandoned_cart_emails SET restored_at = NOW()
+         WHERE restore_token = $1
+           AND restored_at IS NULL
+           AND sent_at > NOW() - make_interval(days => $2)
+         RETURNING email, cart",
+    )
+    .bind(token)
+    .bind(RESTORE_LINK_TTL_DAYS)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|(email, Json(items))| RestoredCartResponse { email, items }))
+}
+
+/// Opts the address behind an unsubscribe link out of further reminders.
+/// Returns `false` for unknown tokens.
+pub async fn opt_out(pool: &PgPool, unsubscribe_token: Uuid) -> Result<bool> {
+    let result = sqlx::query(
+        "INSERT INTO email_opt_outs (email)
+         SELECT email FROM abandoned_cart_emails WHERE unsubscribe_token = $1
+         ON CONFLICT (email) DO NOTHING",
+    )
+    .bind(unsubscribe_token)
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() > 0 {
+        return Ok(true);
+    }
+
+    let known = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM abandoned_cart_emails WHERE unsubscribe_token = $1)",
+    )
+    .bind(unsubscribe_token)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(known)
+}
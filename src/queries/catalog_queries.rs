@@ -0,0 +1,272 @@
+use sqlx::{PgPool, types::Json};
+
+use crate::{
+    error::Result,
+    models::{
+        CatalogFileFormat, CatalogJob, CatalogJobKind, CatalogJobQuery, CatalogReport, Product,
+    },
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 20;
+const MAX_PAGE_SIZE: i64 = 100;
+
+const JOB_COLUMNS: &str = "id, kind, format, status, dry_run, file_name, report, error,
+     output IS NOT NULL AS has_output, created_by_user_id, created_at, started_at, finished_at";
+
+/// A job that has been running this long is assumed to have died with its
+/// process and is picked up again.
+const STALE_AFTER_MINUTES: i32 = 60;
+
+pub async fn create_import_job(
+    pool: &PgPool,
+    format: CatalogFileFormat,
+    dry_run: bool,
+    file_name: Option<&str>,
+    input: &[u8],
+    created_by_user_id: i32,
+) -> Result<CatalogJob> {
+    let job = sqlx::query_as::<_, CatalogJob>(&format!(
+        "INSERT INTO catalog_jobs (kind, format, dry_run, file_name, input, created_by_user_id)
+         VALUES ('import', $1, $2, $3, $4, $5)
+         RETURNING {JOB_COLUMNS}"
+    ))
+    .bind(format)
+    .bind(dry_run)
+    .bind(file_name)
+    .bind(input)
+    .bind(created_by_user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(job)
+}
+
+pub async fn create_export_job(
+    pool: &PgPool,
+    format: CatalogFileFormat,
+    created_by_user_id: i32,
+) -> Result<CatalogJob> {
+    let job = sqlx::query_as::<_, CatalogJob>(&format!(
+        "INSERT INTO catalog_jobs (kind, format, created_by_user_id)
+         VALUES ('export', $1, $2)
+         RETURNING {JOB_COLUMNS}"
+    ))
+    .bind(format)
+    .bind(created_by_user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(job)
+}
+
+pub async fn find_job(pool: &PgPool, id: i32) -> Result<Option<CatalogJob>> {
+    let job = sqlx::query_as::<_, CatalogJob>(&format!(
+        "SELECT {JOB_COLUMNS} FROM catalog_jobs WHERE id = $1"
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(job)
+}
+
+pub async fn search_jobs(
+    pool: &PgPool,
+    params: &CatalogJobQuery,
+) -> Result<(Vec<CatalogJob>, i64, i64, i64)> {
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let offset = params.offset.unwrap_or(0).max(0);
+
+    let mut qb = sqlx::QueryBuilder::<sqlx::Postgres>::new(format!(
+        "SELECT {JOB_COLUMNS}, COUNT(*) OVER() AS total_count FROM catalog_jobs WHERE 1=1"
+    ));
+
+    if let Some(kind) = params.kind {
+        qb.push(" AND kind = ");
+        qb.push_bind(kind);
+    }
+    if let Some(status) = params.status {
+        qb.push(" AND status = ");
+        qb.push_bind(status);
+    }
+
+    qb.push(" ORDER BY created_at DESC, id DESC LIMIT ");
+    qb.push_bind(limit);
+    qb.push(" OFFSET ");
+    qb.push_bind(offset);
+
+    #[derive(sqlx::FromRow)]
+    struct Row {
+        #[sqlx(flatten)]
+        job: CatalogJob,
+        total_count: i64,
+    }
+
+    let rows = qb.build_query_as::<Row>().fetch_all(pool).await?;
+    let total = rows.first().map(|r| r.total_count).unwrap_or(0);
+    let jobs = rows.into_iter().map(|r| r.job).collect();
+
+    Ok((jobs, total, limit, offset))
+}
+
+/// Takes the oldest waiting job and marks it running.
+pub async fn claim_next_job(pool: &PgPool) -> Result<Option<CatalogJob>> {
+    let job = sqlx::query_as::<_, CatalogJob>(&format!(
+        "UPDATE catalog_jobs SET status = 'running', started_at = NOW()
+         WHERE id = (
+             SELECT id FROM catalog_jobs
+             WHERE status = 'pending'
+                OR (status = 'running'
+                    AND started_at < NOW() - make_interval(mins => $1))
+             ORDER BY id
+             LIMIT 1
+             FOR UPDATE SKIP LOCKED
+         )
+         RETURNING {JOB_COLUMNS}"
+    ))
+    .bind(STALE_AFTER_MINUTES)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(job)
+}
+
+pub async fn find_input(pool: &PgPool, id: i32) -> Result<Option<Vec<u8>>> {
+    let input =
+        sqlx::query_scalar::<_, Option<Vec<u8>>>("SELECT input FROM catalog_jobs WHERE id = $1")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+
+    Ok(input.flatten())
+}
+
+pub async fn find_output(pool: &PgPool, id: i32) -> Result<Option<Vec<u8>>> {
+    let output =
+        sqlx::query_scalar::<_, Option<Vec<u8>>>("SELECT output FROM catalog_jobs WHERE id = $1")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+
+    Ok(output.flatten())
+}
+
+/// The upload is kept after a dry run so it can be applied later, and dropped
+/// once it has been.
+pub async fn complete_job(
+    pool: &PgPool,
+    id: i32,
+    report: &CatalogReport,
+    output: Option<&[u8]>,
+) -> Result<()> {
+    sqlx::query(
+        "UPDATE catalog_jobs SET
+             status = 'completed',
+             report = $2,
+             output = $3,
+             input = CASE WHEN dry_run THEN input END,
+             finished_at = NOW()
+         WHERE id = $1",
+    )
+    .bind(id)
+    .bind(Json(report))
+    .bind(output)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn fail_job(
+    pool: &PgPool,
+    id: i32,
+    report: Option<&CatalogReport>,
+    error: &str,
+) -> Result<()> {
+    sqlx::query(
+        "UPDATE catalog_jobs SET status = 'failed', report = $2, error = $3, finished_at = NOW()
+         WHERE id = $1",
+    )
+    .bind(id)
+    .bind(report.map(Json))
+    .bind(error)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Queues a clean dry run to be imported for real. Returns None when the job
+/// isn't one.
+pub async fn apply_dry_run(pool: &PgPool, id: i32) -> Result<Option<CatalogJob>> {
+    let job = sqlx::query_as::<_, CatalogJob>(&format!(
+        "UPDATE catalog_jobs SET
+             status = 'pending', dry_run = false, report = NULL, error = NULL,
+             started_at = NULL, finished_at = NULL
+         WHERE id = $1
+           AND kind = $2
+           AND dry_run
+           AND status = 'completed'
+           AND input IS NOT NULL
+           AND jsonb_array_length(report->'errors') = 0
+         RETURNING {JOB_COLUMNS}"
+    ))
+    .bind(id)
+    .bind(CatalogJobKind::Import)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(job)
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct CatalogExportRow {
+    #[sqlx(flatten)]
+    pub product: Product,
+    pub category_paths: Vec<String>,
+    pub seo_slug: Option<String>,
+    pub seo_meta_title: Option<String>,
+    pub seo_meta_description: Option<String>,
+    pub seo_meta_keywords: Option<Vec<String>>,
+    pub seo_search_terms: Option<Vec<String>>,
+    pub seo_no_index: Option<bool>,
+}
+
+/// Every product with its categories as slug paths from the root, e.g.
+/// `electronics/cables`.
+pub async fn get_export_rows(pool: &PgPool) -> Result<Vec<CatalogExportRow>> {
+    let rows = sqlx::query_as::<_, CatalogExportRow>(
+        "WITH RECURSIVE paths AS (
+             SELECT id, slug::TEXT AS path FROM categories WHERE parent_id IS NULL
+             UNION ALL
+             SELECT c.id, paths.path || '/' || c.slug
+             FROM categories c
+             JOIN paths ON c.parent_id = paths.id
+         )
+         SELECT p.*, b.name AS brand_name,
+                ARRAY(
+                    SELECT paths.path
+                    FROM product_categories pc
+                    JOIN paths ON paths.id = pc.category_id
+                    WHERE pc.product_id = p.id
+                    ORDER BY paths.path
+                ) AS category_paths,
+                s.slug AS seo_slug,
+                s.meta_title AS seo_meta_title,
+                s.meta_description AS seo_meta_description,
+                s.meta_keywords AS seo_meta_keywords,
+                s.search_terms AS seo_search_terms,
+                s.no_index AS seo_no_index
+         FROM products p
+         LEFT JOIN brands b ON b.id = p.brand_id
+         LEFT JOIN product_seo s ON s.product_id = p.id
+         ORDER BY p.id",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
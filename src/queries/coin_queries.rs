@@ -0,0 +1,281 @@
+use sqlx::{PgConnection, PgPool};
+
+use crate::{
+    error::{AppError, Result},
+    models::{CoinLedgerEntry, CoinReason, Order},
+};
+
+/// Coins are worth one tetri each; approved orders earn this share of the
+/// amount paid for coin-eligible products, after coupon and coin discounts.
+const EARN_PERCENT: i32 = 5;
+
+const DEFAULT_PAGE_SIZE: i64 = 20;
+const MAX_PAGE_SIZE: i64 = 100;
+
+async fn lock_user(conn: &mut PgConnection, user_id: i32) -> Result<()> {
+    sqlx::query("SELECT id FROM users WHERE id = $1 FOR UPDATE")
+        .bind(user_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| {
+            AppError::NotFound(format!("მომხმარებელი id-ით {} ვერ მოიძებნა", user_id))
+        })?;
+    Ok(())
+}
+
+async fn balance_in(conn: &mut PgConnection, user_id: i32) -> Result<i64> {
+    let balance = sqlx::query_scalar::<_, i64>(
+        "SELECT COALESCE(SUM(amount), 0)::bigint FROM coin_ledger WHERE user_id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(balance)
+}
+
+pub async fn get_balance(pool: &PgPool, user_id: i32) -> Result<i64> {
+    let mut conn = pool.acquire().await?;
+    balance_in(&mut conn, user_id).await
+}
+
+pub async fn get_ledger(
+    pool: &PgPool,
+    user_id: i32,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Vec<CoinLedgerEntry>> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let offset = offset.unwrap_or(0).max(0);
+
+    let entries = sqlx::query_as::<_, CoinLedgerEntry>(
+        "SELECT * FROM coin_ledger WHERE user_id = $1
+         ORDER BY created_at DESC, id DESC
+         LIMIT $2 OFFSET $3",
+    )
+    .bind(user_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(entries)
+}
+
+pub async fn add_adjustment(
+    pool: &PgPool,
+    user_id: i32,
+    amount: i32,
+    created_by_user_id: i32,
+    comment: Option<&str>,
+) -> Result<CoinLedgerEntry> {
+    let mut tx = pool.begin().await?;
+
+    lock_user(&mut tx, user_id).await?;
+    if balance_in(&mut tx, user_id).await? + i64::from(amount) < 0 {
+        return Err(AppError::BadRequest(
+            "ქოინების ბალანსი არ შეიძლება იყოს უარყოფითი".to_string(),
+        ));
+    }
+
+    let entry = sqlx::query_as::<_, CoinLedgerEntry>(
+        "INSERT INTO coin_ledger (user_id, amount, reason, created_by_user_id, comment)
+         VALUES ($1, $2, $3, $4, $5)
+         RETURNING *",
+    )
+    .bind(user_id)
+    .bind(amount)
+    .bind(CoinReason::Adjustment)
+    .bind(created_by_user_id)
+    .bind(comment)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(entry)
+}
+
+/// Debits coins for a freshly inserted order inside the caller's transaction.
+pub async fn redeem_for_order(
+    conn: &mut PgConnection,
+    user_id: i32,
+    order_db_id: i32,
+    coins: i32,
+) -> Result<()> {
+    lock_user(conn, user_id).await?;
+    if balance_in(conn, user_id).await? < i64::from(coins) {
+        return Err(AppError::BadRequest(
+            "ქოინების ბალანსი არასაკმარისია".to_string(),
+        ));
+    }
+
+    sqlx::query(
+        "INSERT INTO coin_ledger (user_id, amount, reason, order_id)
+         VALUES ($1, $2, $3, $4)",
+    )
+    .bind(user_id)
+    .bind(-coins)
+    .bind(CoinReason::Redeemed)
+    .bind(order_db_id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Credits the coins an approved order earned. Safe to call more than once.
+pub async fn award_for_order(conn: &mut PgConnection, order: &Order) -> Result<()> {
+    let Some(user_id) = order.user_id else {
+        return Ok(());
+    };
+
+    // goods discounts (coupon and redeemed coins) are spread over the lines by
+    // value, so only what was actually paid for eligible products earns
+    sqlx::query(
+        "INSERT INTO coin_ledger (user_id, amount, reason, order_id)
+         SELECT $1, earned, $2, $3
+         FROM (
+             SELECT FLOOR(
+                        GREATEST(l.eligible - d.goods_discount * l.eligible / l.goods, 0) * $4 / 100
+                    )::int AS earned
+             FROM (
+                 SELECT SUM(oi.price_at_purchase * oi.quantity) * 100 AS goods,
+                        COALESCE(SUM(oi.price_at_purchase * oi.quantity)
+                                     FILTER (WHERE p.coins_eligible), 0) * 100 AS eligible
+                 FROM order_items oi
+                 LEFT JOIN products p ON p.id = oi.product_id
+                 WHERE oi.order_id = $3
+             ) l,
+             (
+                 SELECT o.coins_redeemed + COALESCE((
+                            SELECT CASE WHEN c.discount_type = 'free_delivery' THEN 0
+                                        ELSE cr.discount END
+                            FROM coupon_redemptions cr
+                            JOIN coupons c ON c.id = cr.coupon_id
+                            WHERE cr.order_id = o.id
+                        ), o.coupon_discount) AS goods_discount
+                 FROM orders o
+                 WHERE o.id = $3
+             ) d
+             WHERE l.goods > 0
+         ) e
+         WHERE e.earned > 0
+         ON CONFLICT (order_id) WHERE order_id IS NOT NULL AND reason = 'earned'
+         DO NOTHING",
+    )
+    .bind(user_id)
+    .bind(CoinReason::Earned)
+    .bind(order.id)
+    .bind(EARN_PERCENT)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Coins an order currently holds: redeemed minus what was given back.
+async fn held_for_order(conn: &mut PgConnection, order_db_id: i32) -> Result<i64> {
+    let held = sqlx::query_scalar::<_, i64>(
+        "SELECT COALESCE(-SUM(amount), 0)::bigint FROM coin_ledger
+         WHERE order_id = $1 AND reason IN ('redeemed', 'returned')",
+    )
+    .bind(order_db_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(held)
+}
+
+/// Debits the coins of a declined or expired order again when it gets paid
+/// after all. Fails if the customer has spent them in the meantime.
+pub async fn reclaim_for_order(conn: &mut PgConnection, order: &Order) -> Result<()> {
+    let Some(user_id) = order.user_id else {
+        return Ok(());
+    };
+    if order.coins_redeemed <= 0 {
+        return Ok(());
+    }
+
+    lock_user(conn, user_id).await?;
+    if held_for_order(conn, order.id).await? >= i64::from(order.coins_redeemed) {
+        return Ok(());
+    }
+
+    redeem_for_order(conn, user_id, order.id, order.coins_redeemed).await
+}
+
+/// Gives back coins redeemed on an order that never got paid. Safe to call
+/// more than once.
+pub async fn return_for_order(conn: &mut PgConnection, order: &Order) -> Result<()> {
+    let Some(user_id) = order.user_id else {
+        return Ok(());
+    };
+    if order.coins_redeemed <= 0 {
+        return Ok(());
+    }
+
+    lock_user(conn, user_id).await?;
+    let held = held_for_order(conn, order.id).await?;
+    if held <= 0 {
+        return Ok(());
+    }
+
+    sqlx::query(
+        "INSERT INTO coin_ledger (user_id, amount, reason, order_id)
+         VALUES ($1, $2, $3, $4)",
+    )
+    .bind(user_id)
+    .bind(held as i32)
+    .bind(CoinReason::Returned)
+    .bind(order.id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Takes back the coins an order earned: all of them, or with `refunded` set
+/// the share matching that many tetri refunded in total. Earlier clawbacks
+/// count towards it, so repeated calls never take more. The balance may go
+/// negative if the coins were already spent.
+pub async fn clawback_for_order(
+    conn: &mut PgConnection,
+    order: &Order,
+    refunded: Option<i64>,
+) -> Result<()> {
+    let Some(user_id) = order.user_id else {
+        return Ok(());
+    };
+
+    lock_user(conn, user_id).await?;
+    let (earned, clawed) = sqlx::query_as::<_, (i64, i64)>(
+        "SELECT COALESCE(SUM(amount) FILTER (WHERE reason = 'earned'), 0)::bigint,
+                COALESCE(-SUM(amount) FILTER (WHERE reason = 'clawback'), 0)::bigint
+         FROM coin_ledger WHERE order_id = $1",
+    )
+    .bind(order.id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let due = match refunded {
+        Some(refunded) if order.amount > 0 => {
+            (earned * refunded / i64::from(order.amount)).min(earned)
+        }
+        _ => earned,
+    };
+    let take = due - clawed;
+    if take <= 0 {
+        return Ok(());
+    }
+
+    sqlx::query(
+        "INSERT INTO coin_ledger (user_id, amount, reason, order_id)
+         VALUES ($1, $2, $3, $4)",
+    )
+    .bind(user_id)
+    .bind(-(take as i32))
+    .bind(CoinReason::Clawback)
+    .bind(order.id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
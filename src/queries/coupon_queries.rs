@@ -0,0 +1,299 @@
+use std::collections::HashSet;
+
+use sqlx::{PgConnection, PgPool};
+
+use crate::{
+    error::{AppError, Result},
+    models::{AppliedCoupon, Coupon, CouponQuery, CouponRequest, CouponWithUsage},
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 20;
+const MAX_PAGE_SIZE: i64 = 100;
+
+/// Orders in these states gave their coupon use back.
+const RELEASED_STATUSES: &str = "('declined', 'expired', 'cancelled', 'reversed')";
+
+pub async fn find_by_code(pool: &PgPool, code: &str) -> Result<Option<Coupon>> {
+    let coupon = sqlx::query_as::<_, Coupon>("SELECT * FROM coupons WHERE UPPER(code) = UPPER($1)")
+        .bind(code)
+        .fetch_optional(pool)
+        .await?;
+    Ok(coupon)
+}
+
+pub async fn find_by_id(pool: &PgPool, id: i32) -> Result<Option<Coupon>> {
+    let coupon = sqlx::query_as::<_, Coupon>("SELECT * FROM coupons WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(coupon)
+}
+
+/// Uses that still count against the limits. With `user_id`/`email` set, only
+/// that customer's uses are counted.
+async fn count_uses(
+    conn: &mut PgConnection,
+    coupon_id: i32,
+    customer: Option<(Option<i32>, &str)>,
+) -> Result<i64> {
+    let (user_id, email) = match customer {
+        Some((user_id, email)) => (user_id, Some(email)),
+        None => (None, None),
+    };
+
+    let count = sqlx::query_scalar::<_, i64>(&format!(
+        "SELECT COUNT(*) FROM coupon_redemptions cr
+         JOIN orders o ON o.id = cr.order_id
+         WHERE cr.coupon_id = $1
+           AND o.status NOT IN {RELEASED_STATUSES}
+           AND ($3::text IS NULL OR cr.user_id = $2 OR LOWER(cr.email) = LOWER($3))"
+    ))
+    .bind(coupon_id)
+    .bind(user_id)
+    .bind(email)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(count)
+}
+
+/// Fails with the reason shown to the customer if the coupon has no uses left
+/// overall or for this customer.
+pub async fn check_limits(
+    conn: &mut PgConnection,
+    coupon: &Coupon,
+    user_id: Option<i32>,
+    email: &str,
+) -> Result<()> {
+    if let Some(limit) = coupon.usage_limit {
+        let used = count_uses(conn, coupon.id, None).await?;
+        if used >= i64::from(limit) {
+            return Err(AppError::BadRequest(
+                "პრომო კოდის გამოყენების ლიმიტი ამოიწურა".to_string(),
+            ));
+        }
+    }
+
+    if let Some(limit) = coupon.per_user_limit {
+        let used = count_uses(conn, coupon.id, Some((user_id, email))).await?;
+        if used >= i64::from(limit) {
+            return Err(AppError::BadRequest(
+                "ეს პრომო კოდი უკვე გამოყენებული გაქვთ".to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn check_limits_now(
+    pool: &PgPool,
+    coupon: &Coupon,
+    user_id: Option<i32>,
+    email: &str,
+) -> Result<()> {
+    let mut conn = pool.acquire().await?;
+    check_limits(&mut conn, coupon, user_id, email).await
+}
+
+/// Which of `product_ids` fall under the coupon's category (including
+/// subcategories) and brand scope.
+pub async fn find_eligible_product_ids(
+    pool: &PgPool,
+    coupon: &Coupon,
+    product_ids: &[String],
+) -> Result<HashSet<String>> {
+    let ids = sqlx::query_scalar::<_, String>(
+        "WITH RECURSIVE scope AS (
+             SELECT id FROM categories WHERE id = ANY($2)
+             UNION
+             SELECT c.id FROM categories c JOIN scope s ON c.parent_id = s.id
+         )
+         SELECT p.id FROM products p
+         WHERE p.id = ANY($1)
+           AND (cardinality($2::int[]) = 0 OR EXISTS (
+                SELECT 1 FROM product_categories pc
+                WHERE pc.product_id = p.id AND pc.category_id IN (SELECT id FROM scope)
+           ))
+           AND (cardinality($3::int[]) = 0 OR p.brand_id = ANY($3))",
+    )
+    .bind(product_ids)
+    .bind(&coupon.category_ids)
+    .bind(&coupon.brand_ids)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ids.into_iter().collect())
+}
+
+/// Records the coupon against a freshly inserted order inside the caller's
+/// transaction. Limits are checked again under a lock so concurrent
+/// checkouts can't both take the last use.
+pub async fn redeem_for_order(
+    conn: &mut PgConnection,
+    applied: &AppliedCoupon,
+    order_db_id: i32,
+    user_id: Option<i32>,
+    email: &str,
+) -> Result<()> {
+    let coupon = sqlx::query_as::<_, Coupon>("SELECT * FROM coupons WHERE id = $1 FOR UPDATE")
+        .bind(applied.coupon_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("პრომო კოდი აღარ არსებობს".to_string()))?;
+
+    check_limits(conn, &coupon, user_id, email).await?;
+
+    sqlx::query(
+        "INSERT INTO coupon_redemptions (coupon_id, order_id, user_id, email, discount)
+         VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(coupon.id)
+    .bind(order_db_id)
+    .bind(user_id)
+    .bind(email)
+    .bind(applied.discount)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Takes the coupon use of a declined or expired order back when it gets paid
+/// after all. Must run before the order leaves its released status, and fails
+/// if the coupon has no uses left by now.
+pub async fn reclaim_for_order(conn: &mut PgConnection, order_db_id: i32) -> Result<()> {
+    #[derive(sqlx::FromRow)]
+    struct Redemption {
+        coupon_id: i32,
+        user_id: Option<i32>,
+        email: String,
+    }
+
+    let redemption = sqlx::query_as::<_, Redemption>(
+        "SELECT coupon_id, user_id, email FROM coupon_redemptions WHERE order_id = $1",
+    )
+    .bind(order_db_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let Some(redemption) = redemption else {
+        return Ok(());
+    };
+
+    let coupon = sqlx::query_as::<_, Coupon>("SELECT * FROM coupons WHERE id = $1 FOR UPDATE")
+        .bind(redemption.coupon_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    check_limits(conn, &coupon, redemption.user_id, &redemption.email).await
+}
+
+pub async fn search_coupons(
+    pool: &PgPool,
+    params: &CouponQuery,
+) -> Result<(Vec<CouponWithUsage>, i64, i64, i64)> {
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let offset = params.offset.unwrap_or(0).max(0);
+
+    #[derive(sqlx::FromRow)]
+    struct Row {
+        #[sqlx(flatten)]
+        coupon: CouponWithUsage,
+        total_count: i64,
+    }
+
+    let rows = sqlx::query_as::<_, Row>(&format!(
+        "SELECT c.*, COUNT(*) OVER() AS total_count,
+                (SELECT COUNT(*) FROM coupon_redemptions cr
+                 JOIN orders o ON o.id = cr.order_id
+                 WHERE cr.coupon_id = c.id AND o.status NOT IN {RELEASED_STATUSES}) AS times_used
+         FROM coupons c
+         WHERE ($1::text IS NULL OR c.code ILIKE '%' || $1 || '%' OR c.description ILIKE '%' || $1 || '%')
+           AND ($2::bool IS NULL OR c.enabled = $2)
+         ORDER BY c.created_at DESC, c.id DESC
+         LIMIT $3 OFFSET $4"
+    ))
+    .bind(params.search.as_deref().map(str::trim).filter(|s| !s.is_empty()))
+    .bind(params.enabled)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    let total = rows.first().map(|r| r.total_count).unwrap_or(0);
+    let coupons = rows.into_iter().map(|r| r.coupon).collect();
+
+    Ok((coupons, total, limit, offset))
+}
+
+pub async fn create_coupon(pool: &PgPool, req: &CouponRequest) -> Result<Coupon> {
+    let coupon = sqlx::query_as::<_, Coupon>(
+        "INSERT INTO coupons (code, description, discount_type, value, min_subtotal,
+             category_ids, brand_ids, usage_limit, per_user_limit, starts_at, ends_at, enabled)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+         RETURNING *",
+    )
+    .bind(req.code.trim())
+    .bind(&req.description)
+    .bind(req.discount_type)
+    .bind(req.value)
+    .bind(req.min_subtotal)
+    .bind(&req.category_ids)
+    .bind(&req.brand_ids)
+    .bind(req.usage_limit)
+    .bind(req.per_user_limit)
+    .bind(req.starts_at)
+    .bind(req.ends_at)
+    .bind(req.enabled.unwrap_or(true))
+    .fetch_one(pool)
+    .await?;
+
+    Ok(coupon)
+}
+
+pub async fn update_coupon(pool: &PgPool, id: i32, req: &CouponRequest) -> Result<Coupon> {
+    let coupon = sqlx::query_as::<_, Coupon>(
+        "UPDATE coupons SET
+             code = $1, description = $2, discount_type = $3, value = $4, min_subtotal = $5,
+             category_ids = $6, brand_ids = $7, usage_limit = $8, per_user_limit = $9,
+             starts_at = $10, ends_at = $11, enabled = $12, updated_at = NOW()
+         WHERE id = $13
+         RETURNING *",
+    )
+    .bind(req.code.trim())
+    .bind(&req.description)
+    .bind(req.discount_type)
+    .bind(req.value)
+    .bind(req.min_subtotal)
+    .bind(&req.category_ids)
+    .bind(&req.brand_ids)
+    .bind(req.usage_limit)
+    .bind(req.per_user_limit)
+    .bind(req.starts_at)
+    .bind(req.ends_at)
+    .bind(req.enabled.unwrap_or(true))
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(coupon)
+}
+
+/// Coupons that were already redeemed can only be disabled.
+pub async fn delete_coupon(pool: &PgPool, id: i32) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM coupons WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|err| match &err {
+            sqlx::Error::Database(db) if db.is_foreign_key_violation() => AppError::Conflict(
+                "პრომო კოდი უკვე გამოყენებულია, შესაძლებელია მხოლოდ მისი გათიშვა".to_string(),
+            ),
+            _ => AppError::DatabaseError(err),
+        })?;
+    Ok(result.rows_affected())
+}
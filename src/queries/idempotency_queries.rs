@@ -0,0 +1,123 @@
+use sqlx::PgPool;
+
+use crate::{
+    error::{AppError, Result},
+    models::{IdempotencyClaim, IdempotencyScope},
+};
+
+/// Keys that never completed are freed after this long, so a request that
+/// crashed mid-way doesn't block retries until the key expires.
+const STALE_CLAIM_MINUTES: i32 = 10;
+
+/// Takes `key` for a request, or reports what became of an earlier request
+/// with it. A key is only ever answered for the same caller sending the same
+/// request body.
+pub async fn claim(
+    pool: &PgPool,
+    scope: IdempotencyScope,
+    key: &str,
+    user_id: Option<i32>,
+    fingerprint: &str,
+) -> Result<IdempotencyClaim> {
+    let inserted = sqlx::query(
+        "INSERT INTO idempotency_keys (scope, idempotency_key, user_id, request_fingerprint)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (scope, idempotency_key) DO NOTHING",
+    )
+    .bind(scope)
+    .bind(key)
+    .bind(user_id)
+    .bind(fingerprint)
+    .execute(pool)
+    .await?;
+
+    if inserted.rows_affected() > 0 {
+        return Ok(IdempotencyClaim::Acquired);
+    }
+
+    #[derive(sqlx::FromRow)]
+    struct Existing {
+        user_id: Option<i32>,
+        request_fingerprint: Option<String>,
+        response: Option<serde_json::Value>,
+        response_status: i16,
+    }
+
+    let existing = sqlx::query_as::<_, Existing>(
+        "SELECT user_id, request_fingerprint, response, response_status FROM idempotency_keys
+         WHERE scope = $1 AND idempotency_key = $2",
+    )
+    .bind(scope)
+    .bind(key)
+    .fetch_optional(pool)
+    .await?;
+
+    match existing {
+        Some(e)
+            if e.user_id != user_id || e.request_fingerprint.as_deref() != Some(fingerprint) =>
+        {
+            Err(AppError::Conflict(
+                "Idempotency-Key უკვე გამოყენებულია".to_string(),
+            ))
+        }
+        Some(Existing {
+            response: Some(response),
+            response_status,
+            ..
+        }) => Ok(IdempotencyClaim::Completed(
+            response_status as u16,
+            response,
+        )),
+        _ => Ok(IdempotencyClaim::InProgress),
+    }
+}
+
+pub async fn complete(
+    pool: &PgPool,
+    scope: IdempotencyScope,
+    key: &str,
+    status: u16,
+    response: &serde_json::Value,
+) -> Result<()> {
+    sqlx::query(
+        "UPDATE idempotency_keys
+         SET response = $3, response_status = $4, completed_at = NOW()
+         WHERE scope = $1 AND idempotency_key = $2",
+    )
+    .bind(scope)
+    .bind(key)
+    .bind(response)
+    .bind(status as i16)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Frees a key whose request failed so the client can retry it.
+pub async fn release(pool: &PgPool, scope: IdempotencyScope, key: &str) -> Result<()> {
+    sqlx::query(
+        "DELETE FROM idempotency_keys
+         WHERE scope = $1 AND idempotency_key = $2 AND response IS NULL",
+    )
+    .bind(scope)
+    .bind(key)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete_expired(pool: &PgPool, ttl_hours: i32) -> Result<u64> {
+    let result = sqlx::query(
+        "DELETE FROM idempotency_keys
+         WHERE created_at < NOW() - make_interval(hours => $1)
+            OR (response IS NULL AND created_at < NOW() - make_interval(mins => $2))",
+    )
+    .bind(ttl_hours)
+    .bind(STALE_CLAIM_MINUTES)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,624 @@
+use std::collections::HashMap;
+
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    models::{
+        CreateWarehouseRequest, LowStockVariant, StockCount, StockMovement, StockMovementKind,
+        StockMovementQuery, UpdateWarehouseRequest, Warehouse, WarehouseStockLine,
+    },
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+fn conflict_or(err: sqlx::Error) -> AppError {
+    match &err {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            AppError::Conflict("საწყობი ამ კოდით ან ამ როლით უკვე არსებობს".to_string())
+        }
+        _ => AppError::DatabaseError(err),
+    }
+}
+
+pub struct NewStockMovement<'a> {
+    pub variant_id: i32,
+    pub warehouse_id: i32,
+    /// Signed: positive adds units to the location, negative takes them out.
+    pub quantity: i32,
+    pub kind: StockMovementKind,
+    pub reason: Option<&'a str>,
+    pub order_id: Option<i32>,
+    pub transfer_id: Option<Uuid>,
+    pub created_by_user_id: Option<i32>,
+}
+
+/// Applies a movement to the location's stock and appends it to the ledger.
+/// Returns `None` without writing anything when the location doesn't hold
+/// enough units to take out.
+pub async fn record_movement(
+    conn: &mut PgConnection,
+    movement: &NewStockMovement<'_>,
+) -> Result<Option<StockMovement>> {
+    let applied = if movement.quantity > 0 {
+        sqlx::query(
+            "INSERT INTO warehouse_stock (warehouse_id, variant_id, quantity)
+             VALUES ($1, $2, $3)
+             ON CONFLICT (warehouse_id, variant_id)
+             DO UPDATE SET quantity = warehouse_stock.quantity + EXCLUDED.quantity, updated_at = NOW()",
+        )
+        .bind(movement.warehouse_id)
+        .bind(movement.variant_id)
+        .bind(movement.quantity)
+        .execute(&mut *conn)
+        .await?
+    } else {
+        sqlx::query(
+            "UPDATE warehouse_stock SET quantity = quantity + $3, updated_at = NOW()
+             WHERE warehouse_id = $1 AND variant_id = $2 AND quantity + $3 >= 0",
+        )
+        .bind(movement.warehouse_id)
+        .bind(movement.variant_id)
+        .bind(movement.quantity)
+        .execute(&mut *conn)
+        .await?
+    };
+
+    if applied.rows_affected() == 0 {
+        return Ok(None);
+    }
+
+    let recorded = sqlx::query_as::<_, StockMovement>(
+        "INSERT INTO stock_movements
+             (variant_id, warehouse_id, quantity, kind, reason, order_id, transfer_id, created_by_user_id)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         RETURNING *",
+    )
+    .bind(movement.variant_id)
+    .bind(movement.warehouse_id)
+    .bind(movement.quantity)
+    .bind(movement.kind)
+    .bind(movement.reason)
+    .bind(movement.order_id)
+    .bind(movement.transfer_id)
+    .bind(movement.created_by_user_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(Some(recorded))
+}
+
+/// Rejects ids that aren't existing variants.
+pub async fn ensure_variants_exist(conn: &mut PgConnection, variant_ids: &[i32]) -> Result<()> {
+    let missing = sqlx::query_scalar::<_, i32>(
+        "SELECT id FROM unnest($1::int[]) AS ids(id)
+         WHERE NOT EXISTS (SELECT 1 FROM product_variants pv WHERE pv.id = ids.id)
+         LIMIT 1",
+    )
+    .bind(variant_ids)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    match missing {
+        Some(id) => Err(AppError::BadRequest(format!(
+            "ვარიანტი {} ვერ მოიძებნა",
+            id
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// The enabled location that ships orders, or serves store pickups when
+/// `pickup` is set.
+pub async fn fulfillment_warehouse_id(
+    conn: &mut PgConnection,
+    pickup: bool,
+) -> Result<Option<i32>> {
+    let id = sqlx::query_scalar::<_, i32>(
+        "SELECT id FROM warehouses
+         WHERE enabled = true AND (CASE WHEN $1 THEN fulfills_pickup ELSE fulfills_shipping END)",
+    )
+    .bind(pickup)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    Ok(id)
+}
+
+/// Books the opening stock of a new variant into the shipping location.
+pub async fn receive_opening_stock(
+    conn: &mut PgConnection,
+    variant_id: i32,
+    quantity: i32,
+    created_by_user_id: i32,
+) -> Result<()> {
+    if quantity <= 0 {
+        return Ok(());
+    }
+    let warehouse_id = fulfillment_warehouse_id(conn, false)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("მიწოდების საწყობი არ არის მითითებული".to_string()))?;
+
+    let movement = NewStockMovement {
+        variant_id,
+        warehouse_id,
+        quantity,
+        kind: StockMovementKind::Receipt,
+        reason: None,
+        order_id: None,
+        transfer_id: None,
+        created_by_user_id: Some(created_by_user_id),
+    };
+    record_movement(conn, &movement).await?;
+
+    Ok(())
+}
+
+/// Takes a sold line out of stock, starting at `preferred` and spilling over to
+/// other enabled locations. Units held by other orders' live reservations are
+/// not available. Returns `false` when there aren't enough units in total, in
+/// which case nothing is deducted.
+pub async fn deduct_for_sale(
+    conn: &mut PgConnection,
+    variant_id: i32,
+    quantity: i32,
+    preferred: Option<i32>,
+    order_id: i32,
+) -> Result<bool> {
+    // the same lock checkout takes before reserving, so holds can't change
+    // underneath
+    sqlx::query("SELECT id FROM product_variants WHERE id = $1 FOR UPDATE")
+        .bind(variant_id)
+        .execute(&mut *conn)
+        .await?;
+
+    let held_by_others = sqlx::query_scalar::<_, i64>(
+        "SELECT COALESCE(SUM(quantity), 0)::bigint FROM stock_reservations
+         WHERE variant_id = $1 AND order_id <> $2 AND expires_at > NOW()",
+    )
+    .bind(variant_id)
+    .bind(order_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let locations = sqlx::query_as::<_, (i32, i32)>(
+        "SELECT ws.warehouse_id, ws.quantity
+         FROM warehouse_stock ws
+         JOIN warehouses w ON w.id = ws.warehouse_id
+         WHERE ws.variant_id = $1 AND ws.quantity > 0 AND w.enabled = true
+         ORDER BY ws.warehouse_id IS NOT DISTINCT FROM $2 DESC, ws.warehouse_id
+         FOR UPDATE OF ws",
+    )
+    .bind(variant_id)
+    .bind(preferred)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let on_hand: i64 = locations.iter().map(|&(_, q)| i64::from(q)).sum();
+    if on_hand - held_by_others < i64::from(quantity) {
+        return Ok(false);
+    }
+
+    let mut remaining = quantity;
+    let mut plan = Vec::new();
+    for (warehouse_id, available) in locations {
+        if remaining == 0 {
+            break;
+        }
+        let take = available.min(remaining);
+        plan.push((warehouse_id, take));
+        remaining -= take;
+    }
+    if remaining > 0 {
+        return Ok(false);
+    }
+
+    for (warehouse_id, take) in plan {
+        let movement = NewStockMovement {
+            variant_id,
+            warehouse_id,
+            quantity: -take,
+            kind: StockMovementKind::Sale,
+            reason: None,
+            order_id: Some(order_id),
+            transfer_id: None,
+            created_by_user_id: None,
+        };
+        record_movement(conn, &movement).await?;
+    }
+
+    Ok(true)
+}
+
+/// Puts units of an order line back where the order took them from. Units the
+/// ledger can't place (orders deducted before it existed) go to the shipping
+/// location.
+pub async fn return_for_order(
+    conn: &mut PgConnection,
+    variant_id: i32,
+    quantity: i32,
+    order_id: i32,
+) -> Result<()> {
+    let sold = sqlx::query_as::<_, (i32, i32)>(
+        "SELECT warehouse_id, (-SUM(quantity))::int
+         FROM stock_movements
+         WHERE order_id = $1 AND variant_id = $2 AND kind IN ('sale', 'return')
+         GROUP BY warehouse_id
+         HAVING SUM(quantity) < 0
+         ORDER BY warehouse_id",
+    )
+    .bind(order_id)
+    .bind(variant_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut remaining = quantity;
+    let mut plan = Vec::new();
+    for (warehouse_id, outstanding) in sold {
+        if remaining == 0 {
+            break;
+        }
+        let put = outstanding.min(remaining);
+        plan.push((warehouse_id, put));
+        remaining -= put;
+    }
+    if remaining > 0 {
+        let Some(warehouse_id) = fulfillment_warehouse_id(conn, false).await? else {
+            tracing::warn!(
+                "no shipping warehouse to return {remaining} units of variant {variant_id}"
+            );
+            return Ok(());
+        };
+        plan.push((warehouse_id, remaining));
+    }
+
+    for (warehouse_id, put) in plan {
+        let movement = NewStockMovement {
+            variant_id,
+            warehouse_id,
+            quantity: put,
+            kind: StockMovementKind::Return,
+            reason: None,
+            order_id: Some(order_id),
+            transfer_id: None,
+            created_by_user_id: None,
+        };
+        record_movement(conn, &movement).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn get_warehouses(pool: &PgPool) -> Result<Vec<Warehouse>> {
+    let warehouses = sqlx::query_as::<_, Warehouse>("SELECT * FROM warehouses ORDER BY id")
+        .fetch_all(pool)
+        .await?;
+
+    Ok(warehouses)
+}
+
+pub async fn find_warehouse(pool: &PgPool, id: i32) -> Result<Option<Warehouse>> {
+    let warehouse = sqlx::query_as::<_, Warehouse>("SELECT * FROM warehouses WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(warehouse)
+}
+
+pub async fn create_warehouse(pool: &PgPool, req: &CreateWarehouseRequest) -> Result<Warehouse> {
+    let warehouse = sqlx::query_as::<_, Warehouse>(
+        "INSERT INTO warehouses (code, name, kind, address, fulfills_shipping, fulfills_pickup)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         RETURNING *",
+    )
+    .bind(&req.code)
+    .bind(&req.name)
+    .bind(req.kind)
+    .bind(&req.address)
+    .bind(req.fulfills_shipping)
+    .bind(req.fulfills_pickup)
+    .fetch_one(pool)
+    .await
+    .map_err(conflict_or)?;
+
+    Ok(warehouse)
+}
+
+pub async fn update_warehouse(
+    pool: &PgPool,
+    id: i32,
+    req: &UpdateWarehouseRequest,
+) -> Result<Option<Warehouse>> {
+    let warehouse = sqlx::query_as::<_, Warehouse>(
+        "UPDATE warehouses SET
+             name = COALESCE($2, name),
+             kind = COALESCE($3, kind),
+             address = COALESCE($4, address),
+             fulfills_shipping = COALESCE($5, fulfills_shipping),
+             fulfills_pickup = COALESCE($6, fulfills_pickup),
+             enabled = COALESCE($7, enabled),
+             updated_at = NOW()
+         WHERE id = $1
+         RETURNING *",
+    )
+    .bind(id)
+    .bind(&req.name)
+    .bind(req.kind)
+    .bind(&req.address)
+    .bind(req.fulfills_shipping)
+    .bind(req.fulfills_pickup)
+    .bind(req.enabled)
+    .fetch_optional(pool)
+    .await
+    .map_err(conflict_or)?;
+
+    Ok(warehouse)
+}
+
+pub async fn get_warehouse_stock(
+    pool: &PgPool,
+    warehouse_id: i32,
+) -> Result<Vec<WarehouseStockLine>> {
+    let lines = sqlx::query_as::<_, WarehouseStockLine>(
+        "SELECT ws.variant_id, pv.sku, pv.product_id, p.name AS product_name, pv.color,
+                ws.quantity, ws.updated_at
+         FROM warehouse_stock ws
+         JOIN product_variants pv ON pv.id = ws.variant_id
+         JOIN products p ON p.id = pv.product_id
+         WHERE ws.warehouse_id = $1
+         ORDER BY p.name, pv.sku",
+    )
+    .bind(warehouse_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(lines)
+}
+
+pub async fn receive_stock(
+    pool: &PgPool,
+    movement: &NewStockMovement<'_>,
+) -> Result<Option<StockMovement>> {
+    let mut tx = pool.begin().await?;
+    ensure_variants_exist(&mut tx, &[movement.variant_id]).await?;
+    let recorded = record_movement(&mut tx, movement).await?;
+    tx.commit().await?;
+    Ok(recorded)
+}
+
+/// Moves units between two locations as a pair of ledger rows sharing a
+/// `transfer_id`. Returns `None` when the source doesn't hold enough units.
+pub async fn transfer_stock(
+    pool: &PgPool,
+    variant_id: i32,
+    from_warehouse_id: i32,
+    to_warehouse_id: i32,
+    quantity: i32,
+    reason: Option<&str>,
+    created_by_user_id: i32,
+) -> Result<Option<Vec<StockMovement>>> {
+    let mut tx = pool.begin().await?;
+    ensure_variants_exist(&mut tx, &[variant_id]).await?;
+
+    let mut movement = NewStockMovement {
+        variant_id,
+        warehouse_id: from_warehouse_id,
+        quantity: -quantity,
+        kind: StockMovementKind::Transfer,
+        reason,
+        order_id: None,
+        transfer_id: Some(Uuid::new_v4()),
+        created_by_user_id: Some(created_by_user_id),
+    };
+    let Some(outgoing) = record_movement(&mut tx, &movement).await? else {
+        tx.rollback().await?;
+        return Ok(None);
+    };
+
+    movement.warehouse_id = to_warehouse_id;
+    movement.quantity = quantity;
+    let incoming = record_movement(&mut tx, &movement).await?;
+
+    tx.commit().await?;
+    Ok(Some(std::iter::once(outgoing).chain(incoming).collect()))
+}
+
+/// Sets each counted variant to its counted quantity, recording the
+/// difference as an adjustment. Returns the adjustments that were made.
+pub async fn apply_stock_take(
+    pool: &PgPool,
+    warehouse_id: i32,
+    counts: &[StockCount],
+    reason: Option<&str>,
+    created_by_user_id: i32,
+) -> Result<Vec<StockMovement>> {
+    let mut tx = pool.begin().await?;
+
+    let mut variant_ids: Vec<i32> = counts.iter().map(|c| c.variant_id).collect();
+    variant_ids.sort_unstable();
+    ensure_variants_exist(&mut tx, &variant_ids).await?;
+
+    let current: HashMap<i32, i32> = sqlx::query_as::<_, (i32, i32)>(
+        "SELECT variant_id, quantity FROM warehouse_stock
+         WHERE warehouse_id = $1 AND variant_id = ANY($2)
+         ORDER BY variant_id
+         FOR UPDATE",
+    )
+    .bind(warehouse_id)
+    .bind(&variant_ids)
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .collect();
+
+    let mut adjustments = Vec::new();
+    for count in counts {
+        let delta = count.quantity - current.get(&count.variant_id).copied().unwrap_or(0);
+        if delta == 0 {
+            continue;
+        }
+        let movement = NewStockMovement {
+            variant_id: count.variant_id,
+            warehouse_id,
+            quantity: delta,
+            kind: StockMovementKind::Adjustment,
+            reason,
+            order_id: None,
+            transfer_id: None,
+            created_by_user_id: Some(created_by_user_id),
+        };
+        adjustments.extend(record_movement(&mut tx, &movement).await?);
+    }
+
+    tx.commit().await?;
+    Ok(adjustments)
+}
+
+pub async fn search_movements(
+    pool: &PgPool,
+    params: &StockMovementQuery,
+) -> Result<(Vec<StockMovement>, i64, i64, i64)> {
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let offset = params.offset.unwrap_or(0).max(0);
+
+    let mut qb = sqlx::QueryBuilder::<sqlx::Postgres>::new(
+        "SELECT sm.*, COUNT(*) OVER() AS total_count FROM stock_movements sm WHERE 1=1",
+    );
+
+    if let Some(warehouse_id) = params.warehouse_id {
+        qb.push(" AND sm.warehouse_id = ");
+        qb.push_bind(warehouse_id);
+    }
+    if let Some(variant_id) = params.variant_id {
+        qb.push(" AND sm.variant_id = ");
+        qb.push_bind(variant_id);
+    }
+    if let Some(ref product_id) = params.product_id {
+        qb.push(" AND sm.variant_id IN (SELECT id FROM product_variants WHERE product_id = ");
+        qb.push_bind(product_id);
+        qb.push(")");
+    }
+    if let Some(order_id) = params.order_id {
+        qb.push(" AND sm.order_id = ");
+        qb.push_bind(order_id);
+    }
+    if let Some(kind) = params.kind {
+        qb.push(" AND sm.kind = ");
+        qb.push_bind(kind);
+    }
+
+    qb.push(" ORDER BY sm.created_at DESC, sm.id DESC LIMIT ");
+    qb.push_bind(limit);
+    qb.push(" OFFSET ");
+    qb.push_bind(offset);
+
+    #[derive(sqlx::FromRow)]
+    struct Row {
+        #[sqlx(flatten)]
+        movement: StockMovement,
+        total_count: i64,
+    }
+
+    let rows = qb.build_query_as::<Row>().fetch_all(pool).await?;
+    let total = rows.first().map(|r| r.total_count).unwrap_or(0);
+    let movements = rows.into_iter().map(|r| r.movement).collect();
+
+    Ok((movements, total, limit, offset))
+}
+
+/// Variants of enabled products at or below their product's threshold, most
+/// urgent first. Sales only count orders whose stock was taken and not given
+/// back.
+pub async fn get_low_stock(
+    pool: &PgPool,
+    default_threshold: i32,
+    days: i32,
+) -> Result<Vec<LowStockVariant>> {
+    let variants = sqlx::query_as::<_, LowStockVariant>(
+        "WITH sold_lines AS (
+             SELECT oi.variant_id, oi.quantity, oi.bundle_items
+             FROM order_items oi
+             JOIN orders o ON o.id = oi.order_id
+             WHERE o.stock_deducted_at >= NOW() - make_interval(days => $2)
+               AND o.stock_restored_at IS NULL
+         ),
+         -- bundle sales count against the components they took stock from
+         sales AS (
+             SELECT variant_id, SUM(units)::BIGINT AS units_sold
+             FROM (
+                 SELECT variant_id, quantity AS units
+                 FROM sold_lines
+                 WHERE bundle_items IS NULL AND variant_id IS NOT NULL
+                 UNION ALL
+                 SELECT bi.variant_id, sl.quantity * bi.quantity
+                 FROM sold_lines sl,
+                      jsonb_to_recordset(sl.bundle_items) AS bi(variant_id INTEGER, quantity INTEGER)
+             ) units
+             GROUP BY variant_id
+         ),
+         report AS (
+             SELECT v.id AS variant_id,
+                    v.sku,
+                    v.color,
+                    p.id AS product_id,
+                    p.name,
+                    v.stock,
+                    COALESCE(p.low_stock_threshold, $1) AS threshold,
+                    COALESCE(s.units_sold, 0) AS units_sold
+             FROM product_variants v
+             JOIN products p ON p.id = v.product_id
+             LEFT JOIN sales s ON s.variant_id = v.id
+             WHERE p.enabled AND v.stock <= COALESCE(p.low_stock_threshold, $1)
+               AND NOT EXISTS (SELECT 1 FROM bundle_components bc WHERE bc.bundle_id = p.id)
+         )
+         SELECT variant_id, sku, color, product_id, name, stock, threshold, units_sold,
+                ROUND(units_sold::NUMERIC / $2, 2) AS daily_velocity,
+                CASE WHEN units_sold > 0
+                     THEN ROUND(stock::NUMERIC * $2 / units_sold, 1)
+                END AS days_of_cover,
+                GREATEST(units_sold + threshold - stock, 0) AS suggested_reorder
+         FROM report
+         ORDER BY days_of_cover ASC NULLS LAST, units_sold DESC, stock ASC, sku",
+    )
+    .bind(default_threshold)
+    .bind(days)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(variants)
+}
+
+/// Marks the digest for `date` as sent. Returns false when it already went
+/// out that day.
+pub async fn claim_low_stock_digest(
+    pool: &PgPool,
+    date: chrono::NaiveDate,
+    product_count: i32,
+) -> Result<bool> {
+    let result = sqlx::query(
+        "INSERT INTO low_stock_digests (digest_date, product_count)
+         VALUES ($1, $2)
+         ON CONFLICT (digest_date) DO NOTHING",
+    )
+    .bind(date)
+    .bind(product_count)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn release_low_stock_digest(pool: &PgPool, date: chrono::NaiveDate) -> Result<()> {
+    sqlx::query("DELETE FROM low_stock_digests WHERE digest_date = $1")
+        .bind(date)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
@@ -1,8 +1,27 @@
 pub mod admin_queries;
 pub mod blog_queries;
+pub mod bundle_queries;
+pub mod campaign_queries;
+pub mod cart_queries;
+pub mod catalog_queries;
 pub mod category_queries;
+pub mod coin_queries;
+pub mod coupon_queries;
 pub mod email_queries;
+pub mod idempotency_queries;
+pub mod inventory_queries;
 pub mod order_queries;
+pub mod price_queries;
+pub mod product_alert_queries;
 pub mod products_queries;
+pub mod question_queries;
+pub mod recommendation_queries;
+pub mod reconciliation_queries;
+pub mod refund_queries;
+pub mod reservation_queries;
+pub mod review_queries;
+pub mod search_queries;
 pub mod task_queries;
 pub mod user_queries;
+pub mod variant_queries;
+pub mod wishlist_queries;
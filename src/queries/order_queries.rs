@@ -1,15 +1,62 @@
 use rust_decimal::Decimal;
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool, types::Json};
 
 use crate::{
-    error::Result,
+    error::{AppError, Result},
     models::{
-        AdminOrderRequest, CheckoutRequest, CustomerInfo, Order, OrderCommentImage, OrderItem,
-        OrderItemData, OrderSource,
+        AdminOrderRequest, AppliedCoupon, BundleItem, CheckoutRequest, CustomerInfo, Order,
+        OrderCommentImage, OrderItem, OrderItemData, OrderSource, OrderStatus,
+        OrderStatusHistoryEntry, OrderStatusSource,
     },
+    queries::{cart_queries, coin_queries, coupon_queries, inventory_queries, reservation_queries},
 };
 use uuid::Uuid;
 
+/// The variant an order line takes stock from. Lines without a variant id fall
+/// back to the first variant with their color; a variant of another product
+/// resolves to none.
+async fn line_variant_id(
+    conn: &mut PgConnection,
+    variant_id: Option<i32>,
+    product_id: &str,
+    color: Option<&str>,
+) -> Result<Option<i32>> {
+    let id = sqlx::query_scalar::<_, i32>(
+        "SELECT id FROM product_variants
+         WHERE product_id = $2
+           AND CASE WHEN $1::int IS NULL THEN color IS NOT DISTINCT FROM $3::text ELSE id = $1 END
+         ORDER BY id LIMIT 1",
+    )
+    .bind(variant_id)
+    .bind(product_id)
+    .bind(color)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    Ok(id)
+}
+
+/// The variants a line takes stock from and how many units of each: its own
+/// variant, or every component of a bundle.
+async fn line_stock(
+    conn: &mut PgConnection,
+    variant_id: Option<i32>,
+    product_id: &str,
+    color: Option<&str>,
+    bundle_items: Option<&[BundleItem]>,
+    quantity: i32,
+) -> Result<Vec<(i32, i32)>> {
+    if let Some(items) = bundle_items.filter(|items| !items.is_empty()) {
+        return Ok(items
+            .iter()
+            .map(|i| (i.variant_id, i.quantity * quantity))
+            .collect());
+    }
+
+    let variant_id = line_variant_id(conn, variant_id, product_id, color).await?;
+    Ok(variant_id.map(|id| (id, quantity)).into_iter().collect())
+}
+
 pub struct OrderContact<'a> {
     pub customer: &'a CustomerInfo,
     pub email: &'a str,
@@ -22,39 +69,36 @@ pub struct OrderContact<'a> {
     pub delivery_time: &'a str,
     pub comment: Option<&'a str>,
     pub payment_method: Option<&'a str>,
+    pub coins_redeemed: i32,
+    pub coupon: Option<&'a AppliedCoupon>,
 }
 
-pub async fn create_order_with_items(
-    pool: &PgPool,
-    user_id: Option<i32>,
-    order_id: &str,
-    amount: i32,
-    status: &str,
-    req: &CheckoutRequest,
-    items: &[OrderItemData],
-) -> Result<Order> {
-    let contact = OrderContact {
-        customer: &req.customer,
-        email: &req.email,
-        phone_number: &req.phone_number,
-        address: &req.address,
-        city: req.city.as_deref(),
-        region: req.region.as_deref(),
-        details: req.details.as_deref(),
-        delivery_type: &req.delivery_type,
-        delivery_time: &req.delivery_time,
-        comment: req.comment.as_deref(),
-        payment_method: Some(req.payment_method.as_str()),
-    };
-    create_order_with_items_raw(pool, user_id, order_id, amount, status, &contact, items).await
+impl<'a> OrderContact<'a> {
+    pub fn from_checkout(req: &'a CheckoutRequest, coupon: Option<&'a AppliedCoupon>) -> Self {
+        OrderContact {
+            customer: &req.customer,
+            email: &req.email,
+            phone_number: &req.phone_number,
+            address: &req.address,
+            city: req.city.as_deref(),
+            region: req.region.as_deref(),
+            details: req.details.as_deref(),
+            delivery_type: &req.delivery_type,
+            delivery_time: &req.delivery_time,
+            comment: req.comment.as_deref(),
+            payment_method: Some(req.payment_method.as_str()),
+            coins_redeemed: req.coins_to_redeem,
+            coupon,
+        }
+    }
 }
 
-pub async fn create_order_with_items_raw(
+pub async fn create_order_with_items(
     pool: &PgPool,
     user_id: Option<i32>,
     order_id: &str,
     amount: i32,
-    status: &str,
+    status: OrderStatus,
     contact: &OrderContact<'_>,
     items: &[OrderItemData],
 ) -> Result<Order> {
@@ -87,8 +131,9 @@ pub async fn create_order_with_items_raw(
     let order = sqlx::query_as::<_, Order>(
         "INSERT INTO orders (user_id, order_id, amount, status, customer_type, customer_name, customer_surname,
          organization_type, organization_name, organization_code, email, phone_number, address,
-         city, region, details, delivery_type, delivery_time, comment, payment_method)
-         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20)
+         city, region, details, delivery_type, delivery_time, comment, payment_method, coins_redeemed,
+         coupon_code, coupon_discount)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23)
          RETURNING *",
     )
     .bind(user_id)
@@ -111,10 +156,49 @@ pub async fn create_order_with_items_raw(
     .bind(contact.delivery_time)
     .bind(contact.comment)
     .bind(contact.payment_method)
+    .bind(contact.coins_redeemed)
+    .bind(contact.coupon.map(|c| c.code.as_str()))
+    .bind(contact.coupon.map_or(0, |c| c.discount))
     .fetch_one(&mut *tx)
     .await?;
 
+    if let Some(coupon) = contact.coupon {
+        coupon_queries::redeem_for_order(&mut tx, coupon, order.id, user_id, contact.email).await?;
+    }
+
+    if contact.coins_redeemed > 0 {
+        let Some(user_id) = user_id else {
+            return Err(AppError::Unauthorized(
+                "ქოინების გამოსაყენებლად საჭიროა ავტორიზაცია".to_string(),
+            ));
+        };
+        coin_queries::redeem_for_order(&mut tx, user_id, order.id, contact.coins_redeemed).await?;
+    }
+
+    let change = StatusChange {
+        source: OrderStatusSource::Checkout,
+        changed_by_user_id: user_id,
+        comment: None,
+    };
+    insert_status_history(&mut tx, order.id, None, status, &change).await?;
+
+    insert_order_items(&mut tx, order.id, items).await?;
+
+    tx.commit().await?;
+    Ok(order)
+}
+
+async fn insert_order_items(
+    conn: &mut PgConnection,
+    order_db_id: i32,
+    items: &[OrderItemData],
+) -> Result<()> {
+    if items.is_empty() {
+        return Ok(());
+    }
+
     let product_ids: Vec<Option<&str>> = items.iter().map(|i| i.product_id.as_deref()).collect();
+    let variant_ids: Vec<Option<i32>> = items.iter().map(|i| i.variant_id).collect();
     let colors: Vec<Option<&str>> = items.iter().map(|i| i.color.as_deref()).collect();
     let quantities: Vec<i32> = items.iter().map(|i| i.quantity).collect();
     let prices: Vec<Decimal> = items.iter().map(|i| i.price).collect();
@@ -122,12 +206,24 @@ pub async fn create_order_with_items_raw(
     let product_images: Vec<serde_json::Value> = items.iter().map(|i| i.image.clone()).collect();
     let cable_configs: Vec<Option<serde_json::Value>> =
         items.iter().map(|i| i.cable_config.clone()).collect();
+    let campaign_ids: Vec<Option<i32>> = items.iter().map(|i| i.campaign_id).collect();
+    let campaign_discounts: Vec<Option<Decimal>> =
+        items.iter().map(|i| i.campaign_discount).collect();
+    let bundle_items: Vec<Option<serde_json::Value>> = items
+        .iter()
+        .map(|i| {
+            i.bundle_items
+                .as_ref()
+                .map(serde_json::to_value)
+                .transpose()
+        })
+        .collect::<std::result::Result<_, _>>()?;
 
     sqlx::query(
-        "INSERT INTO order_items (order_id, product_id, color, quantity, price_at_purchase, product_name, product_image, cable_config)
-         SELECT $1, unnest($2::text[]), unnest($3::varchar[]), unnest($4::int[]), unnest($5::decimal[]), unnest($6::varchar[]), unnest($7::jsonb[]), unnest($8::jsonb[])",
+        "INSERT INTO order_items (order_id, product_id, color, quantity, price_at_purchase, product_name, product_image, cable_config, variant_id, campaign_id, campaign_discount, bundle_items)
+         SELECT $1, unnest($2::text[]), unnest($3::varchar[]), unnest($4::int[]), unnest($5::decimal[]), unnest($6::varchar[]), unnest($7::jsonb[]), unnest($8::jsonb[]), unnest($9::int[]), unnest($10::int[]), unnest($11::decimal[]), unnest($12::jsonb[])",
     )
-    .bind(order.id)
+    .bind(order_db_id)
     .bind(&product_ids)
     .bind(&colors)
     .bind(&quantities)
@@ -135,18 +231,21 @@ pub async fn create_order_with_items_raw(
     .bind(&product_names)
     .bind(&product_images)
     .bind(&cable_configs)
-    .execute(&mut *tx)
+    .bind(&variant_ids)
+    .bind(&campaign_ids)
+    .bind(&campaign_discounts)
+    .bind(&bundle_items)
+    .execute(conn)
     .await?;
 
-    tx.commit().await?;
-    Ok(order)
+    Ok(())
 }
 
 pub async fn create_admin_order(
     pool: &PgPool,
     order_id: &str,
     amount: i32,
-    status: &str,
+    status: OrderStatus,
     created_by_user_id: i32,
     req: &AdminOrderRequest,
     items: &[OrderItemData],
@@ -161,7 +260,7 @@ pub async fn create_admin_order(
         }
     });
 
-    let order = sqlx::query_as::<_, Order>(
+    let mut order = sqlx::query_as::<_, Order>(
         "INSERT INTO orders (user_id, order_id, amount, status, customer_type, customer_name, customer_surname,
          organization_type, organization_name, organization_code, email, phone_number, address,
          city, region, details, delivery_type, delivery_time, comment,
@@ -200,118 +299,333 @@ pub async fn create_admin_order(
     .fetch_one(&mut *tx)
     .await?;
 
-    if !items.is_empty() {
-        let product_ids: Vec<Option<&str>> =
-            items.iter().map(|i| i.product_id.as_deref()).collect();
-        let colors: Vec<Option<&str>> = items.iter().map(|i| i.color.as_deref()).collect();
-        let quantities: Vec<i32> = items.iter().map(|i| i.quantity).collect();
-        let prices: Vec<Decimal> = items.iter().map(|i| i.price).collect();
-        let product_names: Vec<&str> = items.iter().map(|i| i.product_name.as_str()).collect();
-        let product_images: Vec<serde_json::Value> =
-            items.iter().map(|i| i.image.clone()).collect();
-        let cable_configs: Vec<Option<serde_json::Value>> =
-            items.iter().map(|i| i.cable_config.clone()).collect();
+    let change = StatusChange {
+        source: OrderStatusSource::Operator,
+        changed_by_user_id: Some(created_by_user_id),
+        comment: None,
+    };
+    insert_status_history(&mut tx, order.id, None, status, &change).await?;
+
+    insert_order_items(&mut tx, order.id, items).await?;
 
-        sqlx::query(
-            "INSERT INTO order_items (order_id, product_id, color, quantity, price_at_purchase, product_name, product_image, cable_config)
-             SELECT $1, unnest($2::text[]), unnest($3::varchar[]), unnest($4::int[]), unnest($5::decimal[]), unnest($6::varchar[]), unnest($7::jsonb[]), unnest($8::jsonb[])",
-        )
-        .bind(order.id)
-        .bind(&product_ids)
-        .bind(&colors)
-        .bind(&quantities)
-        .bind(&prices)
-        .bind(&product_names)
-        .bind(&product_images)
-        .bind(&cable_configs)
-        .execute(&mut *tx)
-        .await?;
+    // entered as already sold, so the stock leaves the warehouse right away
+    if status.holds_stock() && !apply_approval(&mut tx, &mut order).await? {
+        return Err(AppError::BadRequest(
+            "პროდუქტი აღარ არის მარაგში".to_string(),
+        ));
     }
 
     tx.commit().await?;
     Ok(order)
 }
 
+pub struct StatusChange<'a> {
+    pub source: OrderStatusSource,
+    pub changed_by_user_id: Option<i32>,
+    pub comment: Option<&'a str>,
+}
+
+pub async fn insert_status_history(
+    conn: &mut PgConnection,
+    order_db_id: i32,
+    from: Option<OrderStatus>,
+    to: OrderStatus,
+    change: &StatusChange<'_>,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO order_status_history
+            (order_id, from_status, to_status, source, changed_by_user_id, comment)
+         VALUES ($1, $2, $3, $4, $5, $6)",
+    )
+    .bind(order_db_id)
+    .bind(from)
+    .bind(to)
+    .bind(change.source)
+    .bind(change.changed_by_user_id)
+    .bind(change.comment)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Moves a locked order to `to` and records the change. Callers are expected
+/// to have checked `can_transition_to` already.
+pub async fn set_status(
+    conn: &mut PgConnection,
+    order: &Order,
+    to: OrderStatus,
+    change: &StatusChange<'_>,
+) -> Result<Order> {
+    let updated = sqlx::query_as::<_, Order>(
+        "UPDATE orders SET status = $1, updated_at = NOW() WHERE id = $2 RETURNING *",
+    )
+    .bind(to)
+    .bind(order.id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    insert_status_history(conn, order.id, Some(order.status), to, change).await?;
+
+    Ok(updated)
+}
+
+/// Takes back the coins and coupon use a declined or expired order gave up,
+/// before it is approved after all.
+pub async fn reclaim_released(conn: &mut PgConnection, order: &Order) -> Result<()> {
+    coupon_queries::reclaim_for_order(conn, order.id).await?;
+    coin_queries::reclaim_for_order(conn, order).await
+}
+
+/// Takes an approved order's stock, drops its reservation, awards its coins
+/// and clears the ordered items from the cart. Returns `false` if a line is
+/// out of stock, in which case the caller must roll back.
+pub async fn apply_approval(conn: &mut PgConnection, order: &mut Order) -> Result<bool> {
+    let items = sqlx::query_as::<_, OrderItem>("SELECT * FROM order_items WHERE order_id = $1")
+        .bind(order.id)
+        .fetch_all(&mut *conn)
+        .await?;
+    let warehouse_id =
+        inventory_queries::fulfillment_warehouse_id(conn, order.is_store_pickup()).await?;
+
+    for item in &items {
+        let Some(product_id) = &item.product_id else {
+            continue;
+        };
+        let stock = line_stock(
+            conn,
+            item.variant_id,
+            product_id,
+            item.color.as_deref(),
+            item.bundle_items.as_deref().map(|b| b.as_slice()),
+            item.quantity,
+        )
+        .await?;
+        if stock.is_empty() {
+            return Ok(false);
+        }
+
+        for &(variant_id, quantity) in &stock {
+            if !inventory_queries::deduct_for_sale(
+                conn,
+                variant_id,
+                quantity,
+                warehouse_id,
+                order.id,
+            )
+            .await?
+            {
+                return Ok(false);
+            }
+        }
+    }
+
+    order.stock_deducted_at = sqlx::query_scalar(
+        "UPDATE orders SET stock_deducted_at = NOW() WHERE id = $1 RETURNING stock_deducted_at",
+    )
+    .bind(order.id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    reservation_queries::release_for_order(conn, order.id).await?;
+    coin_queries::award_for_order(conn, order).await?;
+    cart_queries::remove_ordered_items(conn, order).await?;
+
+    Ok(true)
+}
+
+/// Undoes what an order held or took: its reservation, redeemed and earned
+/// coins and any stock already deducted.
+pub async fn apply_release(conn: &mut PgConnection, order: &Order) -> Result<()> {
+    reservation_queries::release_for_order(conn, order.id).await?;
+    coin_queries::return_for_order(conn, order).await?;
+    coin_queries::clawback_for_order(conn, order, None).await?;
+    restore_order_stock(conn, order.id).await?;
+    Ok(())
+}
+
 pub async fn update_order_status_and_deduct_stock(
     pool: &PgPool,
     order_id: &str,
-    status: &str,
+    status: OrderStatus,
     payment_id: Option<i32>,
+    source: OrderStatusSource,
 ) -> Result<Option<(Order, bool)>> {
     let mut tx = pool.begin().await?;
 
-    let order = sqlx::query_as::<_, Order>(
-        "UPDATE orders SET status = $1, payment_id = $2, updated_at = NOW()
-         WHERE order_id = $3 AND status <> 'approved' RETURNING *",
-    )
-    .bind(status)
-    .bind(payment_id)
-    .bind(order_id)
-    .fetch_optional(&mut *tx)
-    .await?;
+    let current = sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE order_id = $1 FOR UPDATE")
+        .bind(order_id)
+        .fetch_optional(&mut *tx)
+        .await?;
 
-    let order = match order {
-        Some(o) => o,
-        None => {
+    let current = match current {
+        Some(o) if o.status.can_transition_to(status) => o,
+        _ => {
             tx.commit().await?;
             return Ok(None);
         }
     };
 
-    let mut stock_ok = true;
-
-    if status == "approved" {
-        let items = sqlx::query_as::<_, OrderItem>("SELECT * FROM order_items WHERE order_id = $1")
-            .bind(order.id)
-            .fetch_all(&mut *tx)
-            .await?;
-
-        for item in &items {
-            let Some(product_id) = &item.product_id else {
-                continue;
-            };
-            let result = match &item.color {
-                Some(color) => {
-                    sqlx::query(
-                        "UPDATE product_images
-                         SET quantity = quantity - $1
-                         WHERE product_id = $2 AND color = $3 AND quantity >= $1",
-                    )
-                    .bind(item.quantity)
-                    .bind(product_id)
-                    .bind(color)
-                    .execute(&mut *tx)
-                    .await?
-                }
-                None => {
-                    sqlx::query(
-                        "UPDATE product_images
-                         SET quantity = quantity - $1
-                         WHERE product_id = $2 AND is_primary = true AND quantity >= $1",
-                    )
-                    .bind(item.quantity)
-                    .bind(product_id)
-                    .execute(&mut *tx)
-                    .await?
-                }
-            };
-
-            if result.rows_affected() == 0 {
-                stock_ok = false;
-                break;
+    // the coins and coupon use were given back when the order was released
+    let reapproved = status == OrderStatus::Approved
+        && matches!(current.status, OrderStatus::Declined | OrderStatus::Expired);
+    if reapproved {
+        match reclaim_released(&mut tx, &current).await {
+            Ok(()) => {}
+            Err(AppError::BadRequest(reason)) => {
+                tracing::warn!("order {} can't be approved again: {reason}", order_id);
+                tx.rollback().await?;
+                return Ok(None);
             }
+            Err(e) => return Err(e),
         }
+    }
 
+    let mut order = sqlx::query_as::<_, Order>(
+        "UPDATE orders SET status = $1, payment_id = COALESCE($2, payment_id), updated_at = NOW()
+         WHERE id = $3 RETURNING *",
+    )
+    .bind(status)
+    .bind(payment_id)
+    .bind(current.id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let mut stock_ok = true;
+
+    if status == OrderStatus::Approved {
+        stock_ok = apply_approval(&mut tx, &mut order).await?;
         if !stock_ok {
+            // nothing was changed, so the caller gets the order as it still is
             tx.rollback().await?;
-            return Ok(Some((order, false)));
+            return Ok(Some((current, false)));
         }
+    } else if matches!(
+        status,
+        OrderStatus::Declined | OrderStatus::Expired | OrderStatus::Reversed
+    ) {
+        apply_release(&mut tx, &order).await?;
     }
 
+    let change = StatusChange {
+        source,
+        changed_by_user_id: None,
+        comment: None,
+    };
+    insert_status_history(&mut tx, order.id, Some(current.status), status, &change).await?;
+
     tx.commit().await?;
     Ok(Some((order, stock_ok)))
 }
 
+pub async fn get_status_history(
+    pool: &PgPool,
+    order_db_id: i32,
+) -> Result<Vec<OrderStatusHistoryEntry>> {
+    let history = sqlx::query_as::<_, OrderStatusHistoryEntry>(
+        "SELECT h.*, u.name AS changed_by_name
+         FROM order_status_history h
+         LEFT JOIN users u ON u.id = h.changed_by_user_id
+         WHERE h.order_id = $1
+         ORDER BY h.created_at, h.id",
+    )
+    .bind(order_db_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(history)
+}
+
+/// Inverse of the stock deduction done when an order is approved.
+pub async fn restore_item_stock(
+    conn: &mut PgConnection,
+    order_db_id: i32,
+    variant_id: Option<i32>,
+    product_id: &str,
+    color: Option<&str>,
+    bundle_items: Option<&[BundleItem]>,
+    quantity: i32,
+) -> Result<()> {
+    let stock = line_stock(conn, variant_id, product_id, color, bundle_items, quantity).await?;
+    for (variant_id, quantity) in stock {
+        inventory_queries::return_for_order(conn, variant_id, quantity, order_db_id).await?;
+    }
+    Ok(())
+}
+
+/// Puts every unit an order took from stock back, minus lines already restocked
+/// by approved refunds. Lines of refunds still in flight go back too, since
+/// approving those later no longer restocks. Only the first call for an order
+/// does anything, and orders whose stock was never deducted are left alone.
+pub async fn restore_order_stock(conn: &mut PgConnection, order_db_id: i32) -> Result<bool> {
+    let claimed = sqlx::query(
+        "UPDATE orders SET stock_restored_at = NOW()
+         WHERE id = $1 AND stock_deducted_at IS NOT NULL AND stock_restored_at IS NULL",
+    )
+    .bind(order_db_id)
+    .execute(&mut *conn)
+    .await?;
+
+    if claimed.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    #[derive(sqlx::FromRow)]
+    struct Line {
+        variant_id: Option<i32>,
+        product_id: Option<String>,
+        color: Option<String>,
+        bundle_items: Option<Json<Vec<BundleItem>>>,
+        quantity: i64,
+    }
+
+    let lines = sqlx::query_as::<_, Line>(
+        "SELECT oi.variant_id, oi.product_id, oi.color, oi.bundle_items,
+                oi.quantity - COALESCE(SUM(ri.quantity) FILTER (
+                    WHERE r.status = 'approved'
+                ), 0)::bigint AS quantity
+         FROM order_items oi
+         LEFT JOIN refund_items ri ON ri.order_item_id = oi.id
+         LEFT JOIN refunds r ON r.id = ri.refund_id
+         WHERE oi.order_id = $1
+         GROUP BY oi.id
+         ORDER BY oi.product_id, oi.color",
+    )
+    .bind(order_db_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for line in &lines {
+        let Some(product_id) = &line.product_id else {
+            continue;
+        };
+        if line.quantity <= 0 {
+            continue;
+        }
+        restore_item_stock(
+            conn,
+            order_db_id,
+            line.variant_id,
+            product_id,
+            line.color.as_deref(),
+            line.bundle_items.as_deref().map(|b| b.as_slice()),
+            line.quantity as i32,
+        )
+        .await?;
+    }
+
+    Ok(true)
+}
+
+pub async fn get_order_by_id(pool: &PgPool, id: i32) -> Result<Option<Order>> {
+    let order = sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(order)
+}
+
 pub async fn update_order_checkout_url(
     pool: &PgPool,
     order_id: &str,
@@ -0,0 +1,215 @@
+use sqlx::{PgConnection, PgPool};
+
+use crate::{
+    error::{AppError, Result},
+    models::{
+        CreatePriceScheduleRequest, PriceChangeOrigin, PriceHistoryEntry, PriceScheduleQuery,
+        ScheduledPriceChange,
+    },
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+/// Appends the product's current prices to its history unless they match the
+/// latest entry.
+pub async fn record_price(
+    conn: &mut PgConnection,
+    product_id: &str,
+    origin: PriceChangeOrigin,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO product_price_history (
+             product_id, price, discount, discounted_price, source, changed_by_user_id, schedule_id
+         )
+         SELECT p.id, p.price, COALESCE(p.discount, 0), p.discounted_price, $2, $3, $4
+         FROM products p
+         WHERE p.id = $1
+           AND NOT EXISTS (
+               SELECT 1
+               FROM (
+                   SELECT price, discount, discounted_price
+                   FROM product_price_history
+                   WHERE product_id = $1
+                   ORDER BY id DESC
+                   LIMIT 1
+               ) last
+               WHERE last.price = p.price
+                 AND last.discount = COALESCE(p.discount, 0)
+                 AND last.discounted_price IS NOT DISTINCT FROM p.discounted_price
+           )",
+    )
+    .bind(product_id)
+    .bind(origin.source)
+    .bind(origin.changed_by_user_id)
+    .bind(origin.schedule_id)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_price_history(pool: &PgPool, product_id: &str) -> Result<Vec<PriceHistoryEntry>> {
+    let entries = sqlx::query_as::<_, PriceHistoryEntry>(
+        "SELECT h.*, u.name AS changed_by_name
+         FROM product_price_history h
+         LEFT JOIN users u ON u.id = h.changed_by_user_id
+         WHERE h.product_id = $1
+         ORDER BY h.id DESC",
+    )
+    .bind(product_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(entries)
+}
+
+pub async fn create_schedule(
+    pool: &PgPool,
+    product_id: &str,
+    req: &CreatePriceScheduleRequest,
+    created_by_user_id: i32,
+) -> Result<ScheduledPriceChange> {
+    let schedule = sqlx::query_as::<_, ScheduledPriceChange>(
+        "INSERT INTO scheduled_price_changes (
+             product_id, price, discount, discounted_price, apply_at, created_by_user_id
+         )
+         VALUES ($1, $2, $3, $4, $5, $6)
+         RETURNING *",
+    )
+    .bind(product_id)
+    .bind(req.price)
+    .bind(req.discount)
+    .bind(req.discounted_price)
+    .bind(req.apply_at)
+    .bind(created_by_user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(schedule)
+}
+
+pub async fn find_schedule(pool: &PgPool, id: i32) -> Result<Option<ScheduledPriceChange>> {
+    let schedule = sqlx::query_as::<_, ScheduledPriceChange>(
+        "SELECT * FROM scheduled_price_changes WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(schedule)
+}
+
+pub async fn search_schedules(
+    pool: &PgPool,
+    params: &PriceScheduleQuery,
+) -> Result<(Vec<ScheduledPriceChange>, i64, i64, i64)> {
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let offset = params.offset.unwrap_or(0).max(0);
+
+    let mut qb = sqlx::QueryBuilder::<sqlx::Postgres>::new(
+        "SELECT s.*, COUNT(*) OVER() AS total_count FROM scheduled_price_changes s WHERE 1=1",
+    );
+
+    if let Some(ref product_id) = params.product_id {
+        qb.push(" AND s.product_id = ");
+        qb.push_bind(product_id);
+    }
+    if let Some(status) = params.status {
+        qb.push(" AND s.status = ");
+        qb.push_bind(status);
+    }
+
+    qb.push(" ORDER BY s.apply_at DESC, s.id DESC LIMIT ");
+    qb.push_bind(limit);
+    qb.push(" OFFSET ");
+    qb.push_bind(offset);
+
+    #[derive(sqlx::FromRow)]
+    struct Row {
+        #[sqlx(flatten)]
+        schedule: ScheduledPriceChange,
+        total_count: i64,
+    }
+
+    let rows = qb.build_query_as::<Row>().fetch_all(pool).await?;
+    let total = rows.first().map(|r| r.total_count).unwrap_or(0);
+    let schedules = rows.into_iter().map(|r| r.schedule).collect();
+
+    Ok((schedules, total, limit, offset))
+}
+
+/// Returns None when the schedule has already run or been cancelled.
+pub async fn cancel_schedule(pool: &PgPool, id: i32) -> Result<Option<ScheduledPriceChange>> {
+    let schedule = sqlx::query_as::<_, ScheduledPriceChange>(
+        "UPDATE scheduled_price_changes SET status = 'cancelled', cancelled_at = NOW()
+         WHERE id = $1 AND status = 'pending'
+         RETURNING *",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(schedule)
+}
+
+/// Claims due schedules so a schedule is only picked up by one worker. They
+/// stay `running` until `mark_schedule_applied` commits with the price change;
+/// one that fails is moved to `failed`, and one abandoned by a crashed worker
+/// is claimed again after a while.
+pub async fn claim_due_schedules(pool: &PgPool, limit: i64) -> Result<Vec<ScheduledPriceChange>> {
+    let mut schedules = sqlx::query_as::<_, ScheduledPriceChange>(
+        "UPDATE scheduled_price_changes SET status = 'running', claimed_at = NOW()
+         WHERE id IN (
+             SELECT id FROM scheduled_price_changes
+             WHERE (status = 'pending' AND apply_at <= NOW())
+                OR (status = 'running' AND claimed_at < NOW() - INTERVAL '15 minutes')
+             ORDER BY apply_at, id
+             LIMIT $1
+             FOR UPDATE SKIP LOCKED
+         )
+         RETURNING *",
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    schedules.sort_by_key(|s| (s.apply_at, s.id));
+    Ok(schedules)
+}
+
+/// Runs in the transaction that changes the price, so a schedule only reads
+/// as applied once the new price is in place.
+pub async fn mark_schedule_applied(conn: &mut PgConnection, id: i32) -> Result<()> {
+    let result = sqlx::query(
+        "UPDATE scheduled_price_changes SET status = 'applied', applied_at = NOW()
+         WHERE id = $1 AND status = 'running'",
+    )
+    .bind(id)
+    .execute(conn)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::Conflict(
+            "ფასის ცვლილება უკვე შესრულებულია".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+pub async fn fail_schedule(pool: &PgPool, id: i32, error: &str) -> Result<()> {
+    sqlx::query(
+        "UPDATE scheduled_price_changes SET status = 'failed', error = $2
+         WHERE id = $1 AND status = 'running'",
+    )
+    .bind(id)
+    .bind(error)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,154 @@
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    error::Result,
+    models::{PendingProductAlert, ProductAlertKind},
+};
+
+/// Deliveries are given up on after this many failed sends.
+const MAX_ATTEMPTS: i32 = 5;
+
+pub struct NewProductAlert<'a> {
+    pub product_id: &'a str,
+    pub color: Option<&'a str>,
+    pub kind: ProductAlertKind,
+    pub email: &'a str,
+    pub user_id: Option<i32>,
+    pub reference_price: Option<Decimal>,
+}
+
+/// Subscribing again re-arms an alert that already fired.
+pub async fn subscribe(pool: &PgPool, alert: &NewProductAlert<'_>) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO product_alerts (product_id, color, kind, email, user_id, reference_price)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         ON CONFLICT (product_id, (COALESCE(color, '')), kind, email)
+         DO UPDATE SET notified_at = NULL,
+                       reference_price = EXCLUDED.reference_price,
+                       user_id = COALESCE(EXCLUDED.user_id, product_alerts.user_id)",
+    )
+    .bind(alert.product_id)
+    .bind(alert.color)
+    .bind(alert.kind)
+    .bind(alert.email)
+    .bind(alert.user_id)
+    .bind(alert.reference_price)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn unsubscribe(pool: &PgPool, token: Uuid) -> Result<bool> {
+    let result = sqlx::query("DELETE FROM product_alerts WHERE unsubscribe_token = $1")
+        .bind(token)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Queues back-in-stock emails for the given colors. Subscriptions without a
+/// color only fire when `product_restocked` is set.
+pub async fn enqueue_back_in_stock(
+    pool: &PgPool,
+    product_id: &str,
+    colors: &[String],
+    product_restocked: bool,
+) -> Result<u64> {
+    let result = sqlx::query(
+        "INSERT INTO product_alert_deliveries (alert_id)
+         SELECT id FROM product_alerts
+         WHERE product_id = $1 AND kind = $2 AND notified_at IS NULL
+           AND (color = ANY($3) OR (color IS NULL AND $4))
+         ON CONFLICT (alert_id) WHERE sent_at IS NULL AND failed_at IS NULL DO NOTHING",
+    )
+    .bind(product_id)
+    .bind(ProductAlertKind::BackInStock)
+    .bind(colors)
+    .bind(product_restocked)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Queues price-drop emails for subscribers whose reference price is above
+/// `price`. A still-queued email is updated to the newer, lower price.
+pub async fn enqueue_price_drop(pool: &PgPool, product_id: &str, price: Decimal) -> Result<u64> {
+    let result = sqlx::query(
+        "INSERT INTO product_alert_deliveries (alert_id, price)
+         SELECT id, $3 FROM product_alerts
+         WHERE product_id = $1 AND kind = $2 AND reference_price > $3
+         ON CONFLICT (alert_id) WHERE sent_at IS NULL AND failed_at IS NULL
+         DO UPDATE SET price = EXCLUDED.price",
+    )
+    .bind(product_id)
+    .bind(ProductAlertKind::PriceDrop)
+    .bind(price)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+pub async fn find_pending(pool: &PgPool, limit: i64) -> Result<Vec<PendingProductAlert>> {
+    let alerts = sqlx::query_as::<_, PendingProductAlert>(
+        "SELECT d.id AS delivery_id, a.id AS alert_id, a.kind, a.email, a.color, d.price,
+                a.unsubscribe_token, p.id AS product_id, p.name AS product_name
+         FROM product_alert_deliveries d
+         JOIN product_alerts a ON a.id = d.alert_id
+         JOIN products p ON p.id = a.product_id
+         WHERE d.sent_at IS NULL AND d.failed_at IS NULL AND p.enabled = true
+         ORDER BY d.created_at
+         LIMIT $1",
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(alerts)
+}
+
+/// Back-in-stock alerts fire once; price-drop alerts move their reference
+/// price down to the price just announced.
+pub async fn mark_sent(pool: &PgPool, alert: &PendingProductAlert) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("UPDATE product_alert_deliveries SET sent_at = NOW() WHERE id = $1")
+        .bind(alert.delivery_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        "UPDATE product_alerts
+         SET notified_at = NOW(), reference_price = COALESCE($2, reference_price)
+         WHERE id = $1",
+    )
+    .bind(alert.alert_id)
+    .bind(alert.price)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Counts a failed send. The last allowed attempt marks the delivery failed,
+/// which frees the subscription for the next event.
+pub async fn mark_failed(pool: &PgPool, delivery_id: i32) -> Result<()> {
+    sqlx::query(
+        "UPDATE product_alert_deliveries
+         SET attempts = attempts + 1,
+             failed_at = CASE WHEN attempts + 1 >= $2 THEN NOW() END
+         WHERE id = $1",
+    )
+    .bind(delivery_id)
+    .bind(MAX_ATTEMPTS)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
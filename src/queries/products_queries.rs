@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 
+use chrono::NaiveDate;
 use sqlx::PgPool;
+use uuid::Uuid;
 
 use crate::{
     error::Result,
@@ -10,6 +12,15 @@ use crate::{
     },
 };
 
+/// Storefront queries join this to load the running campaign, if any, onto
+/// each product.
+const CAMPAIGN_JOIN: &str = "LEFT JOIN active_campaign_prices acp ON acp.product_id = p.id";
+const CAMPAIGN_COLUMNS: &str = "acp.campaign_id, acp.campaign_name, acp.campaign_price, \
+     acp.campaign_ends_at, acp.campaign_discount_percent";
+
+/// What the customer pays; matches `pricing_service::effective_price`.
+const EFFECTIVE_PRICE: &str = "COALESCE(acp.campaign_price, CASE WHEN p.discounted_price < p.price THEN p.discounted_price ELSE p.price END)";
+
 pub async fn find_cable_variants_by_type_ids(
     pool: &PgPool,
     type_ids: &[i32],
@@ -50,11 +61,12 @@ pub async fn find_product_bundle(
         seo_json: Option<serde_json::Value>,
     }
 
-    let row = sqlx::query_as::<_, Row>(
+    let row = sqlx::query_as::<_, Row>(&format!(
         r#"
         SELECT
             p.*,
             b.name AS brand_name,
+            {CAMPAIGN_COLUMNS},
             COALESCE((
                 SELECT jsonb_agg(
                     jsonb_build_object(
@@ -63,7 +75,7 @@ pub async fn find_product_bundle(
                         'color', pi.color,
                         'is_primary', pi.is_primary,
                         'extension', pi.extension,
-                        'quantity', pi.quantity
+                        'variant_id', pi.variant_id
                     )
                     ORDER BY pi.is_primary DESC, pi.created_at ASC
                 )
@@ -92,9 +104,10 @@ pub async fn find_product_bundle(
             ) AS seo_json
         FROM products p
         LEFT JOIN brands b ON p.brand_id = b.id
+        {CAMPAIGN_JOIN}
         WHERE p.id = $1
-        "#,
-    )
+        "#
+    ))
     .bind(id)
     .fetch_optional(pool)
     .await?;
@@ -124,7 +137,7 @@ pub async fn find_by_id(pool: &PgPool, id: &str) -> Result<Option<Product>> {
 
 pub async fn find_images_by_product_id(pool: &PgPool, id: &str) -> Result<Vec<ProductImage>> {
     let product_images = sqlx::query_as::<_, ProductImage>(
-        "SELECT product_id, image_uuid, color, is_primary, extension, quantity
+        "SELECT product_id, image_uuid, color, is_primary, extension, variant_id
          FROM product_images
          WHERE product_id = $1
          ORDER BY is_primary DESC, created_at ASC",
@@ -137,11 +150,11 @@ pub async fn find_images_by_product_id(pool: &PgPool, id: &str) -> Result<Vec<Pr
 }
 
 pub async fn find_by_ids(pool: &PgPool, ids: &[String]) -> Result<HashMap<String, Product>> {
-    let products = sqlx::query_as::<_, Product>(
-        "SELECT p.*, b.name as brand_name
-         FROM products p LEFT JOIN brands b ON p.brand_id = b.id
-         WHERE p.id = ANY($1)",
-    )
+    let products = sqlx::query_as::<_, Product>(&format!(
+        "SELECT p.*, b.name as brand_name, {CAMPAIGN_COLUMNS}
+         FROM products p LEFT JOIN brands b ON p.brand_id = b.id {CAMPAIGN_JOIN}
+         WHERE p.id = ANY($1)"
+    ))
     .bind(ids)
     .fetch_all(pool)
     .await?;
@@ -154,7 +167,7 @@ pub async fn find_images_by_product_ids(
     ids: &[String],
 ) -> Result<HashMap<String, Vec<ProductImage>>> {
     let images = sqlx::query_as::<_, ProductImage>(
-        "SELECT product_id, image_uuid, color, is_primary, extension, quantity
+        "SELECT product_id, image_uuid, color, is_primary, extension, variant_id
          FROM product_images
          WHERE product_id = ANY($1)
          ORDER BY product_id, is_primary DESC, created_at ASC",
@@ -189,6 +202,7 @@ pub async fn build_products_response_ordered(
             let seo = seo_map.remove(id);
             out.push(ProductResponse {
                 videos: ProductResponse::videos_from(&product),
+                rating: ProductResponse::rating_from(&product),
                 data: product,
                 images,
                 categories: Vec::new(),
@@ -233,9 +247,81 @@ fn escape_like(s: &str) -> String {
         .replace('_', "\\_")
 }
 
+/// Joins the product's search document as `ps` and the query, parsed once, as
+/// `sq.tsq` (full-text, with synonyms) and `sq.nq` (transliterated text).
+fn push_search_join<'a>(qb: &mut sqlx::QueryBuilder<'a, sqlx::Postgres>, q: &'a str) {
+    qb.push(
+        " LEFT JOIN product_search_documents ps ON ps.product_id = p.id
+          CROSS JOIN (SELECT product_search_query(",
+    );
+    qb.push_bind(q);
+    qb.push(") AS tsq, search_normalize(");
+    qb.push_bind(q);
+    qb.push(") AS nq) sq");
+}
+
+/// One wrong or missing letter in a five-letter word still scores 0.5.
+const TYPO_SIMILARITY: &str = "0.5";
+
+/// Sets the threshold `<%` in `push_search_filter` uses for the transaction
+/// only, which also holds behind a transaction-mode pooler.
+async fn begin_search(pool: &PgPool) -> Result<sqlx::Transaction<'static, sqlx::Postgres>> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("SELECT set_config('pg_trgm.word_similarity_threshold', $1, true)")
+        .bind(TYPO_SIMILARITY)
+        .execute(&mut *tx)
+        .await?;
+    Ok(tx)
+}
+
+/// Full-text match, a close-enough word for typos, or a plain substring of the
+/// name or description. Each branch is answered from its own index; run it in
+/// `begin_search`.
+fn push_search_filter<'a>(qb: &mut sqlx::QueryBuilder<'a, sqlx::Postgres>, q: &'a str) {
+    let like_q = format!("%{}%", escape_like(q));
+    qb.push(
+        " AND p.id IN (
+              SELECT product_id FROM product_search_documents
+              WHERE document @@ product_search_query(",
+    );
+    qb.push_bind(q);
+    qb.push(") OR search_normalize(");
+    qb.push_bind(q);
+    qb.push(
+        ") <% search_text
+              UNION
+              SELECT id FROM products WHERE name ILIKE ",
+    );
+    qb.push_bind(like_q.clone());
+    qb.push(" OR description ILIKE ");
+    qb.push_bind(like_q);
+    qb.push(")");
+}
+
 const DEFAULT_PAGE_SIZE: i64 = 12;
 const MAX_PAGE_SIZE: i64 = 100;
 
+fn push_sale_type_filter(
+    qb: &mut sqlx::QueryBuilder<'_, sqlx::Postgres>,
+    has_discount: bool,
+    has_coins: bool,
+) {
+    match (has_discount, has_coins) {
+        (true, false) => {
+            qb.push(" AND (p.discount > 0 OR acp.campaign_id IS NOT NULL)");
+        }
+        (false, true) => {
+            qb.push(" AND p.coins_eligible = true");
+        }
+        (true, true) => {
+            qb.push(
+                " AND (p.discount > 0 OR acp.campaign_id IS NOT NULL OR p.coins_eligible = true)",
+            );
+        }
+        (false, false) => {}
+    }
+}
+
 pub async fn search_products(
     pool: &PgPool,
     params: ProductQuery,
@@ -254,6 +340,7 @@ pub async fn search_products(
             Some((product, images, categories, seo)) => Ok(crate::models::ProductSearchResponse {
                 products: vec![ProductResponse {
                     videos: ProductResponse::videos_from(&product),
+                    rating: ProductResponse::rating_from(&product),
                     data: product,
                     images,
                     categories,
@@ -275,28 +362,31 @@ pub async fn search_products(
     let needs_views = matches!(params.sort_by, Some(SortBy::ViewsDesc));
     let has_query = params.query.is_some();
 
-    let mut qb = sqlx::QueryBuilder::<sqlx::Postgres>::new("SELECT p.*, b.name as brand_name");
+    let mut qb = sqlx::QueryBuilder::<sqlx::Postgres>::new("SELECT p.*, b.name as brand_name, ");
+    qb.push(CAMPAIGN_COLUMNS);
 
     if has_query {
-        let q = params.query.as_ref().unwrap();
-        qb.push(", GREATEST(similarity(p.name, ");
-        qb.push_bind(q);
-        qb.push("), similarity(COALESCE(p.description, ''), ");
-        qb.push_bind(q);
-        qb.push(")) as relevance_score");
+        qb.push(
+            ", COALESCE(ts_rank(ps.document, sq.tsq), 0)
+                + similarity(search_normalize(p.name), sq.nq) as relevance_score",
+        );
     }
     if needs_views {
         qb.push(", COALESCE(pvc.view_count, 0) as view_count");
     }
     qb.push(
-        ", COUNT(*) OVER() as total_count FROM products p LEFT JOIN brands b ON p.brand_id = b.id",
+        ", COUNT(*) OVER() as total_count FROM products p LEFT JOIN brands b ON p.brand_id = b.id ",
     );
+    qb.push(CAMPAIGN_JOIN);
+    if let Some(q) = &params.query {
+        push_search_join(&mut qb, q);
+    }
     if needs_views {
         qb.push(
             " LEFT JOIN (
-                SELECT product_id, COUNT(*) AS view_count
-                FROM product_views
-                WHERE viewed_at >= NOW() - INTERVAL '7 days'
+                SELECT product_id, SUM(views)::BIGINT AS view_count
+                FROM product_view_daily
+                WHERE day >= CURRENT_DATE - 7
                 GROUP BY product_id
             ) pvc ON pvc.product_id = p.id",
         );
@@ -313,24 +403,15 @@ pub async fn search_products(
     }
 
     if let Some(q) = &params.query {
-        let like_q = format!("%{}%", escape_like(q));
-        qb.push(" AND (p.name ILIKE ");
-        qb.push_bind(like_q.clone());
-        qb.push(" OR COALESCE(p.description, '') ILIKE ");
-        qb.push_bind(like_q);
-        qb.push(" OR p.name % ");
-        qb.push_bind(q);
-        qb.push(" OR COALESCE(p.description, '') % ");
-        qb.push_bind(q);
-        qb.push(")");
+        push_search_filter(&mut qb, q);
     }
 
     if let Some(min_price) = params.price_from {
-        qb.push(" AND p.price >= ");
+        qb.push(format!(" AND {EFFECTIVE_PRICE} >= "));
         qb.push_bind(min_price);
     }
     if let Some(max_price) = params.price_to {
-        qb.push(" AND p.price <= ");
+        qb.push(format!(" AND {EFFECTIVE_PRICE} <= "));
         qb.push_bind(max_price);
     }
 
@@ -340,7 +421,7 @@ pub async fn search_products(
     }
 
     if !params.color.is_empty() {
-        qb.push(" AND EXISTS (SELECT 1 FROM product_images pi WHERE pi.product_id = p.id AND pi.color = ANY(");
+        qb.push(" AND EXISTS (SELECT 1 FROM product_variants pv WHERE pv.product_id = p.id AND pv.color = ANY(");
         qb.push_bind(&params.color);
         qb.push("))");
     }
@@ -376,22 +457,18 @@ pub async fn search_products(
     let has_discount = params.sale_type.contains(&SaleType::Discount);
     let has_coins = params.sale_type.contains(&SaleType::Coins);
 
-    if has_discount && !has_coins {
-        qb.push(" AND p.discount > 0");
-    } else if !has_discount && has_coins {
-        qb.push(" AND false");
-    }
+    push_sale_type_filter(&mut qb, has_discount, has_coins);
 
     match params.sort_by {
         Some(SortBy::PriceAsc) => {
-            qb.push(" ORDER BY p.price ASC");
+            qb.push(format!(" ORDER BY {EFFECTIVE_PRICE} ASC"));
             if has_query {
                 qb.push(", relevance_score DESC");
             }
             qb.push(", p.created_at DESC");
         }
         Some(SortBy::PriceDesc) => {
-            qb.push(" ORDER BY p.price DESC");
+            qb.push(format!(" ORDER BY {EFFECTIVE_PRICE} DESC"));
             if has_query {
                 qb.push(", relevance_score DESC");
             }
@@ -404,6 +481,13 @@ pub async fn search_products(
             }
             qb.push(", p.created_at DESC");
         }
+        Some(SortBy::RatingDesc) => {
+            qb.push(" ORDER BY p.rating_avg DESC NULLS LAST, p.rating_count DESC");
+            if has_query {
+                qb.push(", relevance_score DESC");
+            }
+            qb.push(", p.created_at DESC");
+        }
         None => {
             if has_query {
                 qb.push(" ORDER BY relevance_score DESC, p.created_at DESC");
@@ -425,7 +509,12 @@ pub async fn search_products(
         total_count: i64,
     }
 
-    let results = qb.build_query_as::<SearchResult>().fetch_all(pool).await?;
+    let mut tx = begin_search(pool).await?;
+    let results = qb
+        .build_query_as::<SearchResult>()
+        .fetch_all(&mut *tx)
+        .await?;
+    tx.commit().await?;
 
     let total = results.first().map(|r| r.total_count).unwrap_or(0);
 
@@ -448,7 +537,7 @@ pub async fn search_products(
     }
 
     let images_fut = sqlx::query_as::<_, ProductImage>(
-        "SELECT product_id, image_uuid, color, is_primary, extension, quantity
+        "SELECT product_id, image_uuid, color, is_primary, extension, variant_id
          FROM product_images
          WHERE product_id = ANY($1)
          ORDER BY product_id, is_primary DESC, created_at ASC",
@@ -493,6 +582,7 @@ pub async fn search_products(
         .map(|result| ProductResponse {
             images: image_groups.remove(&result.product.id).unwrap_or_default(),
             videos: ProductResponse::videos_from(&result.product),
+            rating: ProductResponse::rating_from(&result.product),
             categories: category_groups
                 .remove(&result.product.id)
                 .unwrap_or_default(),
@@ -514,7 +604,7 @@ pub async fn get_related_products(
     product_id: &str,
     limit: i64,
 ) -> Result<Vec<ProductResponse>> {
-    let products = sqlx::query_as::<_, Product>(
+    let products = sqlx::query_as::<_, Product>(&format!(
         "WITH src_cats AS (
              SELECT category_id FROM product_categories WHERE product_id = $1
          ),
@@ -525,18 +615,61 @@ pub async fn get_related_products(
              WHERE pc.product_id <> $1
              GROUP BY pc.product_id
          )
-         SELECT p.*, b.name as brand_name
+         SELECT p.*, b.name as brand_name, {CAMPAIGN_COLUMNS}
          FROM scored s
          JOIN products p ON p.id = s.product_id AND p.enabled = true
          LEFT JOIN brands b ON p.brand_id = b.id
+         {CAMPAIGN_JOIN}
          ORDER BY s.shared DESC, p.created_at DESC
-         LIMIT $2",
-    )
+         LIMIT $2"
+    ))
+    .bind(product_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    related_responses(pool, products).await
+}
+
+/// Enabled products ranked by how many paid orders contained them together
+/// with `product_id`.
+pub async fn get_bought_together(
+    pool: &PgPool,
+    product_id: &str,
+    limit: i64,
+) -> Result<Vec<ProductResponse>> {
+    let products = sqlx::query_as::<_, Product>(&format!(
+        "WITH src_orders AS (
+             SELECT DISTINCT oi.order_id
+             FROM order_items oi
+             JOIN orders o ON o.id = oi.order_id
+             WHERE oi.product_id = $1
+               AND o.status IN ('approved', 'shipped', 'delivered', 'partially_refunded')
+         ),
+         scored AS (
+             SELECT oi.product_id, COUNT(DISTINCT oi.order_id) AS together
+             FROM order_items oi
+             JOIN src_orders so ON so.order_id = oi.order_id
+             WHERE oi.product_id <> $1
+             GROUP BY oi.product_id
+         )
+         SELECT p.*, b.name as brand_name, {CAMPAIGN_COLUMNS}
+         FROM scored s
+         JOIN products p ON p.id = s.product_id AND p.enabled = true
+         LEFT JOIN brands b ON p.brand_id = b.id
+         {CAMPAIGN_JOIN}
+         ORDER BY s.together DESC, p.created_at DESC
+         LIMIT $2"
+    ))
     .bind(product_id)
     .bind(limit)
     .fetch_all(pool)
     .await?;
 
+    related_responses(pool, products).await
+}
+
+async fn related_responses(pool: &PgPool, products: Vec<Product>) -> Result<Vec<ProductResponse>> {
     if products.is_empty() {
         return Ok(Vec::new());
     }
@@ -552,6 +685,7 @@ pub async fn get_related_products(
             let seo = seo_map.remove(&product.id);
             ProductResponse {
                 videos: ProductResponse::videos_from(&product),
+                rating: ProductResponse::rating_from(&product),
                 data: product,
                 images,
                 categories: Vec::new(),
@@ -566,7 +700,6 @@ pub async fn get_related_products(
 pub async fn get_product_facets(pool: &PgPool, params: ProductQuery) -> Result<ProductFacets> {
     let has_discount = params.sale_type.contains(&SaleType::Discount);
     let has_coins = params.sale_type.contains(&SaleType::Coins);
-    let force_empty = !has_discount && has_coins;
 
     let mut qb = sqlx::QueryBuilder::<sqlx::Postgres>::new("");
 
@@ -605,43 +738,31 @@ pub async fn get_product_facets(pool: &PgPool, params: ProductQuery) -> Result<P
         );
     }
 
-    qb.push("), base_ids AS (SELECT p.id FROM products p WHERE 1=1");
+    qb.push("), base_ids AS (SELECT p.id FROM products p ");
+    qb.push(CAMPAIGN_JOIN);
+    qb.push(" WHERE 1=1");
 
-    if force_empty {
-        qb.push(" AND false");
-    }
     if let Some(enabled) = params.enabled {
         qb.push(" AND p.enabled = ");
         qb.push_bind(enabled);
     }
     if let Some(q) = &params.query {
-        let like_q = format!("%{}%", escape_like(q));
-        qb.push(" AND (p.name ILIKE ");
-        qb.push_bind(like_q.clone());
-        qb.push(" OR p.description ILIKE ");
-        qb.push_bind(like_q);
-        qb.push(" OR similarity(p.name, ");
-        qb.push_bind(q);
-        qb.push(") > 0.3 OR similarity(COALESCE(p.description, ''), ");
-        qb.push_bind(q);
-        qb.push(") > 0.3)");
+        push_search_filter(&mut qb, q);
     }
     if let Some(min_price) = params.price_from {
-        qb.push(" AND p.price >= ");
+        qb.push(format!(" AND {EFFECTIVE_PRICE} >= "));
         qb.push_bind(min_price);
     }
     if let Some(max_price) = params.price_to {
-        qb.push(" AND p.price <= ");
+        qb.push(format!(" AND {EFFECTIVE_PRICE} <= "));
         qb.push_bind(max_price);
     }
     if !params.color.is_empty() {
-        qb.push(" AND EXISTS (SELECT 1 FROM product_images pi WHERE pi.product_id = p.id AND pi.color = ANY(");
+        qb.push(" AND EXISTS (SELECT 1 FROM product_variants pv WHERE pv.product_id = p.id AND pv.color = ANY(");
         qb.push_bind(&params.color);
         qb.push("))");
     }
-    if has_discount && !has_coins {
-        qb.push(" AND p.discount > 0");
-    }
+    push_sale_type_filter(&mut qb, has_discount, has_coins);
     if !params.parent_category_id.is_empty() {
         qb.push(
             " AND EXISTS (
@@ -679,12 +800,12 @@ pub async fn get_product_facets(pool: &PgPool, params: ProductQuery) -> Result<P
             ORDER BY cnt DESC
             LIMIT 50
         ), color_facet AS (
-            SELECT pi.color AS k1, NULL::text AS k2, COUNT(DISTINCT p.id)::bigint AS cnt
+            SELECT pv.color AS k1, NULL::text AS k2, COUNT(DISTINCT p.id)::bigint AS cnt
             FROM filtered_ids f
             JOIN products p ON p.id = f.id
-            JOIN product_images pi ON pi.product_id = p.id
-            WHERE pi.color IS NOT NULL AND pi.color != ''
-            GROUP BY pi.color
+            JOIN product_variants pv ON pv.product_id = p.id
+            WHERE pv.color IS NOT NULL AND pv.color != ''
+            GROUP BY pv.color
             ORDER BY cnt DESC
             LIMIT 50
         ), category_facet AS (
@@ -724,7 +845,9 @@ pub async fn get_product_facets(pool: &PgPool, params: ProductQuery) -> Result<P
         parent_id: Option<i32>,
     }
 
-    let rows: Vec<Row> = qb.build_query_as().fetch_all(pool).await?;
+    let mut tx = begin_search(pool).await?;
+    let rows: Vec<Row> = qb.build_query_as().fetch_all(&mut *tx).await?;
+    tx.commit().await?;
 
     let mut brands = Vec::new();
     let mut colors = Vec::new();
@@ -774,16 +897,117 @@ pub async fn get_product_facets(pool: &PgPool, params: ProductQuery) -> Result<P
     })
 }
 
+/// Records a view unless the same viewer already viewed the product within
+/// `dedup_minutes`. Returns whether a row was written. Anonymous viewers are
+/// told apart by address before session, since the client picks the session id
+/// and could send a fresh one with every request.
 pub async fn add_product_views(
     pool: &PgPool,
     product_id: &str,
     user_id: Option<i32>,
-) -> Result<()> {
-    sqlx::query("INSERT INTO product_views(product_id, user_id) VALUES($1, $2)")
-        .bind(product_id)
-        .bind(user_id)
+    session_id: Option<Uuid>,
+    ip_address: Option<&str>,
+    dedup_minutes: i32,
+) -> Result<bool> {
+    let result = sqlx::query(
+        "WITH viewer AS (
+             SELECT COALESCE('u:' || $2::TEXT, 'ip:' || $4, 's:' || $3::TEXT) AS key
+         )
+         INSERT INTO product_views(product_id, user_id, session_id, ip_address, viewer_key)
+         SELECT $1, $2, $3, $4, viewer.key
+         FROM viewer
+         WHERE viewer.key IS NULL
+            OR NOT EXISTS (
+                SELECT 1 FROM product_views pv
+                WHERE pv.viewer_key = viewer.key
+                  AND pv.product_id = $1
+                  AND pv.viewed_at >= NOW() - make_interval(mins => $5)
+            )",
+    )
+    .bind(product_id)
+    .bind(user_id)
+    .bind(session_id)
+    .bind(ip_address)
+    .bind(dedup_minutes)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn count_recent_views_from_ip(
+    pool: &PgPool,
+    ip_address: &str,
+    seconds: i32,
+) -> Result<i64> {
+    let count = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM product_views
+         WHERE ip_address = $1 AND viewed_at >= NOW() - make_interval(secs => $2)",
+    )
+    .bind(ip_address)
+    .bind(seconds)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count)
+}
+
+/// Recomputes `product_view_daily` for the given day onwards.
+pub async fn refresh_view_rollup(pool: &PgPool, from_day: NaiveDate) -> Result<()> {
+    sqlx::query("SELECT refresh_product_view_daily($1)")
+        .bind(from_day)
         .execute(pool)
         .await?;
 
     Ok(())
 }
+
+/// Distinct enabled products the user viewed, most recent first.
+pub async fn get_recently_viewed_ids(
+    pool: &PgPool,
+    user_id: i32,
+    limit: i64,
+) -> Result<Vec<String>> {
+    let ids = sqlx::query_scalar(
+        "SELECT pv.product_id
+         FROM product_views pv
+         JOIN products p ON p.id = pv.product_id AND p.enabled = true
+         WHERE pv.user_id = $1 AND pv.hidden_from_history_at IS NULL
+         GROUP BY pv.product_id
+         ORDER BY MAX(pv.viewed_at) DESC
+         LIMIT $2",
+    )
+    .bind(user_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ids)
+}
+
+/// Attributes the anonymous views of a client session to the user.
+pub async fn claim_session_views(pool: &PgPool, user_id: i32, session_id: Uuid) -> Result<u64> {
+    let result = sqlx::query(
+        "UPDATE product_views SET user_id = $1 WHERE session_id = $2 AND user_id IS NULL",
+    )
+    .bind(user_id)
+    .bind(session_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Hides the user's views from their history. The rows keep their viewer, so
+/// analytics and view de-duplication still count them as before.
+pub async fn clear_view_history(pool: &PgPool, user_id: i32) -> Result<()> {
+    sqlx::query(
+        "UPDATE product_views SET hidden_from_history_at = NOW()
+         WHERE user_id = $1 AND hidden_from_history_at IS NULL",
+    )
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,148 @@
+use sqlx::PgPool;
+
+use crate::{
+    error::{AppError, Result},
+    models::{ProductQuestion, PublicProductQuestion, QuestionQuery},
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 20;
+const MAX_PAGE_SIZE: i64 = 100;
+/// The product page shows at most this many answered questions.
+const MAX_PUBLIC_QUESTIONS: i64 = 50;
+
+pub async fn create_question(
+    pool: &PgPool,
+    product_id: &str,
+    user_id: i32,
+    author_name: &str,
+    email: &str,
+    question: &str,
+) -> Result<ProductQuestion> {
+    let question = sqlx::query_as::<_, ProductQuestion>(
+        "INSERT INTO product_questions (product_id, user_id, author_name, email, question)
+         VALUES ($1, $2, $3, $4, $5)
+         RETURNING *",
+    )
+    .bind(product_id)
+    .bind(user_id)
+    .bind(author_name)
+    .bind(email)
+    .bind(question)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(question)
+}
+
+pub async fn get_answered_for_product(
+    pool: &PgPool,
+    product_id: &str,
+) -> Result<Vec<PublicProductQuestion>> {
+    let questions = sqlx::query_as::<_, PublicProductQuestion>(
+        "SELECT id, author_name, question, answer, created_at, answered_at
+         FROM product_questions
+         WHERE product_id = $1 AND answer IS NOT NULL
+         ORDER BY answered_at DESC
+         LIMIT $2",
+    )
+    .bind(product_id)
+    .bind(MAX_PUBLIC_QUESTIONS)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(questions)
+}
+
+pub async fn search_questions(
+    pool: &PgPool,
+    params: &QuestionQuery,
+) -> Result<(Vec<ProductQuestion>, i64, i64, i64)> {
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let offset = params.offset.unwrap_or(0).max(0);
+
+    let mut qb = sqlx::QueryBuilder::<sqlx::Postgres>::new(
+        "SELECT *, COUNT(*) OVER() AS total_count FROM product_questions WHERE 1=1",
+    );
+
+    if let Some(ref product_id) = params.product_id {
+        qb.push(" AND product_id = ");
+        qb.push_bind(product_id);
+    }
+    match params.answered {
+        Some(true) => {
+            qb.push(" AND answer IS NOT NULL");
+        }
+        Some(false) => {
+            qb.push(" AND answer IS NULL");
+        }
+        None => {}
+    }
+
+    qb.push(" ORDER BY created_at DESC, id DESC LIMIT ");
+    qb.push_bind(limit);
+    qb.push(" OFFSET ");
+    qb.push_bind(offset);
+
+    #[derive(sqlx::FromRow)]
+    struct Row {
+        #[sqlx(flatten)]
+        question: ProductQuestion,
+        total_count: i64,
+    }
+
+    let rows = qb.build_query_as::<Row>().fetch_all(pool).await?;
+    let total = rows.first().map(|r| r.total_count).unwrap_or(0);
+    let questions = rows.into_iter().map(|r| r.question).collect();
+
+    Ok((questions, total, limit, offset))
+}
+
+/// Stores the answer. The second value is `true` the first time a question is
+/// answered, which is when the asker gets notified.
+pub async fn answer_question(
+    pool: &PgPool,
+    id: i32,
+    answer: &str,
+    answered_by_user_id: i32,
+) -> Result<(ProductQuestion, bool)> {
+    #[derive(sqlx::FromRow)]
+    struct Row {
+        #[sqlx(flatten)]
+        question: ProductQuestion,
+        first_answer: bool,
+    }
+
+    let row = sqlx::query_as::<_, Row>(
+        "WITH prev AS (
+             SELECT id, answer IS NULL AS first_answer FROM product_questions
+             WHERE id = $1
+             FOR UPDATE
+         )
+         UPDATE product_questions q
+         SET answer = $2, answered_by_user_id = $3,
+             answered_at = COALESCE(q.answered_at, NOW()), updated_at = NOW()
+         FROM prev
+         WHERE q.id = prev.id
+         RETURNING q.*, prev.first_answer",
+    )
+    .bind(id)
+    .bind(answer)
+    .bind(answered_by_user_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("კითხვა id-ით {} ვერ მოიძებნა", id)))?;
+
+    Ok((row.question, row.first_answer))
+}
+
+pub async fn delete_question(pool: &PgPool, id: i32) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM product_questions WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
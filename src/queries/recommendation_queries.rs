@@ -0,0 +1,163 @@
+use sqlx::PgPool;
+
+use crate::error::Result;
+
+/// Recomputes `product_recommendations` from co-views (same user, or same
+/// session for anonymous visitors) and co-purchases (same paid order). Only
+/// the `per_product` best pairs are kept for each product.
+pub async fn rebuild(
+    pool: &PgPool,
+    view_window_days: i32,
+    purchase_window_days: i32,
+    purchase_weight: f64,
+    per_product: i64,
+) -> Result<u64> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM product_recommendations")
+        .execute(&mut *tx)
+        .await?;
+
+    let inserted = sqlx::query(
+        "WITH viewers AS (
+             SELECT DISTINCT product_id,
+                    COALESCE('u' || user_id::TEXT, 's' || session_id::TEXT) AS viewer
+             FROM product_views
+             WHERE viewed_at >= NOW() - make_interval(days => $1)
+               AND (user_id IS NOT NULL OR session_id IS NOT NULL)
+         ),
+         co_views AS (
+             SELECT a.product_id, b.product_id AS related_product_id, COUNT(*) AS n
+             FROM viewers a
+             JOIN viewers b ON b.viewer = a.viewer AND b.product_id <> a.product_id
+             GROUP BY a.product_id, b.product_id
+         ),
+         purchases AS (
+             SELECT DISTINCT oi.order_id, oi.product_id
+             FROM order_items oi
+             JOIN orders o ON o.id = oi.order_id
+             WHERE o.status IN ('approved', 'shipped', 'delivered', 'partially_refunded')
+               AND o.created_at >= NOW() - make_interval(days => $2)
+         ),
+         co_purchases AS (
+             SELECT a.product_id, b.product_id AS related_product_id, COUNT(*) AS n
+             FROM purchases a
+             JOIN purchases b ON b.order_id = a.order_id AND b.product_id <> a.product_id
+             GROUP BY a.product_id, b.product_id
+         ),
+         pairs AS (
+             SELECT COALESCE(v.product_id, c.product_id) AS product_id,
+                    COALESCE(v.related_product_id, c.related_product_id) AS related_product_id,
+                    COALESCE(v.n, 0)::INTEGER AS co_views,
+                    COALESCE(c.n, 0)::INTEGER AS co_purchases
+             FROM co_views v
+             FULL JOIN co_purchases c
+                 ON c.product_id = v.product_id AND c.related_product_id = v.related_product_id
+         ),
+         ranked AS (
+             SELECT product_id, related_product_id, co_views, co_purchases,
+                    co_views + $3 * co_purchases AS score,
+                    ROW_NUMBER() OVER (
+                        PARTITION BY product_id
+                        ORDER BY co_views + $3 * co_purchases DESC, related_product_id
+                    ) AS rank
+             FROM pairs
+         )
+         INSERT INTO product_recommendations
+             (product_id, related_product_id, co_views, co_purchases, score)
+         SELECT product_id, related_product_id, co_views, co_purchases, score
+         FROM ranked
+         WHERE rank <= $4",
+    )
+    .bind(view_window_days)
+    .bind(purchase_window_days)
+    .bind(purchase_weight)
+    .bind(per_product)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    tx.commit().await?;
+    Ok(inserted)
+}
+
+/// Enabled products scored for `product_id`, best first. With `viewed_only`
+/// only pairs that were actually co-viewed are returned, ranked by co-views.
+pub async fn get_related_ids(
+    pool: &PgPool,
+    product_id: &str,
+    viewed_only: bool,
+    limit: i64,
+) -> Result<Vec<String>> {
+    let ids = sqlx::query_scalar(
+        "SELECT r.related_product_id
+         FROM product_recommendations r
+         JOIN products p ON p.id = r.related_product_id AND p.enabled = true
+         WHERE r.product_id = $1 AND (NOT $2 OR r.co_views > 0)
+         ORDER BY CASE WHEN $2 THEN r.co_views ELSE 0 END DESC, r.score DESC,
+                  r.related_product_id
+         LIMIT $3",
+    )
+    .bind(product_id)
+    .bind(viewed_only)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ids)
+}
+
+/// Products seen or bought by the user recently. These seed the personal
+/// recommendations and are never recommended back. Views the user cleared
+/// from their history are left out.
+pub async fn get_user_seed_ids(
+    pool: &PgPool,
+    user_id: i32,
+    window_days: i32,
+) -> Result<Vec<String>> {
+    let ids = sqlx::query_scalar(
+        "SELECT product_id
+         FROM product_views
+         WHERE user_id = $1 AND viewed_at >= NOW() - make_interval(days => $2)
+           AND hidden_from_history_at IS NULL
+         UNION
+         SELECT oi.product_id
+         FROM order_items oi
+         JOIN orders o ON o.id = oi.order_id
+         WHERE o.user_id = $1 AND oi.product_id IS NOT NULL
+           AND o.status IN ('approved', 'shipped', 'delivered', 'partially_refunded')",
+    )
+    .bind(user_id)
+    .bind(window_days)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ids)
+}
+
+/// Enabled products ranked by their summed score against `seed_ids`.
+pub async fn get_ids_for_seeds(
+    pool: &PgPool,
+    seed_ids: &[String],
+    limit: i64,
+) -> Result<Vec<String>> {
+    if seed_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let ids = sqlx::query_scalar(
+        "SELECT r.related_product_id
+         FROM product_recommendations r
+         JOIN products p ON p.id = r.related_product_id AND p.enabled = true
+         WHERE r.product_id = ANY($1) AND NOT (r.related_product_id = ANY($1))
+         GROUP BY r.related_product_id
+         ORDER BY SUM(r.score) DESC, r.related_product_id
+         LIMIT $2",
+    )
+    .bind(seed_ids)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ids)
+}
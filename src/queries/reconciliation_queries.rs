@@ -0,0 +1,117 @@
+use chrono::NaiveDate;
+use sqlx::{PgPool, types::Json};
+
+use crate::{
+    error::Result,
+    models::{Order, ReconciliationMismatch, ReconciliationReport},
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 30;
+const MAX_PAGE_SIZE: i64 = 100;
+
+/// Card orders still waiting on Flitt that haven't been polled recently,
+/// least recently checked first.
+pub async fn find_unsettled_card_orders(
+    pool: &PgPool,
+    stale_after_minutes: i32,
+    lookback_hours: i32,
+    limit: i64,
+) -> Result<Vec<Order>> {
+    let orders = sqlx::query_as::<_, Order>(
+        "SELECT * FROM orders
+         WHERE checkout_url IS NOT NULL
+           AND status IN ('pending', 'expired')
+           AND created_at < NOW() - make_interval(mins => $1)
+           AND created_at > NOW() - make_interval(hours => $2)
+           AND (payment_checked_at IS NULL
+                OR payment_checked_at < NOW() - make_interval(mins => $1))
+         ORDER BY payment_checked_at NULLS FIRST, created_at
+         LIMIT $3",
+    )
+    .bind(stale_after_minutes)
+    .bind(lookback_hours)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(orders)
+}
+
+pub async fn mark_payment_checked(pool: &PgPool, order_db_id: i32) -> Result<()> {
+    sqlx::query("UPDATE orders SET payment_checked_at = NOW() WHERE id = $1")
+        .bind(order_db_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Orders sent to Flitt on the given UTC day.
+pub async fn find_card_orders_created_on(pool: &PgPool, date: NaiveDate) -> Result<Vec<Order>> {
+    let orders = sqlx::query_as::<_, Order>(
+        "SELECT * FROM orders
+         WHERE checkout_url IS NOT NULL AND (created_at AT TIME ZONE 'UTC')::date = $1
+         ORDER BY created_at",
+    )
+    .bind(date)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(orders)
+}
+
+pub async fn report_exists(pool: &PgPool, date: NaiveDate) -> Result<bool> {
+    let exists = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM payment_reconciliation_reports WHERE report_date = $1)",
+    )
+    .bind(date)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(exists)
+}
+
+/// Stores the report for a day. Returns `None` if another instance got there first.
+pub async fn insert_report(
+    pool: &PgPool,
+    date: NaiveDate,
+    orders_checked: i32,
+    mismatches: &[ReconciliationMismatch],
+) -> Result<Option<ReconciliationReport>> {
+    let report = sqlx::query_as::<_, ReconciliationReport>(
+        "INSERT INTO payment_reconciliation_reports
+             (report_date, orders_checked, mismatch_count, mismatches)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (report_date) DO NOTHING
+         RETURNING *",
+    )
+    .bind(date)
+    .bind(orders_checked)
+    .bind(mismatches.len() as i32)
+    .bind(Json(mismatches))
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(report)
+}
+
+pub async fn get_reports(
+    pool: &PgPool,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Vec<ReconciliationReport>> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let offset = offset.unwrap_or(0).max(0);
+
+    let reports = sqlx::query_as::<_, ReconciliationReport>(
+        "SELECT * FROM payment_reconciliation_reports
+         ORDER BY report_date DESC
+         LIMIT $1 OFFSET $2",
+    )
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(reports)
+}
@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+
+use rust_decimal::{Decimal, prelude::ToPrimitive};
+use sqlx::{PgPool, types::Json};
+
+use crate::{
+    error::{AppError, Result},
+    models::{
+        BundleItem, Order, OrderStatus, OrderStatusSource, Refund, RefundItem, RefundItemRequest,
+        RefundResponse, RefundStatus, UnsettledRefund,
+    },
+    queries::{coin_queries, order_queries},
+};
+
+#[derive(sqlx::FromRow)]
+struct RefundableLine {
+    id: i32,
+    quantity: i32,
+    price_at_purchase: Decimal,
+    refunded_quantity: i64,
+}
+
+/// Validates the request against what is still refundable and records it as
+/// `pending` before Flitt is called, so concurrent refunds can't overdraw.
+pub async fn create_pending_refund(
+    pool: &PgPool,
+    order_db_id: i32,
+    amount: Option<i32>,
+    items: &[RefundItemRequest],
+    comment: Option<&str>,
+    created_by_user_id: i32,
+) -> Result<RefundResponse> {
+    let mut tx = pool.begin().await?;
+
+    let order = sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE id = $1 FOR UPDATE")
+        .bind(order_db_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("შეკვეთა id-ით {} ვერ მოიძებნა", order_db_id)))?;
+
+    if !matches!(
+        order.status,
+        OrderStatus::Approved
+            | OrderStatus::Shipped
+            | OrderStatus::Delivered
+            | OrderStatus::PartiallyRefunded
+    ) {
+        return Err(AppError::BadRequest(
+            "თანხის დაბრუნება შესაძლებელია მხოლოდ გადახდილ შეკვეთაზე".to_string(),
+        ));
+    }
+
+    let refunded_amount = sqlx::query_scalar::<_, i64>(
+        "SELECT COALESCE(SUM(amount), 0)::bigint FROM refunds
+         WHERE order_id = $1 AND status IN ('pending', 'approved', 'processing')",
+    )
+    .bind(order.id)
+    .fetch_one(&mut *tx)
+    .await?;
+    let remaining_amount = i64::from(order.amount) - refunded_amount;
+
+    let lines = sqlx::query_as::<_, RefundableLine>(
+        "SELECT oi.id, oi.quantity, oi.price_at_purchase,
+                COALESCE(SUM(ri.quantity) FILTER (
+                    WHERE r.status IN ('pending', 'approved', 'processing')
+                ), 0)::bigint AS refunded_quantity
+         FROM order_items oi
+         LEFT JOIN refund_items ri ON ri.order_item_id = oi.id
+         LEFT JOIN refunds r ON r.id = ri.refund_id
+         WHERE oi.order_id = $1
+         GROUP BY oi.id",
+    )
+    .bind(order.id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let lines: HashMap<i32, RefundableLine> = lines.into_iter().map(|l| (l.id, l)).collect();
+
+    // an amount-only refund that takes what is left of the order covers every
+    // remaining line, so it restocks them like a full refund does
+    let whole_remainder = amount.is_none_or(|amount| i64::from(amount) >= remaining_amount);
+    let items: Vec<RefundItemRequest> = if items.is_empty() && whole_remainder {
+        lines
+            .values()
+            .filter(|l| i64::from(l.quantity) > l.refunded_quantity)
+            .map(|l| RefundItemRequest {
+                order_item_id: l.id,
+                quantity: l.quantity - l.refunded_quantity as i32,
+            })
+            .collect()
+    } else {
+        items.to_vec()
+    };
+
+    let mut items_amount = Decimal::ZERO;
+    for item in &items {
+        let line = lines.get(&item.order_item_id).ok_or_else(|| {
+            AppError::BadRequest(format!(
+                "პროდუქტი {} ამ შეკვეთას არ ეკუთვნის",
+                item.order_item_id
+            ))
+        })?;
+        if item.quantity <= 0
+            || i64::from(item.quantity) > i64::from(line.quantity) - line.refunded_quantity
+        {
+            return Err(AppError::BadRequest(format!(
+                "არასწორი დასაბრუნებელი რაოდენობა პროდუქტისთვის {}",
+                item.order_item_id
+            )));
+        }
+        items_amount += line.price_at_purchase * Decimal::from(item.quantity);
+    }
+
+    let amount = match amount {
+        Some(amount) => i64::from(amount),
+        None if items.is_empty() || remaining_amount <= 0 => remaining_amount,
+        None => (items_amount * Decimal::from(100))
+            .trunc()
+            .to_i64()
+            .unwrap_or(i64::MAX)
+            .min(remaining_amount),
+    };
+
+    if amount <= 0 || amount > remaining_amount {
+        return Err(AppError::BadRequest(format!(
+            "დასაბრუნებელი თანხა უნდა იყოს 1-დან {}-მდე თეთრი",
+            remaining_amount.max(0)
+        )));
+    }
+
+    let refund = sqlx::query_as::<_, Refund>(
+        "INSERT INTO refunds (order_id, amount, comment, created_by_user_id)
+         VALUES ($1, $2, $3, $4)
+         RETURNING *",
+    )
+    .bind(order.id)
+    .bind(amount as i32)
+    .bind(comment)
+    .bind(created_by_user_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let order_item_ids: Vec<i32> = items.iter().map(|i| i.order_item_id).collect();
+    let quantities: Vec<i32> = items.iter().map(|i| i.quantity).collect();
+
+    let items = sqlx::query_as::<_, RefundItem>(
+        "INSERT INTO refund_items (refund_id, order_item_id, quantity)
+         SELECT $1, unnest($2::int[]), unnest($3::int[])
+         RETURNING *",
+    )
+    .bind(refund.id)
+    .bind(&order_item_ids)
+    .bind(&quantities)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(RefundResponse { refund, items })
+}
+
+/// Stores Flitt's outcome. A `processing` refund stays open until the
+/// reconciler sees it settle. Approved refunds put their lines back into stock
+/// and move the order to `refunded` or `partially_refunded`.
+pub async fn finish_refund(
+    pool: &PgPool,
+    refund_id: i32,
+    status: RefundStatus,
+) -> Result<RefundResponse> {
+    let mut tx = pool.begin().await?;
+
+    let refund = sqlx::query_as::<_, Refund>(
+        "UPDATE refunds SET status = $2, updated_at = NOW()
+         WHERE id = $1 AND status IN ('pending', 'processing') AND status <> $2
+         RETURNING *",
+    )
+    .bind(refund_id)
+    .bind(status)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let refund = match refund {
+        Some(refund) => refund,
+        None => {
+            tx.rollback().await?;
+            return get_refund(pool, refund_id).await;
+        }
+    };
+
+    let items = sqlx::query_as::<_, RefundItem>("SELECT * FROM refund_items WHERE refund_id = $1")
+        .bind(refund.id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+    if status == RefundStatus::Approved {
+        let order = sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE id = $1 FOR UPDATE")
+            .bind(refund.order_id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        // only units that actually left stock can go back into it
+        if order.stock_deducted_at.is_some() && order.stock_restored_at.is_none() {
+            #[derive(sqlx::FromRow)]
+            struct Line {
+                variant_id: Option<i32>,
+                product_id: Option<String>,
+                color: Option<String>,
+                bundle_items: Option<Json<Vec<BundleItem>>>,
+                quantity: i32,
+            }
+
+            let lines = sqlx::query_as::<_, Line>(
+                "SELECT oi.variant_id, oi.product_id, oi.color, oi.bundle_items, ri.quantity
+                 FROM refund_items ri
+                 JOIN order_items oi ON oi.id = ri.order_item_id
+                 WHERE ri.refund_id = $1",
+            )
+            .bind(refund.id)
+            .fetch_all(&mut *tx)
+            .await?;
+
+            for line in &lines {
+                let Some(product_id) = &line.product_id else {
+                    continue;
+                };
+                order_queries::restore_item_stock(
+                    &mut tx,
+                    order.id,
+                    line.variant_id,
+                    product_id,
+                    line.color.as_deref(),
+                    line.bundle_items.as_deref().map(|b| b.as_slice()),
+                    line.quantity,
+                )
+                .await?;
+            }
+        }
+
+        let refunded_amount = sqlx::query_scalar::<_, i64>(
+            "SELECT COALESCE(SUM(amount), 0)::bigint FROM refunds
+             WHERE order_id = $1 AND status = 'approved'",
+        )
+        .bind(order.id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        coin_queries::clawback_for_order(&mut tx, &order, Some(refunded_amount)).await?;
+
+        let next = if refunded_amount >= i64::from(order.amount) {
+            OrderStatus::Refunded
+        } else {
+            OrderStatus::PartiallyRefunded
+        };
+
+        if order.status.can_transition_to(next) {
+            let change = order_queries::StatusChange {
+                source: OrderStatusSource::Operator,
+                changed_by_user_id: refund.created_by_user_id,
+                comment: refund.comment.as_deref(),
+            };
+            order_queries::set_status(&mut tx, &order, next, &change).await?;
+        }
+    }
+
+    tx.commit().await?;
+    Ok(RefundResponse { refund, items })
+}
+
+/// Refunds still `pending` or `processing` that weren't looked at in the last
+/// `stale_after_minutes`, oldest first. A `pending` one that old was left
+/// behind by a request that never got Flitt's answer.
+pub async fn find_unsettled_refunds(
+    pool: &PgPool,
+    stale_after_minutes: i32,
+    lookback_hours: i32,
+    limit: i64,
+) -> Result<Vec<UnsettledRefund>> {
+    let refunds = sqlx::query_as::<_, UnsettledRefund>(
+        "SELECT r.id, o.order_id, r.status,
+                (SELECT COALESCE(SUM(e.amount), 0) FROM refunds e
+                 WHERE e.order_id = r.order_id
+                   AND (e.status = 'approved'
+                        OR (e.status IN ('pending', 'processing') AND e.id <= r.id))
+                )::bigint AS settles_at,
+                r.created_at < NOW() - make_interval(hours => $2) AS overdue
+         FROM refunds r
+         JOIN orders o ON o.id = r.order_id
+         WHERE r.status IN ('pending', 'processing')
+           AND r.updated_at < NOW() - make_interval(mins => $1)
+         ORDER BY r.updated_at, r.id
+         LIMIT $3",
+    )
+    .bind(stale_after_minutes)
+    .bind(lookback_hours)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(refunds)
+}
+
+pub async fn mark_refund_checked(pool: &PgPool, refund_id: i32) -> Result<()> {
+    sqlx::query("UPDATE refunds SET updated_at = NOW() WHERE id = $1")
+        .bind(refund_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+async fn get_refund(pool: &PgPool, refund_id: i32) -> Result<RefundResponse> {
+    let refund = sqlx::query_as::<_, Refund>("SELECT * FROM refunds WHERE id = $1")
+        .bind(refund_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("დაბრუნება id-ით {} ვერ მოიძებნა", refund_id)))?;
+
+    let items = sqlx::query_as::<_, RefundItem>("SELECT * FROM refund_items WHERE refund_id = $1")
+        .bind(refund_id)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(RefundResponse { refund, items })
+}
+
+pub async fn get_order_refunds(pool: &PgPool, order_db_id: i32) -> Result<Vec<RefundResponse>> {
+    let refunds = sqlx::query_as::<_, Refund>(
+        "SELECT * FROM refunds WHERE order_id = $1 ORDER BY created_at DESC, id DESC",
+    )
+    .bind(order_db_id)
+    .fetch_all(pool)
+    .await?;
+
+    let refund_ids: Vec<i32> = refunds.iter().map(|r| r.id).collect();
+    let items = sqlx::query_as::<_, RefundItem>(
+        "SELECT * FROM refund_items WHERE refund_id = ANY($1) ORDER BY id",
+    )
+    .bind(&refund_ids)
+    .fetch_all(pool)
+    .await?;
+
+    let mut items_by_refund: HashMap<i32, Vec<RefundItem>> = HashMap::new();
+    for item in items {
+        items_by_refund
+            .entry(item.refund_id)
+            .or_default()
+            .push(item);
+    }
+
+    Ok(refunds
+        .into_iter()
+        .map(|refund| {
+            let items = items_by_refund.remove(&refund.id).unwrap_or_default();
+            RefundResponse { refund, items }
+        })
+        .collect())
+}
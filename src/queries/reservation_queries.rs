@@ -0,0 +1,150 @@
+use std::collections::{BTreeMap, HashMap};
+
+use sqlx::{PgConnection, PgPool};
+
+use crate::{
+    error::Result,
+    models::{Order, OrderItemData, OrderStatus, OrderStatusSource},
+    queries::{coin_queries, order_queries},
+};
+
+/// Holds stock for a pending order. Returns `false` without reserving anything
+/// when another order already holds the remaining units.
+pub async fn reserve_for_order(
+    pool: &PgPool,
+    order_db_id: i32,
+    items: &[OrderItemData],
+    ttl_minutes: i32,
+) -> Result<bool> {
+    // BTreeMap keeps the row lock order stable between concurrent checkouts
+    let mut demand: BTreeMap<i32, (&str, Option<&str>, i32)> = BTreeMap::new();
+    for item in items {
+        if let Some(bundle_items) = &item.bundle_items {
+            for component in bundle_items {
+                demand
+                    .entry(component.variant_id)
+                    .or_insert((&component.product_id, component.color.as_deref(), 0))
+                    .2 += component.quantity * item.quantity;
+            }
+            continue;
+        }
+        let (Some(product_id), Some(variant_id)) = (item.product_id.as_deref(), item.variant_id)
+        else {
+            continue;
+        };
+        demand
+            .entry(variant_id)
+            .or_insert((product_id, item.color.as_deref(), 0))
+            .2 += item.quantity;
+    }
+
+    let mut tx = pool.begin().await?;
+
+    for (&variant_id, &(_, _, quantity)) in &demand {
+        let stock = sqlx::query_scalar::<_, i32>(
+            "SELECT stock FROM product_variants WHERE id = $1 FOR UPDATE",
+        )
+        .bind(variant_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .unwrap_or(0);
+
+        let reserved = sqlx::query_scalar::<_, i64>(
+            "SELECT COALESCE(SUM(quantity), 0)::bigint FROM stock_reservations
+             WHERE variant_id = $1 AND expires_at > NOW()",
+        )
+        .bind(variant_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if i64::from(stock) - reserved < i64::from(quantity) {
+            tx.rollback().await?;
+            return Ok(false);
+        }
+    }
+
+    let variant_ids: Vec<i32> = demand.keys().copied().collect();
+    let product_ids: Vec<&str> = demand.values().map(|(p, _, _)| *p).collect();
+    let colors: Vec<Option<&str>> = demand.values().map(|(_, c, _)| *c).collect();
+    let quantities: Vec<i32> = demand.values().map(|(_, _, q)| *q).collect();
+
+    sqlx::query(
+        "INSERT INTO stock_reservations (order_id, variant_id, product_id, color, quantity, expires_at)
+         SELECT $1, unnest($2::int[]), unnest($3::text[]), unnest($4::text[]), unnest($5::int[]),
+                NOW() + make_interval(mins => $6)",
+    )
+    .bind(order_db_id)
+    .bind(&variant_ids)
+    .bind(&product_ids)
+    .bind(&colors)
+    .bind(&quantities)
+    .bind(ttl_minutes)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Units currently held by unexpired reservations, keyed by variant.
+pub async fn find_reserved_quantities(
+    pool: &PgPool,
+    product_ids: &[String],
+) -> Result<HashMap<i32, i32>> {
+    let rows = sqlx::query_as::<_, (i32, i32)>(
+        "SELECT variant_id, SUM(quantity)::int
+         FROM stock_reservations
+         WHERE product_id = ANY($1) AND expires_at > NOW()
+         GROUP BY variant_id",
+    )
+    .bind(product_ids)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().collect())
+}
+
+pub async fn release_for_order(conn: &mut PgConnection, order_db_id: i32) -> Result<()> {
+    sqlx::query("DELETE FROM stock_reservations WHERE order_id = $1")
+        .bind(order_db_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Drops expired holds and marks the pending orders they belonged to as expired.
+pub async fn expire_abandoned_orders(pool: &PgPool) -> Result<Vec<Order>> {
+    let mut tx = pool.begin().await?;
+
+    let orders = sqlx::query_as::<_, Order>(
+        "WITH expired AS (
+             DELETE FROM stock_reservations WHERE expires_at <= NOW() RETURNING order_id
+         )
+         UPDATE orders SET status = 'expired', updated_at = NOW()
+         WHERE id IN (SELECT order_id FROM expired) AND status = 'pending'
+         RETURNING *",
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let change = order_queries::StatusChange {
+        source: OrderStatusSource::System,
+        changed_by_user_id: None,
+        comment: None,
+    };
+    for order in &orders {
+        coin_queries::return_for_order(&mut tx, order).await?;
+        order_queries::insert_status_history(
+            &mut tx,
+            order.id,
+            Some(OrderStatus::Pending),
+            OrderStatus::Expired,
+            &change,
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(orders)
+}
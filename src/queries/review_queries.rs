@@ -0,0 +1,246 @@
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    models::{ProductReview, ProductReviewImage, ReviewQuery, ReviewStatus},
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 10;
+const MAX_PAGE_SIZE: i64 = 100;
+
+/// Only customers who received the product may review it.
+pub async fn has_purchased(pool: &PgPool, user_id: i32, product_id: &str) -> Result<bool> {
+    let purchased = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (
+             SELECT 1 FROM orders o
+             JOIN order_items oi ON oi.order_id = o.id
+             WHERE o.user_id = $1 AND oi.product_id = $2
+               AND o.status IN ('approved', 'shipped', 'delivered', 'partially_refunded')
+         )",
+    )
+    .bind(user_id)
+    .bind(product_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(purchased)
+}
+
+pub async fn find_by_user_and_product(
+    pool: &PgPool,
+    user_id: i32,
+    product_id: &str,
+) -> Result<Option<ProductReview>> {
+    let review = sqlx::query_as::<_, ProductReview>(
+        "SELECT * FROM product_reviews WHERE user_id = $1 AND product_id = $2",
+    )
+    .bind(user_id)
+    .bind(product_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(review)
+}
+
+/// Creates a pending review and attaches the uploaded photos the same user
+/// requested URLs for.
+pub async fn create_review(
+    pool: &PgPool,
+    user_id: i32,
+    product_id: &str,
+    rating: i32,
+    body: Option<&str>,
+    image_uuids: &[Uuid],
+) -> Result<ProductReview> {
+    let mut tx = pool.begin().await?;
+
+    let review = sqlx::query_as::<_, ProductReview>(
+        "INSERT INTO product_reviews (product_id, user_id, rating, body)
+         VALUES ($1, $2, $3, $4)
+         RETURNING *",
+    )
+    .bind(product_id)
+    .bind(user_id)
+    .bind(rating)
+    .bind(body)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if !image_uuids.is_empty() {
+        sqlx::query(
+            "UPDATE product_review_images
+             SET review_id = $1, position = data.position
+             FROM (SELECT unnest($2::uuid[]) AS image_uuid, generate_subscripts($2::uuid[], 1) - 1 AS position) AS data
+             WHERE product_review_images.image_uuid = data.image_uuid
+               AND product_review_images.review_id IS NULL
+               AND product_review_images.user_id = $3",
+        )
+        .bind(review.id)
+        .bind(image_uuids)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(review)
+}
+
+pub async fn add_review_image(
+    pool: &PgPool,
+    user_id: i32,
+    image_uuid: Uuid,
+    extension: &str,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO product_review_images (user_id, image_uuid, extension) VALUES ($1, $2, $3)",
+    )
+    .bind(user_id)
+    .bind(image_uuid)
+    .bind(extension)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_images_for_reviews(
+    pool: &PgPool,
+    review_ids: &[i32],
+) -> Result<Vec<ProductReviewImage>> {
+    let images = sqlx::query_as::<_, ProductReviewImage>(
+        "SELECT * FROM product_review_images WHERE review_id = ANY($1) ORDER BY position, id",
+    )
+    .bind(review_ids)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(images)
+}
+
+/// Reviews with their author's name, newest first.
+pub async fn search_reviews(
+    pool: &PgPool,
+    params: &ReviewQuery,
+) -> Result<(Vec<(ProductReview, String)>, i64, i64, i64)> {
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let offset = params.offset.unwrap_or(0).max(0);
+
+    let mut qb = sqlx::QueryBuilder::<sqlx::Postgres>::new(
+        "SELECT r.*, u.name AS author_name, COUNT(*) OVER() AS total_count
+         FROM product_reviews r
+         JOIN users u ON u.id = r.user_id
+         WHERE 1=1",
+    );
+
+    if let Some(status) = params.status {
+        qb.push(" AND r.status = ");
+        qb.push_bind(status);
+    }
+    if let Some(ref product_id) = params.product_id {
+        qb.push(" AND r.product_id = ");
+        qb.push_bind(product_id);
+    }
+
+    qb.push(" ORDER BY r.created_at DESC, r.id DESC LIMIT ");
+    qb.push_bind(limit);
+    qb.push(" OFFSET ");
+    qb.push_bind(offset);
+
+    #[derive(sqlx::FromRow)]
+    struct Row {
+        #[sqlx(flatten)]
+        review: ProductReview,
+        author_name: String,
+        total_count: i64,
+    }
+
+    let rows = qb.build_query_as::<Row>().fetch_all(pool).await?;
+    let total = rows.first().map(|r| r.total_count).unwrap_or(0);
+    let reviews = rows
+        .into_iter()
+        .map(|r| (r.review, r.author_name))
+        .collect();
+
+    Ok((reviews, total, limit, offset))
+}
+
+async fn refresh_product_rating(conn: &mut PgConnection, product_id: &str) -> Result<()> {
+    sqlx::query(
+        "UPDATE products SET
+             rating_avg = (
+                 SELECT ROUND(AVG(rating), 2) FROM product_reviews
+                 WHERE product_id = $1 AND status = 'approved'
+             ),
+             rating_count = (
+                 SELECT COUNT(*) FROM product_reviews
+                 WHERE product_id = $1 AND status = 'approved'
+             )
+         WHERE id = $1",
+    )
+    .bind(product_id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn moderate_review(
+    pool: &PgPool,
+    id: i32,
+    status: ReviewStatus,
+    moderated_by_user_id: i32,
+    comment: Option<&str>,
+) -> Result<ProductReview> {
+    let mut tx = pool.begin().await?;
+
+    let review = sqlx::query_as::<_, ProductReview>(
+        "UPDATE product_reviews
+         SET status = $2, moderated_by_user_id = $3, moderated_at = NOW(),
+             moderation_comment = $4, updated_at = NOW()
+         WHERE id = $1
+         RETURNING *",
+    )
+    .bind(id)
+    .bind(status)
+    .bind(moderated_by_user_id)
+    .bind(comment)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("შეფასება id-ით {} ვერ მოიძებნა", id)))?;
+
+    refresh_product_rating(&mut tx, &review.product_id).await?;
+
+    tx.commit().await?;
+    Ok(review)
+}
+
+/// Deletes the review and returns its photos so the caller can remove them
+/// from storage.
+pub async fn delete_review(pool: &PgPool, id: i32) -> Result<Vec<ProductReviewImage>> {
+    let mut tx = pool.begin().await?;
+
+    let images = sqlx::query_as::<_, ProductReviewImage>(
+        "SELECT * FROM product_review_images WHERE review_id = $1",
+    )
+    .bind(id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let product_id = sqlx::query_scalar::<_, String>(
+        "DELETE FROM product_reviews WHERE id = $1 RETURNING product_id",
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("შეფასება id-ით {} ვერ მოიძებნა", id)))?;
+
+    refresh_product_rating(&mut tx, &product_id).await?;
+
+    tx.commit().await?;
+    Ok(images)
+}
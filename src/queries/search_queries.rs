@@ -0,0 +1,65 @@
+use sqlx::PgPool;
+
+use crate::{
+    error::{AppError, Result},
+    models::SearchSynonym,
+};
+
+fn conflict_or(err: sqlx::Error) -> AppError {
+    match &err {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            AppError::Conflict("სინონიმები ამ სიტყვისთვის უკვე არსებობს".to_string())
+        }
+        _ => AppError::DatabaseError(err),
+    }
+}
+
+pub async fn get_synonyms(pool: &PgPool) -> Result<Vec<SearchSynonym>> {
+    let synonyms =
+        sqlx::query_as::<_, SearchSynonym>("SELECT * FROM search_synonyms ORDER BY term ASC")
+            .fetch_all(pool)
+            .await?;
+    Ok(synonyms)
+}
+
+pub async fn create_synonym(
+    pool: &PgPool,
+    term: &str,
+    synonyms: &[String],
+) -> Result<SearchSynonym> {
+    sqlx::query_as::<_, SearchSynonym>(
+        "INSERT INTO search_synonyms (term, synonyms) VALUES ($1, $2) RETURNING *",
+    )
+    .bind(term)
+    .bind(synonyms)
+    .fetch_one(pool)
+    .await
+    .map_err(conflict_or)
+}
+
+pub async fn update_synonym(
+    pool: &PgPool,
+    id: i32,
+    term: &str,
+    synonyms: &[String],
+) -> Result<Option<SearchSynonym>> {
+    sqlx::query_as::<_, SearchSynonym>(
+        "UPDATE search_synonyms SET term = $2, synonyms = $3, updated_at = NOW()
+         WHERE id = $1
+         RETURNING *",
+    )
+    .bind(id)
+    .bind(term)
+    .bind(synonyms)
+    .fetch_optional(pool)
+    .await
+    .map_err(conflict_or)
+}
+
+pub async fn delete_synonym(pool: &PgPool, id: i32) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM search_synonyms WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
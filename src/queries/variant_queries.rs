@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+
+use sqlx::{PgPool, types::Json};
+
+use crate::{
+    error::{AppError, Result},
+    models::{CreateVariantRequest, ProductVariant, UpdateVariantRequest},
+    queries::inventory_queries,
+};
+
+/// SKUs, barcodes and attribute sets are unique; report a clash instead of a
+/// database error.
+fn conflict_or(err: sqlx::Error) -> AppError {
+    match &err {
+        sqlx::Error::Database(db) if db.is_unique_violation() => AppError::Conflict(
+            "ვარიანტი ამ SKU-ით, შტრიხკოდით ან მახასიათებლებით უკვე არსებობს".to_string(),
+        ),
+        _ => AppError::DatabaseError(err),
+    }
+}
+
+pub async fn find_by_product_id(pool: &PgPool, product_id: &str) -> Result<Vec<ProductVariant>> {
+    let variants = sqlx::query_as::<_, ProductVariant>(
+        "SELECT * FROM product_variants WHERE product_id = $1 ORDER BY id",
+    )
+    .bind(product_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(variants)
+}
+
+pub async fn find_by_product_ids(
+    pool: &PgPool,
+    product_ids: &[String],
+) -> Result<HashMap<String, Vec<ProductVariant>>> {
+    let variants = sqlx::query_as::<_, ProductVariant>(
+        "SELECT * FROM product_variants WHERE product_id = ANY($1) ORDER BY product_id, id",
+    )
+    .bind(product_ids)
+    .fetch_all(pool)
+    .await?;
+
+    let mut groups: HashMap<String, Vec<ProductVariant>> = HashMap::new();
+    for variant in variants {
+        groups
+            .entry(variant.product_id.clone())
+            .or_default()
+            .push(variant);
+    }
+    Ok(groups)
+}
+
+/// Distinct products the given variants belong to.
+pub async fn find_product_ids(pool: &PgPool, variant_ids: &[i32]) -> Result<Vec<String>> {
+    let ids = sqlx::query_scalar::<_, String>(
+        "SELECT DISTINCT product_id FROM product_variants WHERE id = ANY($1)",
+    )
+    .bind(variant_ids)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ids)
+}
+
+pub async fn find_by_id(
+    pool: &PgPool,
+    product_id: &str,
+    variant_id: i32,
+) -> Result<Option<ProductVariant>> {
+    let variant = sqlx::query_as::<_, ProductVariant>(
+        "SELECT * FROM product_variants WHERE id = $1 AND product_id = $2",
+    )
+    .bind(variant_id)
+    .bind(product_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(variant)
+}
+
+pub async fn create_variant(
+    pool: &PgPool,
+    product_id: &str,
+    req: &CreateVariantRequest,
+    created_by_user_id: i32,
+) -> Result<ProductVariant> {
+    let mut tx = pool.begin().await?;
+
+    let variant = sqlx::query_as::<_, ProductVariant>(
+        "INSERT INTO product_variants (product_id, sku, attributes, price_override, barcode)
+         VALUES ($1, $2, $3, $4, $5)
+         RETURNING *",
+    )
+    .bind(product_id)
+    .bind(&req.sku)
+    .bind(Json(&req.attributes))
+    .bind(req.price_override)
+    .bind(&req.barcode)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(conflict_or)?;
+
+    inventory_queries::receive_opening_stock(&mut tx, variant.id, req.stock, created_by_user_id)
+        .await?;
+    let variant =
+        sqlx::query_as::<_, ProductVariant>("SELECT * FROM product_variants WHERE id = $1")
+            .bind(variant.id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+    tx.commit().await?;
+    Ok(variant)
+}
+
+pub async fn update_variant(
+    pool: &PgPool,
+    product_id: &str,
+    variant_id: i32,
+    req: &UpdateVariantRequest,
+) -> Result<Option<ProductVariant>> {
+    let variant = sqlx::query_as::<_, ProductVariant>(
+        "UPDATE product_variants SET
+             sku = COALESCE($3, sku),
+             attributes = COALESCE($4, attributes),
+             price_override = CASE WHEN $5 THEN NULL ELSE COALESCE($6, price_override) END,
+             barcode = COALESCE($7, barcode),
+             updated_at = NOW()
+         WHERE id = $1 AND product_id = $2
+         RETURNING *",
+    )
+    .bind(variant_id)
+    .bind(product_id)
+    .bind(&req.sku)
+    .bind(req.attributes.as_ref().map(Json))
+    .bind(req.clear_price_override)
+    .bind(req.price_override)
+    .bind(&req.barcode)
+    .fetch_optional(pool)
+    .await
+    .map_err(conflict_or)?;
+
+    Ok(variant)
+}
+
+pub async fn delete_variant(pool: &PgPool, product_id: &str, variant_id: i32) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM product_variants WHERE id = $1 AND product_id = $2")
+        .bind(variant_id)
+        .bind(product_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,41 @@
+use sqlx::PgPool;
+
+use crate::error::Result;
+
+/// Saved products, newest first. Disabled products stay saved but are hidden.
+pub async fn get_product_ids(pool: &PgPool, user_id: i32) -> Result<Vec<String>> {
+    let ids = sqlx::query_scalar::<_, String>(
+        "SELECT w.product_id FROM wishlists w
+         JOIN products p ON p.id = w.product_id
+         WHERE w.user_id = $1 AND p.enabled = true
+         ORDER BY w.created_at DESC",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ids)
+}
+
+pub async fn add_product(pool: &PgPool, user_id: i32, product_id: &str) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO wishlists (user_id, product_id) VALUES ($1, $2)
+         ON CONFLICT (user_id, product_id) DO NOTHING",
+    )
+    .bind(user_id)
+    .bind(product_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn remove_product(pool: &PgPool, user_id: i32, product_id: &str) -> Result<()> {
+    sqlx::query("DELETE FROM wishlists WHERE user_id = $1 AND product_id = $2")
+        .bind(user_id)
+        .bind(product_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
@@ -12,164 +12,38 @@ use crate::{
     AppState,
     error::{AppError, Result},
     models::*,
-    queries::{admin_queries, category_queries, order_queries, products_queries, user_queries},
+    queries::{
+        admin_queries, bundle_queries, category_queries, order_queries, products_queries,
+        reconciliation_queries, refund_queries, user_queries,
+    },
     services::{
-        flitt_service,
+        flitt_service::{self, ReverseOutcome},
         image_url_service::{delete_objects_by_prefix, delete_single_object, put_object_url},
+        pricing_service, product_alert_service, product_service,
+    },
+    utils::{
+        extractors::{FingerprintedJson, IdempotencyKey},
+        idempotency::{self, Committed},
+        jwt::Claims,
     },
-    utils::jwt::Claims,
 };
 
-fn resolve_discount(
-    price: Option<Decimal>,
-    discount: Option<Decimal>,
-    discounted_price: Option<Decimal>,
-) -> Result<Option<(Decimal, Option<Decimal>)>> {
-    if discount.is_some() && discounted_price.is_some() {
-        return Err(AppError::BadRequest(
-            "discount და discounted_price ერთდროულად ვერ მიეთითება".to_string(),
-        ));
-    }
-
-    if let Some(d) = discount {
-        if d < Decimal::ZERO || d > Decimal::from(100) {
-            return Err(AppError::BadRequest(
-                "discount უნდა იყოს 0-დან 100-მდე".to_string(),
-            ));
-        }
-        if d == Decimal::ZERO {
-            return Ok(Some((Decimal::ZERO, None)));
-        }
-        let p = price
-            .ok_or_else(|| AppError::BadRequest("discount-ისთვის price აუცილებელია".to_string()))?;
-        if p <= Decimal::ZERO {
-            return Err(AppError::BadRequest("price უნდა იყოს დადებითი".to_string()));
-        }
-        let dp = (p * (Decimal::ONE - d / Decimal::from(100))).round_dp(2);
-        return Ok(Some((discount_percent(p, dp), Some(dp))));
-    }
-
-    if let Some(dp) = discounted_price {
-        let p = price.ok_or_else(|| {
-            AppError::BadRequest("discounted_price-ისთვის price აუცილებელია".to_string())
-        })?;
-        if p <= Decimal::ZERO {
-            return Err(AppError::BadRequest("price უნდა იყოს დადებითი".to_string()));
-        }
-        if dp < Decimal::ZERO || dp > p {
-            return Err(AppError::BadRequest(
-                "discounted_price უნდა იყოს 0-დან price-მდე".to_string(),
-            ));
-        }
-        let dp = dp.round_dp(2);
-        if dp == p {
-            return Ok(Some((Decimal::ZERO, None)));
-        }
-        return Ok(Some((discount_percent(p, dp), Some(dp))));
-    }
-
-    Ok(None)
-}
-
-fn discount_percent(price: Decimal, discounted_price: Decimal) -> Decimal {
-    ((price - discounted_price) * Decimal::from(100) / price).round_dp(2)
-}
-
-fn detect_video_platform(url: &str) -> Option<VideoPlatform> {
-    let host = url
-        .split_once("://")
-        .map(|(_, rest)| rest)
-        .unwrap_or(url)
-        .split(['/', '?', '#'])
-        .next()
-        .unwrap_or("")
-        .trim_start_matches("www.")
-        .to_ascii_lowercase();
-
-    match host.as_str() {
-        "youtube.com" | "m.youtube.com" | "youtu.be" => Some(VideoPlatform::Youtube),
-        "facebook.com" | "m.facebook.com" | "fb.watch" | "fb.com" => Some(VideoPlatform::Facebook),
-        _ => None,
-    }
-}
-
-fn validate_videos(urls: &[String]) -> Result<serde_json::Value> {
-    let mut videos = Vec::with_capacity(urls.len());
-    for url in urls {
-        let url = url.trim();
-        if !(url.starts_with("http://") || url.starts_with("https://")) {
-            return Err(AppError::BadRequest(format!("არასწორი ვიდეო URL: {}", url)));
-        }
-        let platform = detect_video_platform(url).ok_or_else(|| {
-            AppError::BadRequest(format!(
-                "მხოლოდ YouTube ან Facebook ვიდეო URL-ებია დაშვებული: {}",
-                url
-            ))
-        })?;
-        videos.push(ProductVideo {
-            platform,
-            url: url.to_string(),
-        });
-    }
-    serde_json::to_value(videos)
-        .map_err(|e| AppError::InternalError(format!("ვიდეოების სერიალიზაცია ვერ მოხერხდა: {}", e)))
-}
-
 // products
 pub async fn create_product(
     State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
     Json(mut payload): Json<ProductRequest>,
 ) -> Result<Json<ProductResponse>> {
-    let id = payload
-        .id
-        .as_ref()
-        .ok_or_else(|| AppError::BadRequest("id აუცილებელია".to_string()))?;
+    let videos = product_service::prepare_create(&state.db, &mut payload).await?;
 
-    if payload.name.is_none() {
-        return Err(AppError::BadRequest("სახელი აუცილებელია".to_string()));
-    }
-
-    if payload.price.is_none() {
-        return Err(AppError::BadRequest("ფასი აუცილებელია".to_string()));
-    }
-
-    if products_queries::find_by_id(&state.db, id).await?.is_some() {
-        return Err(AppError::Conflict(format!(
-            "პროდუქტი id-ით {} უკვე არსებობს",
-            id
-        )));
-    }
-
-    if let Some((discount, discounted_price)) =
-        resolve_discount(payload.price, payload.discount, payload.discounted_price)?
-    {
-        payload.discount = Some(discount);
-        payload.discounted_price = discounted_price;
-    } else {
-        payload.discount = None;
-        payload.discounted_price = None;
-    }
-
-    if let Some(ref seo) = payload.seo {
-        if let Some(ref slug) = seo.slug {
-            if let Some(other_id) = admin_queries::find_product_seo_by_slug(&state.db, slug).await?
-            {
-                if &other_id != id {
-                    return Err(AppError::Conflict(format!(
-                        "slug '{}' უკვე გამოყენებულია",
-                        slug
-                    )));
-                }
-            }
-        }
-    }
-
-    let videos = match payload.videos {
-        Some(ref urls) => validate_videos(urls)?,
-        None => serde_json::json!([]),
-    };
-
-    let product = admin_queries::create_product(&state.db, &payload, &videos).await?;
+    let product = admin_queries::create_product(
+        &state.db,
+        &payload,
+        &videos,
+        claims.user_id,
+        PriceChangeOrigin::admin(claims.user_id),
+    )
+    .await?;
 
     let seo = if let Some(ref seo_req) = payload.seo {
         Some(admin_queries::upsert_product_seo(&state.db, &product.id, seo_req).await?)
@@ -182,6 +56,7 @@ pub async fn create_product(
 
     Ok(Json(ProductResponse {
         videos: ProductResponse::videos_from(&product),
+        rating: ProductResponse::rating_from(&product),
         data: product,
         images,
         categories,
@@ -191,6 +66,7 @@ pub async fn create_product(
 
 pub async fn update_product(
     State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
     Path(id): Path<String>,
     Json(mut payload): Json<ProductRequest>,
 ) -> Result<Json<ProductResponse>> {
@@ -198,51 +74,22 @@ pub async fn update_product(
         .await?
         .ok_or_else(|| AppError::NotFound(format!("პროდუქტი id-ით {} ვერ მოიძებნა", id)))?;
 
-    let effective_price = payload.price.unwrap_or(existing.price);
-    match resolve_discount(
-        Some(effective_price),
-        payload.discount,
-        payload.discounted_price,
-    )? {
-        Some((discount, discounted_price)) => {
-            payload.discount = Some(discount);
-            payload.discounted_price = discounted_price;
-        }
-        None => {
-            payload.discount = match existing.discounted_price {
-                Some(dp) if dp < effective_price => {
-                    payload.discounted_price = Some(dp);
-                    Some(discount_percent(effective_price, dp))
-                }
-                Some(_) => {
-                    payload.discounted_price = None;
-                    Some(Decimal::ZERO)
-                }
-                None => None,
-            };
-        }
-    }
-
-    if let Some(ref seo) = payload.seo {
-        if let Some(ref slug) = seo.slug {
-            if let Some(other_id) = admin_queries::find_product_seo_by_slug(&state.db, slug).await?
-            {
-                if other_id != id {
-                    return Err(AppError::Conflict(format!(
-                        "slug '{}' უკვე გამოყენებულია",
-                        slug
-                    )));
-                }
-            }
-        }
-    }
+    let videos = product_service::prepare_update(&state.db, &existing, &mut payload).await?;
 
-    let videos = match payload.videos {
-        Some(ref urls) => Some(validate_videos(urls)?),
-        None => None,
-    };
+    let product = admin_queries::update_product(
+        &state.db,
+        &id,
+        &payload,
+        videos.as_ref(),
+        PriceChangeOrigin::admin(claims.user_id),
+    )
+    .await?;
 
-    let product = admin_queries::update_product(&state.db, &id, &payload, videos.as_ref()).await?;
+    if let Err(e) =
+        product_alert_service::queue_price_drop_alerts(&state.db, &existing, &product).await
+    {
+        tracing::error!("failed to queue price-drop alerts for {}: {e}", product.id);
+    }
 
     let seo = if let Some(ref seo_req) = payload.seo {
         Some(admin_queries::upsert_product_seo(&state.db, &product.id, seo_req).await?)
@@ -255,6 +102,7 @@ pub async fn update_product(
 
     Ok(Json(ProductResponse {
         videos: ProductResponse::videos_from(&product),
+        rating: ProductResponse::rating_from(&product),
         data: product,
         images,
         categories,
@@ -273,6 +121,12 @@ pub async fn delete_product(
         return Err(AppError::NotFound("პროდუქტი ვერ მოიძებნა".to_string()));
     }
 
+    if bundle_queries::has_component_variants(&state.db, &id).await? {
+        return Err(AppError::Conflict(
+            "პროდუქტი ნაკრების შემადგენელია".to_string(),
+        ));
+    }
+
     let env_prefix = match state.environment {
         crate::config::Environment::Staging => "products-staging",
         crate::config::Environment::Main => "products-main",
@@ -295,6 +149,10 @@ pub async fn generate_product_urls(
     Path(id): Path<String>,
     Json(payload): Json<ProductImageUrlRequest>,
 ) -> Result<Json<ProductImageUrlResponse>> {
+    for variant_id in payload.images.iter().filter_map(|req| req.variant_id) {
+        super::variants::ensure_variant(&state, &id, variant_id).await?;
+    }
+
     let mut responses = Vec::new();
 
     for req in payload.images {
@@ -338,7 +196,7 @@ pub async fn generate_product_urls(
             req.color,
             req.is_primary,
             extension,
-            req.quantity.unwrap_or(0),
+            req.variant_id,
         )
         .await?;
 
@@ -389,19 +247,23 @@ pub async fn update_product_image_metadata(
     Path((product_id, image_uuid)): Path<(String, Uuid)>,
     Json(payload): Json<ImageMetadataUpdate>,
 ) -> Result<Json<ProductImage>> {
-    if payload.color.is_none() && payload.is_primary.is_none() && payload.quantity.is_none() {
+    if payload.color.is_none() && payload.is_primary.is_none() && payload.variant_id.is_none() {
         return Err(AppError::BadRequest(
-            "მინიმუმ ერთი ველი (ფერი, is_primary ან რაოდენობა) უნდა იყოს მითითებული".to_string(),
+            "მინიმუმ ერთი ველი (ფერი, is_primary ან ვარიანტი) უნდა იყოს მითითებული".to_string(),
         ));
     }
 
+    if let Some(variant_id) = payload.variant_id {
+        super::variants::ensure_variant(&state, &product_id, variant_id).await?;
+    }
+
     let updated_image = admin_queries::update_product_image_metadata(
         &state.db,
         &product_id,
         image_uuid,
         payload.color,
         payload.is_primary,
-        payload.quantity,
+        payload.variant_id,
     )
     .await?
     .ok_or_else(|| {
@@ -1148,19 +1010,143 @@ pub async fn get_orders(
 
 pub async fn update_order_status(
     State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
     Path(id): Path<i32>,
     Json(payload): Json<OrderStatusUpdate>,
 ) -> Result<Json<Order>> {
-    let status = payload.status.trim();
-    if status.is_empty() {
-        return Err(AppError::BadRequest("სტატუსი აუცილებელია".to_string()));
+    let comment = payload
+        .comment
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty());
+
+    let order =
+        admin_queries::update_order_status(&state.db, id, payload.status, claims.user_id, comment)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("შეკვეთა id-ით {} ვერ მოიძებნა", id)))?;
+
+    Ok(Json(order))
+}
+
+pub async fn get_order_status_history(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<Vec<OrderStatusHistoryEntry>>> {
+    if order_queries::get_order_by_id(&state.db, id)
+        .await?
+        .is_none()
+    {
+        return Err(AppError::NotFound(format!(
+            "შეკვეთა id-ით {} ვერ მოიძებნა",
+            id
+        )));
     }
 
-    let order = admin_queries::update_order_status(&state.db, id, status)
+    let history = order_queries::get_status_history(&state.db, id).await?;
+
+    Ok(Json(history))
+}
+
+pub async fn refund_order(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<i32>,
+    Json(payload): Json<RefundRequest>,
+) -> Result<Json<RefundResponse>> {
+    let order = order_queries::get_order_by_id(&state.db, id)
         .await?
         .ok_or_else(|| AppError::NotFound(format!("შეკვეთა id-ით {} ვერ მოიძებნა", id)))?;
 
-    Ok(Json(order))
+    if order.payment_id.is_none() {
+        return Err(AppError::BadRequest(
+            "შეკვეთა არ არის გადახდილი Flitt-ით".to_string(),
+        ));
+    }
+
+    let comment = payload
+        .comment
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty());
+
+    let pending = refund_queries::create_pending_refund(
+        &state.db,
+        order.id,
+        payload.amount,
+        &payload.items,
+        comment,
+        claims.user_id,
+    )
+    .await?;
+
+    let reverse_status = match flitt_service::reverse(
+        &state.flitt,
+        &order.order_id,
+        pending.refund.amount,
+        comment.unwrap_or("Refund"),
+    )
+    .await
+    {
+        Ok(ReverseOutcome::Status(status)) => status,
+        Ok(ReverseOutcome::Rejected(error_message)) => {
+            refund_queries::finish_refund(&state.db, pending.refund.id, RefundStatus::Failed)
+                .await?;
+            return Err(AppError::InternalError(format!(
+                "Flitt მოთხოვნა ვერ შესრულდა: {}",
+                error_message
+            )));
+        }
+        Err(e) => {
+            // Flitt may have reversed the payment anyway, so the reconciler
+            // settles the refund from the status API
+            tracing::error!(
+                "Flitt reverse for order {} has an unknown outcome: {e}",
+                order.order_id
+            );
+            refund_queries::finish_refund(&state.db, pending.refund.id, RefundStatus::Processing)
+                .await?;
+            return Err(AppError::InternalError(
+                "Flitt-ის პასუხი ვერ მივიღეთ, დაბრუნების შედეგი უცნობია და მოწმდება ავტომატურად"
+                    .to_string(),
+            ));
+        }
+    };
+
+    let status = match reverse_status.as_str() {
+        "approved" => RefundStatus::Approved,
+        "processing" => RefundStatus::Processing,
+        _ => RefundStatus::Declined,
+    };
+
+    tracing::info!(
+        "Flitt reverse: order_id={}, amount={}, status={}",
+        order.order_id,
+        pending.refund.amount,
+        reverse_status
+    );
+
+    let refund = refund_queries::finish_refund(&state.db, pending.refund.id, status).await?;
+
+    Ok(Json(refund))
+}
+
+pub async fn get_order_refunds(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<Vec<RefundResponse>>> {
+    let refunds = refund_queries::get_order_refunds(&state.db, id).await?;
+
+    Ok(Json(refunds))
+}
+
+pub async fn get_reconciliation_reports(
+    State(state): State<AppState>,
+    Query(params): Query<ReconciliationReportQuery>,
+) -> Result<Json<Vec<ReconciliationReport>>> {
+    let reports =
+        reconciliation_queries::get_reports(&state.db, params.limit, params.offset).await?;
+
+    Ok(Json(reports))
 }
 
 pub async fn export_orders(
@@ -1255,7 +1241,7 @@ pub async fn export_orders(
         let cells: [String; 18] = [
             order.id.to_string(),
             order.order_id.clone(),
-            order.status.clone(),
+            order.status.as_str().to_string(),
             (Decimal::from(order.amount) / Decimal::from(100)).to_string(),
             order.currency.clone(),
             customer,
@@ -1304,81 +1290,81 @@ pub async fn export_orders(
 pub async fn create_order(
     State(state): State<AppState>,
     Extension(claims): Extension<Claims>,
-    Json(payload): Json<AdminOrderRequest>,
-) -> Result<Json<OrderResponse>> {
+    IdempotencyKey(key): IdempotencyKey,
+    FingerprintedJson(payload, fingerprint): FingerprintedJson<AdminOrderRequest>,
+) -> Result<axum::response::Response> {
+    let user_id = claims.user_id;
+
+    idempotency::run_once(
+        &state.db,
+        IdempotencyScope::AdminOrder,
+        key,
+        Some(user_id),
+        &fingerprint,
+        |committed| insert_admin_order(&state, claims, payload, committed),
+    )
+    .await
+}
+
+async fn insert_admin_order(
+    state: &AppState,
+    claims: Claims,
+    payload: AdminOrderRequest,
+    committed: Committed,
+) -> Result<OrderResponse> {
     let product_ids: Vec<String> = payload
         .items
         .iter()
         .filter_map(|i| i.product_id.clone())
+        .filter(|id| !id.is_empty())
         .collect();
-
-    let products = if product_ids.is_empty() {
-        std::collections::HashMap::new()
-    } else {
-        products_queries::find_by_ids(&state.db, &product_ids).await?
-    };
-
-    let images = if product_ids.is_empty() {
-        std::collections::HashMap::new()
-    } else {
-        products_queries::find_images_by_product_ids(&state.db, &product_ids).await?
-    };
+    let catalog = pricing_service::Catalog::load(&state.db, &product_ids).await?;
 
     let mut order_items = Vec::with_capacity(payload.items.len());
     let mut subtotal = Decimal::ZERO;
 
     for item in &payload.items {
-        let product = item
-            .product_id
-            .as_ref()
-            .and_then(|id| products.get(id.as_str()));
-
         let quantity = item.quantity.unwrap_or(1);
 
-        let price = item
-            .price
-            .or_else(|| {
-                product.map(|p| match p.discounted_price {
-                    Some(dp) if dp < p.price => dp,
-                    _ => p.price,
-                })
-            })
-            .unwrap_or(Decimal::ZERO);
-
-        let product_name = item
-            .product_name
-            .clone()
-            .or_else(|| product.map(|p| p.name.clone()))
-            .unwrap_or_default();
-
-        let image = item
-            .product_id
-            .as_ref()
-            .and_then(|id| images.get(id.as_str()))
-            .and_then(|imgs| match &item.color {
-                Some(color) => imgs
-                    .iter()
-                    .find(|img| img.color.as_deref() == Some(color.as_str())),
-                None => imgs.iter().find(|img| img.is_primary).or(imgs.first()),
-            })
-            .map(serde_json::to_value)
-            .transpose()?
-            .unwrap_or(serde_json::Value::Null);
-
-        subtotal += price * Decimal::from(quantity);
-
-        order_items.push(OrderItemData {
-            product_id: item.product_id.clone().filter(|id| !id.is_empty()),
-            color: item.color.clone(),
-            quantity,
-            price,
-            product_name,
-            image,
-            cable_config: item
-                .cable_config
-                .as_ref()
-                .map(|c| serde_json::json!({ "watts": c.watts, "length_cm": c.length_cm })),
-        });
+        let line = match item.product_id.as_ref().filter(|id| !id.is_empty()) {
+            Some(product_id) => {
+                let mut line = catalog.quote_line(&CartItem {
+                    product_id: product_id.clone(),
+                    variant_id: item.variant_id,
+                    color: item.color.clone(),
+                    quantity,
+                    cable_config: item.cable_config.clone(),
+                })?;
+                if let Some(price) = item.price {
+                    line.price = price;
+                    line.campaign_id = None;
+                    line.campaign_discount = None;
+                }
+                if let Some(name) = &item.product_name {
+                    line.product_name = name.clone();
+                }
+                line
+            }
+            None => OrderItemData {
+                product_id: None,
+                variant_id: None,
+                color: item.color.clone(),
+                quantity,
+                price: item.price.unwrap_or(Decimal::ZERO),
+                product_name: item.product_name.clone().unwrap_or_default(),
+                image: serde_json::Value::Null,
+                cable_config: item
+                    .cable_config
+                    .as_ref()
+                    .map(|c| serde_json::json!({ "watts": c.watts, "length_cm": c.length_cm })),
+                campaign_id: None,
+                campaign_discount: None,
+                bundle_items: None,
+            },
+        };
+
+        subtotal += line.price * Decimal::from(quantity);
+        order_items.push(line);
     }
 
     let amount_tetri = match payload.amount {
@@ -1391,7 +1377,7 @@ pub async fn create_order(
         .ok_or_else(|| AppError::InternalError("თანხის გამოთვლა ვერ მოხერხდა".to_string()))?;
 
     let order_id = format!("tene_{}", Uuid::new_v4());
-    let status = payload.status.as_deref().unwrap_or("created");
+    let status = payload.status.unwrap_or(OrderStatus::Created);
 
     let order = order_queries::create_admin_order(
         &state.db,
@@ -1403,6 +1389,7 @@ pub async fn create_order(
         &order_items,
     )
     .await?;
+    committed.set();
 
     if !payload.comment_image_uuids.is_empty() {
         order_queries::attach_comment_images(&state.db, order.id, &payload.comment_image_uuids)
@@ -1412,9 +1399,9 @@ pub async fn create_order(
     let items = order_queries::get_items_for_orders(&state.db, &[order.id]).await?;
     let comment_image_rows =
         order_queries::get_comment_images_for_orders(&state.db, &[order.id]).await?;
-    let comment_images = super::orders::build_comment_images(&state, comment_image_rows);
+    let comment_images = super::orders::build_comment_images(state, comment_image_rows);
 
-    Ok(Json(OrderResponse {
+    Ok(OrderResponse {
         order,
         items,
         comment_images,
@@ -1423,7 +1410,7 @@ pub async fn create_order(
             name: claims.name,
             email: claims.email,
         }),
-    }))
+    })
 }
 
 pub async fn create_payment_link(
@@ -1470,14 +1457,16 @@ pub async fn create_payment_link(
         delivery_time: "",
         comment: payload.comment.as_deref(),
         payment_method: Some(PaymentMethod::Card.as_str()),
+        coins_redeemed: 0,
+        coupon: None,
     };
 
-    order_queries::create_order_with_items_raw(
+    order_queries::create_order_with_items(
         &state.db,
         None,
         &order_id,
         amount_tetri,
-        "pending",
+        OrderStatus::Pending,
         &contact,
         &[],
     )
@@ -1487,8 +1476,7 @@ pub async fn create_payment_link(
     let response_url = format!("{}/payments/redirect", state.backend_url);
 
     let checkout_url = flitt_service::create_checkout_url(
-        state.flitt_merchant_id,
-        &state.flitt_secret_key,
+        &state.flitt,
         &order_id,
         amount_tetri,
         &format!("Tene order {}", order_id),
@@ -0,0 +1,58 @@
+use std::collections::HashSet;
+
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+
+use crate::{
+    AppState,
+    error::{AppError, Result},
+    models::{BundleComponent, SetBundleRequest},
+    queries::{bundle_queries, products_queries},
+};
+
+async fn ensure_product(state: &AppState, id: &str) -> Result<()> {
+    match products_queries::find_by_id(&state.db, id).await? {
+        Some(_) => Ok(()),
+        None => Err(AppError::NotFound(format!(
+            "პროდუქტი id-ით {} ვერ მოიძებნა",
+            id
+        ))),
+    }
+}
+
+pub async fn get_bundle(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<BundleComponent>>> {
+    ensure_product(&state, &id).await?;
+    let components = bundle_queries::find_by_bundle_id(&state.db, &id).await?;
+
+    Ok(Json(components))
+}
+
+pub async fn set_bundle(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(payload): Json<SetBundleRequest>,
+) -> Result<Json<Vec<BundleComponent>>> {
+    ensure_product(&state, &id).await?;
+
+    if payload.components.iter().any(|c| c.quantity <= 0) {
+        return Err(AppError::BadRequest(
+            "რაოდენობა უნდა იყოს დადებითი".to_string(),
+        ));
+    }
+    let mut seen = HashSet::new();
+    if !payload.components.iter().all(|c| seen.insert(c.variant_id)) {
+        return Err(AppError::BadRequest(
+            "ვარიანტი ნაკრებში მხოლოდ ერთხელ მიეთითება".to_string(),
+        ));
+    }
+
+    bundle_queries::set_components(&state.db, &id, &payload.components).await?;
+    let components = bundle_queries::find_by_bundle_id(&state.db, &id).await?;
+
+    Ok(Json(components))
+}
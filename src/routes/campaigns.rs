@@ -0,0 +1,97 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+};
+use http::StatusCode;
+use rust_decimal::{Decimal, dec};
+
+use crate::{
+    AppState,
+    error::{AppError, Result},
+    models::{Campaign, CampaignQuery, CampaignRequest, CampaignSearchResponse},
+    queries::campaign_queries,
+};
+
+fn validate_campaign(req: &CampaignRequest) -> Result<()> {
+    if req.name.trim().is_empty() {
+        return Err(AppError::BadRequest("სახელი აუცილებელია".to_string()));
+    }
+    if req.discount_percent <= Decimal::ZERO || req.discount_percent >= dec!(100) {
+        return Err(AppError::BadRequest(
+            "პროცენტი უნდა იყოს 0-დან 100-მდე".to_string(),
+        ));
+    }
+    if req.starts_at >= req.ends_at {
+        return Err(AppError::BadRequest(
+            "დაწყების თარიღი უნდა იყოს დასრულებამდე".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+pub async fn search_campaigns(
+    State(state): State<AppState>,
+    Query(params): Query<CampaignQuery>,
+) -> Result<Json<CampaignSearchResponse>> {
+    let (campaigns, total, limit, offset) =
+        campaign_queries::search_campaigns(&state.db, &params).await?;
+
+    Ok(Json(CampaignSearchResponse {
+        campaigns,
+        total,
+        limit,
+        offset,
+    }))
+}
+
+pub async fn get_campaign(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<Campaign>> {
+    let campaign = campaign_queries::find_by_id(&state.db, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("კამპანია id-ით {} ვერ მოიძებნა", id)))?;
+    Ok(Json(campaign))
+}
+
+pub async fn create_campaign(
+    State(state): State<AppState>,
+    Json(payload): Json<CampaignRequest>,
+) -> Result<Json<Campaign>> {
+    validate_campaign(&payload)?;
+
+    let campaign = campaign_queries::create_campaign(&state.db, &payload).await?;
+    Ok(Json(campaign))
+}
+
+pub async fn update_campaign(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Json(payload): Json<CampaignRequest>,
+) -> Result<Json<Campaign>> {
+    if campaign_queries::find_by_id(&state.db, id).await?.is_none() {
+        return Err(AppError::NotFound(format!(
+            "კამპანია id-ით {} ვერ მოიძებნა",
+            id
+        )));
+    }
+
+    validate_campaign(&payload)?;
+
+    let campaign = campaign_queries::update_campaign(&state.db, id, &payload).await?;
+    Ok(Json(campaign))
+}
+
+pub async fn delete_campaign(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<StatusCode> {
+    if campaign_queries::delete_campaign(&state.db, id).await? == 0 {
+        return Err(AppError::NotFound(format!(
+            "კამპანია id-ით {} ვერ მოიძებნა",
+            id
+        )));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,203 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    error::{AppError, Result},
+    models::{
+        CartItem, CartLine, CartResponse, MergeCartRequest, RestoredCartResponse,
+        UpdateCartItemRequest,
+    },
+    queries::cart_queries::{self, MAX_LINE_QUANTITY},
+    services::pricing_service::Catalog,
+    utils::{extractors::extract_user_id, jwt::Claims},
+};
+
+fn validate_quantity(quantity: i32) -> Result<()> {
+    if quantity <= 0 || quantity > MAX_LINE_QUANTITY {
+        return Err(AppError::BadRequest(format!(
+            "რაოდენობა უნდა იყოს 1-დან {}-მდე",
+            MAX_LINE_QUANTITY
+        )));
+    }
+    Ok(())
+}
+
+/// Prices the stored cart with the same rules checkout uses, so the totals
+/// shown here match what the user will be charged.
+async fn cart_response(state: &AppState, user_id: i32) -> Result<CartResponse> {
+    let rows = cart_queries::get_items(&state.db, user_id).await?;
+    let items: Vec<CartItem> = rows.iter().map(|row| row.to_cart_item()).collect();
+
+    let product_ids: Vec<String> = items.iter().map(|i| i.product_id.clone()).collect();
+    let catalog = Catalog::load(&state.db, &product_ids).await?;
+    let demand = catalog.demand_by_variant(&items);
+
+    let mut subtotal = Decimal::ZERO;
+    let mut lines = Vec::with_capacity(rows.len());
+    for (row, item) in rows.iter().zip(&items) {
+        let mut line = CartLine {
+            id: row.id,
+            product_id: row.product_id.clone(),
+            variant_id: row.variant_id,
+            color: row.color.clone(),
+            quantity: row.quantity,
+            cable_config: row.cable_config(),
+            product_name: None,
+            image: None,
+            price: None,
+            line_total: None,
+            error: None,
+        };
+
+        match catalog.price_line(item, &demand) {
+            Ok(priced) => {
+                let line_total = priced.price * Decimal::from(priced.quantity);
+                subtotal += line_total;
+                line.product_name = Some(priced.product_name);
+                line.image = Some(priced.image);
+                line.price = Some(priced.price);
+                line.line_total = Some(line_total);
+            }
+            Err(AppError::BadRequest(msg) | AppError::NotFound(msg)) => line.error = Some(msg),
+            Err(e) => return Err(e),
+        }
+        lines.push(line);
+    }
+
+    Ok(CartResponse {
+        items: lines,
+        subtotal,
+    })
+}
+
+pub async fn get_cart(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<CartResponse>> {
+    let user_id = extract_user_id(&claims)?;
+
+    Ok(Json(cart_response(&state, user_id).await?))
+}
+
+pub async fn add_cart_item(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<CartItem>,
+) -> Result<Json<CartResponse>> {
+    let user_id = extract_user_id(&claims)?;
+    validate_quantity(payload.quantity)?;
+
+    let catalog = Catalog::load(&state.db, std::slice::from_ref(&payload.product_id)).await?;
+    let demand = catalog.demand_by_variant(std::slice::from_ref(&payload));
+    catalog.price_line(&payload, &demand)?;
+
+    cart_queries::add_item(&state.db, user_id, &payload).await?;
+
+    Ok(Json(cart_response(&state, user_id).await?))
+}
+
+/// Setting the quantity to 0 removes the line.
+pub async fn update_cart_item(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<i32>,
+    Json(payload): Json<UpdateCartItemRequest>,
+) -> Result<Json<CartResponse>> {
+    let user_id = extract_user_id(&claims)?;
+
+    let affected = if payload.quantity == 0 {
+        cart_queries::remove_item(&state.db, user_id, id).await?
+    } else {
+        validate_quantity(payload.quantity)?;
+        cart_queries::set_quantity(&state.db, user_id, id, payload.quantity).await?
+    };
+
+    if affected == 0 {
+        return Err(AppError::NotFound(format!(
+            "კალათის პროდუქტი id-ით {} ვერ მოიძებნა",
+            id
+        )));
+    }
+
+    Ok(Json(cart_response(&state, user_id).await?))
+}
+
+pub async fn remove_cart_item(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<i32>,
+) -> Result<Json<CartResponse>> {
+    let user_id = extract_user_id(&claims)?;
+
+    if cart_queries::remove_item(&state.db, user_id, id).await? == 0 {
+        return Err(AppError::NotFound(format!(
+            "კალათის პროდუქტი id-ით {} ვერ მოიძებნა",
+            id
+        )));
+    }
+
+    Ok(Json(cart_response(&state, user_id).await?))
+}
+
+pub async fn clear_cart(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<StatusCode> {
+    let user_id = extract_user_id(&claims)?;
+
+    cart_queries::clear(&state.db, user_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Called after login with the guest cart kept on the client. Products that
+/// no longer exist are dropped; the rest are revalidated on the next read.
+pub async fn merge_cart(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<MergeCartRequest>,
+) -> Result<Json<CartResponse>> {
+    let user_id = extract_user_id(&claims)?;
+
+    let items: Vec<CartItem> = payload
+        .items
+        .into_iter()
+        .filter(|i| i.quantity > 0)
+        .collect();
+    cart_queries::merge(&state.db, user_id, &items).await?;
+
+    Ok(Json(cart_response(&state, user_id).await?))
+}
+
+/// Returns the cart from an abandoned checkout reminder. Each link works once.
+/// The email links to the frontend's `/cart/restore/{token}` page, which calls
+/// this.
+pub async fn restore_abandoned_cart(
+    State(state): State<AppState>,
+    Path(token): Path<Uuid>,
+) -> Result<Json<RestoredCartResponse>> {
+    let cart = cart_queries::redeem_restore_token(&state.db, token)
+        .await?
+        .ok_or_else(|| AppError::NotFound("ბმული არასწორია ან ვადაგასულია".to_string()))?;
+
+    Ok(Json(cart))
+}
+
+/// Called by the frontend's `/cart/unsubscribe/{token}` page, which the
+/// reminder email links to.
+pub async fn unsubscribe_cart_reminders(
+    State(state): State<AppState>,
+    Path(token): Path<Uuid>,
+) -> Result<StatusCode> {
+    if !cart_queries::opt_out(&state.db, token).await? {
+        return Err(AppError::NotFound("ბმული არასწორია".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,137 @@
+use axum::{
+    Extension, Json,
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+
+use crate::{
+    AppState,
+    error::{AppError, Result},
+    models::{
+        CatalogExportQuery, CatalogFileFormat, CatalogImportQuery, CatalogJob, CatalogJobKind,
+        CatalogJobQuery, CatalogJobSearchResponse, CatalogJobStatus,
+    },
+    queries::catalog_queries,
+    services::catalog_service,
+    utils::jwt::Claims,
+};
+
+async fn find_job(state: &AppState, id: i32) -> Result<CatalogJob> {
+    catalog_queries::find_job(&state.db, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("დავალება id-ით {} ვერ მოიძებნა", id)))
+}
+
+/// Queues the uploaded file (sent as the raw request body) for import.
+pub async fn import_products(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(params): Query<CatalogImportQuery>,
+    body: Bytes,
+) -> Result<(StatusCode, Json<CatalogJob>)> {
+    if body.is_empty() {
+        return Err(AppError::BadRequest("ფაილი ცარიელია".to_string()));
+    }
+
+    let format = params
+        .format
+        .unwrap_or_else(|| catalog_service::detect_format(&body));
+    let file_name = params
+        .file_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|n| !n.is_empty());
+
+    let job = catalog_queries::create_import_job(
+        &state.db,
+        format,
+        params.dry_run.unwrap_or(true),
+        file_name,
+        &body,
+        claims.user_id,
+    )
+    .await?;
+
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+pub async fn export_products(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(params): Query<CatalogExportQuery>,
+) -> Result<(StatusCode, Json<CatalogJob>)> {
+    let format = params.format.unwrap_or(CatalogFileFormat::Xlsx);
+    let job = catalog_queries::create_export_job(&state.db, format, claims.user_id).await?;
+
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+pub async fn search_catalog_jobs(
+    State(state): State<AppState>,
+    Query(params): Query<CatalogJobQuery>,
+) -> Result<Json<CatalogJobSearchResponse>> {
+    let (jobs, total, limit, offset) = catalog_queries::search_jobs(&state.db, &params).await?;
+
+    Ok(Json(CatalogJobSearchResponse {
+        jobs,
+        total,
+        limit,
+        offset,
+    }))
+}
+
+pub async fn get_catalog_job(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<CatalogJob>> {
+    Ok(Json(find_job(&state, id).await?))
+}
+
+/// Imports a dry run's file for real once its report came back clean.
+pub async fn apply_catalog_job(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<(StatusCode, Json<CatalogJob>)> {
+    find_job(&state, id).await?;
+
+    let job = catalog_queries::apply_dry_run(&state.db, id)
+        .await?
+        .ok_or_else(|| {
+            AppError::BadRequest(
+                "გაშვება შესაძლებელია მხოლოდ უშეცდომოდ დასრულებული შემოწმებისთვის".to_string(),
+            )
+        })?;
+
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+pub async fn download_catalog_job(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<axum::response::Response> {
+    let job = find_job(&state, id).await?;
+    if job.kind != CatalogJobKind::Export || job.status != CatalogJobStatus::Completed {
+        return Err(AppError::BadRequest("ფაილი ჯერ არ არის მზად".to_string()));
+    }
+
+    let output = catalog_queries::find_output(&state.db, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("ფაილი ვერ მოიძებნა".to_string()))?;
+
+    let disposition = format!(
+        "attachment; filename=\"products-{}.{}\"",
+        job.id,
+        job.format.extension()
+    );
+    let headers = [
+        (
+            http::header::CONTENT_TYPE,
+            job.format.content_type().to_string(),
+        ),
+        (http::header::CONTENT_DISPOSITION, disposition),
+    ];
+
+    Ok((headers, output).into_response())
+}
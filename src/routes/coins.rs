@@ -0,0 +1,74 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+};
+
+use crate::{
+    AppState,
+    error::{AppError, Result},
+    models::{CoinAdjustmentRequest, CoinBalanceResponse, CoinLedgerEntry, CoinLedgerQuery},
+    queries::{coin_queries, user_queries},
+    utils::{extractors::extract_user_id, jwt::Claims},
+};
+
+async fn balance_response(
+    state: &AppState,
+    user_id: i32,
+    query: CoinLedgerQuery,
+) -> Result<CoinBalanceResponse> {
+    let balance = coin_queries::get_balance(&state.db, user_id).await?;
+    let entries = coin_queries::get_ledger(&state.db, user_id, query.limit, query.offset).await?;
+
+    Ok(CoinBalanceResponse { balance, entries })
+}
+
+pub async fn get_coin_balance(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<CoinLedgerQuery>,
+) -> Result<Json<CoinBalanceResponse>> {
+    let user_id = extract_user_id(&claims)?;
+
+    Ok(Json(balance_response(&state, user_id, query).await?))
+}
+
+pub async fn get_user_coins(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Query(query): Query<CoinLedgerQuery>,
+) -> Result<Json<CoinBalanceResponse>> {
+    if user_queries::find_by_id(&state.db, id).await?.is_none() {
+        return Err(AppError::NotFound(format!(
+            "მომხმარებელი id-ით {} ვერ მოიძებნა",
+            id
+        )));
+    }
+
+    Ok(Json(balance_response(&state, id, query).await?))
+}
+
+pub async fn adjust_user_coins(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<i32>,
+    Json(payload): Json<CoinAdjustmentRequest>,
+) -> Result<Json<CoinLedgerEntry>> {
+    let admin_id = extract_user_id(&claims)?;
+
+    if payload.amount == 0 {
+        return Err(AppError::BadRequest(
+            "ქოინების რაოდენობა არ შეიძლება იყოს 0".to_string(),
+        ));
+    }
+
+    let comment = payload
+        .comment
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty());
+
+    let entry =
+        coin_queries::add_adjustment(&state.db, id, payload.amount, admin_id, comment).await?;
+
+    Ok(Json(entry))
+}
@@ -0,0 +1,136 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+};
+use http::StatusCode;
+use rust_decimal::{Decimal, dec};
+
+use crate::{
+    AppState,
+    error::{AppError, Result},
+    models::{Coupon, CouponQuery, CouponRequest, CouponSearchResponse, CouponType},
+    queries::coupon_queries,
+};
+
+fn validate_coupon(req: &CouponRequest) -> Result<()> {
+    let code = req.code.trim();
+    if code.is_empty() || code.len() > 64 || code.chars().any(char::is_whitespace) {
+        return Err(AppError::BadRequest(
+            "პრომო კოდი უნდა იყოს 1-64 სიმბოლო, გამოტოვების გარეშე".to_string(),
+        ));
+    }
+
+    match req.discount_type {
+        CouponType::Percentage if req.value <= Decimal::ZERO || req.value > dec!(100) => {
+            return Err(AppError::BadRequest(
+                "პროცენტი უნდა იყოს 0-დან 100-მდე".to_string(),
+            ));
+        }
+        CouponType::FixedAmount if req.value <= Decimal::ZERO => {
+            return Err(AppError::BadRequest(
+                "ფასდაკლების თანხა უნდა იყოს დადებითი".to_string(),
+            ));
+        }
+        _ => {}
+    }
+
+    if req.min_subtotal.is_some_and(|m| m < Decimal::ZERO) {
+        return Err(AppError::BadRequest(
+            "მინიმალური თანხა არ შეიძლება იყოს უარყოფითი".to_string(),
+        ));
+    }
+    if req.usage_limit.is_some_and(|l| l <= 0) || req.per_user_limit.is_some_and(|l| l <= 0) {
+        return Err(AppError::BadRequest(
+            "გამოყენების ლიმიტი უნდა იყოს დადებითი".to_string(),
+        ));
+    }
+    if matches!((req.starts_at, req.ends_at), (Some(starts), Some(ends)) if starts >= ends) {
+        return Err(AppError::BadRequest(
+            "დაწყების თარიღი უნდა იყოს დასრულებამდე".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+async fn ensure_code_available(
+    state: &AppState,
+    code: &str,
+    exclude_id: Option<i32>,
+) -> Result<()> {
+    match coupon_queries::find_by_code(&state.db, code.trim()).await? {
+        Some(existing) if Some(existing.id) != exclude_id => Err(AppError::Conflict(format!(
+            "პრომო კოდი '{}' უკვე არსებობს",
+            existing.code
+        ))),
+        _ => Ok(()),
+    }
+}
+
+pub async fn search_coupons(
+    State(state): State<AppState>,
+    Query(params): Query<CouponQuery>,
+) -> Result<Json<CouponSearchResponse>> {
+    let (coupons, total, limit, offset) =
+        coupon_queries::search_coupons(&state.db, &params).await?;
+
+    Ok(Json(CouponSearchResponse {
+        coupons,
+        total,
+        limit,
+        offset,
+    }))
+}
+
+pub async fn get_coupon(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<Coupon>> {
+    let coupon = coupon_queries::find_by_id(&state.db, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("პრომო კოდი id-ით {} ვერ მოიძებნა", id)))?;
+    Ok(Json(coupon))
+}
+
+pub async fn create_coupon(
+    State(state): State<AppState>,
+    Json(payload): Json<CouponRequest>,
+) -> Result<Json<Coupon>> {
+    validate_coupon(&payload)?;
+    ensure_code_available(&state, &payload.code, None).await?;
+
+    let coupon = coupon_queries::create_coupon(&state.db, &payload).await?;
+    Ok(Json(coupon))
+}
+
+pub async fn update_coupon(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Json(payload): Json<CouponRequest>,
+) -> Result<Json<Coupon>> {
+    if coupon_queries::find_by_id(&state.db, id).await?.is_none() {
+        return Err(AppError::NotFound(format!(
+            "პრომო კოდი id-ით {} ვერ მოიძებნა",
+            id
+        )));
+    }
+
+    validate_coupon(&payload)?;
+    ensure_code_available(&state, &payload.code, Some(id)).await?;
+
+    let coupon = coupon_queries::update_coupon(&state.db, id, &payload).await?;
+    Ok(Json(coupon))
+}
+
+pub async fn delete_coupon(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<StatusCode> {
+    if coupon_queries::delete_coupon(&state.db, id).await? == 0 {
+        return Err(AppError::NotFound(format!(
+            "პრომო კოდი id-ით {} ვერ მოიძებნა",
+            id
+        )));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
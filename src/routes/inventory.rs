@@ -0,0 +1,321 @@
+use std::collections::{HashMap, HashSet};
+
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+
+use crate::{
+    AppState,
+    error::{AppError, Result},
+    models::{
+        CreateWarehouseRequest, LowStockQuery, LowStockReport, ProductVariant, StockMovement,
+        StockMovementKind, StockMovementQuery, StockMovementSearchResponse, StockReceiptRequest,
+        StockTakeRequest, StockTransferRequest, UpdateWarehouseRequest, Warehouse,
+        WarehouseStockLine,
+    },
+    queries::{
+        inventory_queries::{self, NewStockMovement},
+        variant_queries,
+    },
+    services::product_alert_service,
+    utils::jwt::Claims,
+};
+
+fn trimmed(value: Option<&str>) -> Option<&str> {
+    value.map(str::trim).filter(|v| !v.is_empty())
+}
+
+async fn find_warehouse(state: &AppState, id: i32) -> Result<Warehouse> {
+    inventory_queries::find_warehouse(&state.db, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("საწყობი id-ით {} ვერ მოიძებნა", id)))
+}
+
+/// Variants of every product a stock change touches, taken before the change
+/// so back-in-stock alerts can be queued afterwards.
+async fn variants_before(
+    state: &AppState,
+    variant_ids: &[i32],
+) -> Result<HashMap<String, Vec<ProductVariant>>> {
+    let product_ids = variant_queries::find_product_ids(&state.db, variant_ids).await?;
+    variant_queries::find_by_product_ids(&state.db, &product_ids).await
+}
+
+async fn restock_alerts(state: &AppState, before: HashMap<String, Vec<ProductVariant>>) {
+    for (product_id, variants) in &before {
+        if let Err(e) =
+            product_alert_service::queue_restock_alerts(&state.db, product_id, variants).await
+        {
+            tracing::error!("failed to queue back-in-stock alerts for {product_id}: {e}");
+        }
+    }
+}
+
+pub async fn get_warehouses(State(state): State<AppState>) -> Result<Json<Vec<Warehouse>>> {
+    Ok(Json(inventory_queries::get_warehouses(&state.db).await?))
+}
+
+pub async fn create_warehouse(
+    State(state): State<AppState>,
+    Json(mut payload): Json<CreateWarehouseRequest>,
+) -> Result<(StatusCode, Json<Warehouse>)> {
+    payload.code = payload.code.trim().to_lowercase();
+    payload.name = payload.name.trim().to_string();
+    if payload.code.is_empty() || payload.name.is_empty() {
+        return Err(AppError::BadRequest(
+            "კოდი და სახელი აუცილებელია".to_string(),
+        ));
+    }
+
+    let warehouse = inventory_queries::create_warehouse(&state.db, &payload).await?;
+
+    Ok((StatusCode::CREATED, Json(warehouse)))
+}
+
+pub async fn update_warehouse(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Json(payload): Json<UpdateWarehouseRequest>,
+) -> Result<Json<Warehouse>> {
+    if payload.name.as_deref().is_some_and(|n| n.trim().is_empty()) {
+        return Err(AppError::BadRequest("სახელი აუცილებელია".to_string()));
+    }
+
+    let warehouse = inventory_queries::update_warehouse(&state.db, id, &payload)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("საწყობი id-ით {} ვერ მოიძებნა", id)))?;
+
+    Ok(Json(warehouse))
+}
+
+pub async fn get_warehouse_stock(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<Vec<WarehouseStockLine>>> {
+    find_warehouse(&state, id).await?;
+
+    Ok(Json(
+        inventory_queries::get_warehouse_stock(&state.db, id).await?,
+    ))
+}
+
+pub async fn receive_stock(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<StockReceiptRequest>,
+) -> Result<(StatusCode, Json<StockMovement>)> {
+    if payload.quantity <= 0 {
+        return Err(AppError::BadRequest(
+            "რაოდენობა უნდა იყოს დადებითი".to_string(),
+        ));
+    }
+    find_warehouse(&state, payload.warehouse_id).await?;
+
+    let before = variants_before(&state, &[payload.variant_id]).await?;
+    let movement = inventory_queries::receive_stock(
+        &state.db,
+        &NewStockMovement {
+            variant_id: payload.variant_id,
+            warehouse_id: payload.warehouse_id,
+            quantity: payload.quantity,
+            kind: StockMovementKind::Receipt,
+            reason: trimmed(payload.reason.as_deref()),
+            order_id: None,
+            transfer_id: None,
+            created_by_user_id: Some(claims.user_id),
+        },
+    )
+    .await?
+    .ok_or_else(|| AppError::InternalError("მარაგის მიღება ვერ მოხერხდა".to_string()))?;
+    restock_alerts(&state, before).await;
+
+    Ok((StatusCode::CREATED, Json(movement)))
+}
+
+pub async fn transfer_stock(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<StockTransferRequest>,
+) -> Result<(StatusCode, Json<Vec<StockMovement>>)> {
+    if payload.quantity <= 0 {
+        return Err(AppError::BadRequest(
+            "რაოდენობა უნდა იყოს დადებითი".to_string(),
+        ));
+    }
+    if payload.from_warehouse_id == payload.to_warehouse_id {
+        return Err(AppError::BadRequest(
+            "საწყობები ერთმანეთისგან უნდა განსხვავდებოდეს".to_string(),
+        ));
+    }
+    find_warehouse(&state, payload.from_warehouse_id).await?;
+    find_warehouse(&state, payload.to_warehouse_id).await?;
+
+    let movements = inventory_queries::transfer_stock(
+        &state.db,
+        payload.variant_id,
+        payload.from_warehouse_id,
+        payload.to_warehouse_id,
+        payload.quantity,
+        trimmed(payload.reason.as_deref()),
+        claims.user_id,
+    )
+    .await?
+    .ok_or_else(|| AppError::BadRequest("საწყობში არასაკმარისი მარაგია".to_string()))?;
+
+    Ok((StatusCode::CREATED, Json(movements)))
+}
+
+/// Returns the adjustments the count produced; an empty list means the
+/// recorded stock already matched.
+pub async fn stock_take(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<i32>,
+    Json(payload): Json<StockTakeRequest>,
+) -> Result<Json<Vec<StockMovement>>> {
+    if payload.counts.is_empty() {
+        return Err(AppError::BadRequest(
+            "მინიმუმ ერთი დათვლა აუცილებელია".to_string(),
+        ));
+    }
+    let mut seen = HashSet::new();
+    for count in &payload.counts {
+        if count.quantity < 0 {
+            return Err(AppError::BadRequest(
+                "რაოდენობა არ შეიძლება იყოს უარყოფითი".to_string(),
+            ));
+        }
+        if !seen.insert(count.variant_id) {
+            return Err(AppError::BadRequest(format!(
+                "ვარიანტი {} რამდენჯერმეა მითითებული",
+                count.variant_id
+            )));
+        }
+    }
+    find_warehouse(&state, id).await?;
+
+    let variant_ids: Vec<i32> = seen.into_iter().collect();
+    let before = variants_before(&state, &variant_ids).await?;
+    let adjustments = inventory_queries::apply_stock_take(
+        &state.db,
+        id,
+        &payload.counts,
+        trimmed(payload.reason.as_deref()),
+        claims.user_id,
+    )
+    .await?;
+    restock_alerts(&state, before).await;
+
+    Ok(Json(adjustments))
+}
+
+pub async fn search_stock_movements(
+    State(state): State<AppState>,
+    Query(params): Query<StockMovementQuery>,
+) -> Result<Json<StockMovementSearchResponse>> {
+    let (movements, total, limit, offset) =
+        inventory_queries::search_movements(&state.db, &params).await?;
+
+    Ok(Json(StockMovementSearchResponse {
+        movements,
+        total,
+        limit,
+        offset,
+    }))
+}
+
+const MAX_VELOCITY_DAYS: i32 = 365;
+
+async fn low_stock_report(state: &AppState, params: &LowStockQuery) -> Result<LowStockReport> {
+    let days = params
+        .days
+        .unwrap_or(state.low_stock.velocity_days)
+        .clamp(1, MAX_VELOCITY_DAYS);
+    let variants =
+        inventory_queries::get_low_stock(&state.db, state.low_stock.default_threshold, days)
+            .await?;
+
+    Ok(LowStockReport { days, variants })
+}
+
+pub async fn get_low_stock(
+    State(state): State<AppState>,
+    Query(params): Query<LowStockQuery>,
+) -> Result<Json<LowStockReport>> {
+    Ok(Json(low_stock_report(&state, &params).await?))
+}
+
+pub async fn export_low_stock(
+    State(state): State<AppState>,
+    Query(params): Query<LowStockQuery>,
+) -> Result<axum::response::Response> {
+    use axum::response::IntoResponse;
+    use rust_xlsxwriter::{Format, Workbook};
+
+    let report = low_stock_report(&state, &params).await?;
+
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+
+    let header = Format::new().set_bold();
+    let headers = [
+        "SKU".to_string(),
+        "ID".to_string(),
+        "დასახელება".to_string(),
+        "ფერი".to_string(),
+        "მარაგი".to_string(),
+        "ზღვარი".to_string(),
+        format!("გაყიდული ({} დღე)", report.days),
+        "დღიური გაყიდვა".to_string(),
+        "საკმარისია (დღე)".to_string(),
+        "შესაკვეთი".to_string(),
+    ];
+    for (col, title) in headers.iter().enumerate() {
+        sheet
+            .write_string_with_format(0, col as u16, title, &header)
+            .map_err(|e| {
+                AppError::InternalError(format!("excel-ის გენერაცია ვერ მოხერხდა: {}", e))
+            })?;
+    }
+
+    for (i, p) in report.variants.iter().enumerate() {
+        let row = (i + 1) as u32;
+        let cells: [String; 10] = [
+            p.sku.clone(),
+            p.product_id.clone(),
+            p.name.clone(),
+            p.color.clone().unwrap_or_default(),
+            p.stock.to_string(),
+            p.threshold.to_string(),
+            p.units_sold.to_string(),
+            p.daily_velocity.to_string(),
+            p.days_of_cover.map(|d| d.to_string()).unwrap_or_default(),
+            p.suggested_reorder.to_string(),
+        ];
+
+        for (col, value) in cells.iter().enumerate() {
+            sheet.write_string(row, col as u16, value).map_err(|e| {
+                AppError::InternalError(format!("excel-ის გენერაცია ვერ მოხერხდა: {}", e))
+            })?;
+        }
+    }
+
+    let buffer = workbook
+        .save_to_buffer()
+        .map_err(|e| AppError::InternalError(format!("excel-ის შენახვა ვერ მოხერხდა: {}", e)))?;
+
+    let headers = [
+        (
+            http::header::CONTENT_TYPE,
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        ),
+        (
+            http::header::CONTENT_DISPOSITION,
+            "attachment; filename=\"low-stock.xlsx\"",
+        ),
+    ];
+
+    Ok((headers, buffer).into_response())
+}
@@ -1,15 +1,31 @@
 mod admin;
 mod blogs;
+mod bundles;
+mod campaigns;
+mod cart;
+mod catalog;
 mod categories;
+mod coins;
+mod coupons;
 mod google_auth;
 mod health;
+mod inventory;
 mod login;
-mod orders;
+pub(crate) mod orders;
+mod prices;
+mod product_alerts;
 mod products;
+mod questions;
+mod recently_viewed;
+mod recommendations;
 mod register;
+mod reviews;
+mod search_synonyms;
 mod send_code;
 mod tasks;
 mod user_addresses;
+mod variants;
+mod wishlist;
 
 use axum::{
     Router, middleware,
@@ -53,6 +69,15 @@ fn products_routes() -> Router<AppState> {
         .route("/products/facets", get(products::get_product_facets))
         .route("/products/{id}", get(products::get_product))
         .route("/products/{id}/views", post(products::add_product_views))
+        .route("/products/{id}/reviews", get(reviews::get_product_reviews))
+        .route(
+            "/products/{id}/alerts",
+            post(product_alerts::subscribe_product_alert),
+        )
+        .route(
+            "/product-alerts/unsubscribe/{token}",
+            post(product_alerts::unsubscribe_product_alert),
+        )
         .route("/top-products", get(products::get_top_products))
         .route(
             "/products/{id}/related",
@@ -91,6 +116,38 @@ fn user_routes() -> Router<AppState> {
             "/addresses/{address_id}",
             delete(user_addresses::delete_address),
         )
+        .route("/coins", get(coins::get_coin_balance))
+        .route("/cart", get(cart::get_cart))
+        .route("/cart", delete(cart::clear_cart))
+        .route("/cart/items", post(cart::add_cart_item))
+        .route("/cart/items/{id}", patch(cart::update_cart_item))
+        .route("/cart/items/{id}", delete(cart::remove_cart_item))
+        .route("/cart/merge", post(cart::merge_cart))
+        .route("/questions", post(questions::ask_question))
+        .route("/reviews", post(reviews::create_review))
+        .route("/reviews/images", put(reviews::generate_review_image_urls))
+        .route(
+            "/me/recently-viewed",
+            get(recently_viewed::get_recently_viewed),
+        )
+        .route(
+            "/me/recently-viewed",
+            delete(recently_viewed::clear_recently_viewed),
+        )
+        .route(
+            "/me/recently-viewed/merge",
+            post(recently_viewed::merge_recently_viewed),
+        )
+        .route(
+            "/recommendations",
+            get(recommendations::get_recommendations),
+        )
+        .route("/wishlist", get(wishlist::get_wishlist))
+        .route("/wishlist/{product_id}", put(wishlist::add_to_wishlist))
+        .route(
+            "/wishlist/{product_id}",
+            delete(wishlist::remove_from_wishlist),
+        )
         .layer(middleware::from_fn(auth_middleware))
 }
 
@@ -110,6 +167,11 @@ fn checkout_routes() -> Router<AppState> {
             post(orders::track_checkout_analytics),
         )
         .route("/orders/{id}", get(orders::get_order))
+        .route("/cart/restore/{token}", post(cart::restore_abandoned_cart))
+        .route(
+            "/cart/unsubscribe/{token}",
+            post(cart::unsubscribe_cart_reminders),
+        )
         .merge(authed)
 }
 
@@ -119,7 +181,35 @@ fn admin_routes() -> Router<AppState> {
         .route("/admin/products", get(admin::search_products))
         .route("/admin/products", post(admin::create_product))
         .route("/admin/products/{id}", put(admin::update_product))
+        .route("/admin/products/import", post(catalog::import_products))
+        .route("/admin/products/export", post(catalog::export_products))
+        .route("/admin/products/jobs", get(catalog::search_catalog_jobs))
+        .route("/admin/products/jobs/{id}", get(catalog::get_catalog_job))
+        .route(
+            "/admin/products/jobs/{id}/apply",
+            post(catalog::apply_catalog_job),
+        )
+        .route(
+            "/admin/products/jobs/{id}/download",
+            get(catalog::download_catalog_job),
+        )
         .route("/admin/products/{id}", delete(admin::delete_product))
+        .route(
+            "/admin/products/{id}/price-history",
+            get(prices::get_price_history),
+        )
+        .route(
+            "/admin/products/{id}/price-schedules",
+            post(prices::create_price_schedule),
+        )
+        .route(
+            "/admin/price-schedules",
+            get(prices::search_price_schedules),
+        )
+        .route(
+            "/admin/price-schedules/{id}/cancel",
+            post(prices::cancel_price_schedule),
+        )
         .route(
             "/admin/products/{id}/images",
             put(admin::generate_product_urls),
@@ -136,6 +226,42 @@ fn admin_routes() -> Router<AppState> {
             "/admin/products/{id}/categories",
             put(admin::assign_categories_to_product),
         )
+        .route("/admin/products/{id}/bundle", get(bundles::get_bundle))
+        .route("/admin/products/{id}/bundle", put(bundles::set_bundle))
+        .route(
+            "/admin/products/{id}/variants",
+            get(variants::get_product_variants),
+        )
+        .route(
+            "/admin/products/{id}/variants",
+            post(variants::create_product_variant),
+        )
+        .route(
+            "/admin/products/{id}/variants/{variant_id}",
+            put(variants::update_product_variant),
+        )
+        .route(
+            "/admin/products/{id}/variants/{variant_id}",
+            delete(variants::delete_product_variant),
+        )
+        // inventory
+        .route("/admin/warehouses", get(inventory::get_warehouses))
+        .route("/admin/warehouses", post(inventory::create_warehouse))
+        .route("/admin/warehouses/{id}", put(inventory::update_warehouse))
+        .route(
+            "/admin/warehouses/{id}/stock",
+            get(inventory::get_warehouse_stock),
+        )
+        .route(
+            "/admin/warehouses/{id}/stock-take",
+            post(inventory::stock_take),
+        )
+        .route("/admin/stock/receipts", post(inventory::receive_stock))
+        .route("/admin/stock/transfers", post(inventory::transfer_stock))
+        .route(
+            "/admin/stock/movements",
+            get(inventory::search_stock_movements),
+        )
         // categories
         .route("/admin/categories", get(admin::get_all_categories_admin))
         .route(
@@ -159,6 +285,23 @@ fn admin_routes() -> Router<AppState> {
         .route("/admin/brands", post(admin::create_brand))
         .route("/admin/brands/{id}", put(admin::update_brand))
         .route("/admin/brands/{id}", delete(admin::delete_brand))
+        // search synonyms
+        .route(
+            "/admin/search-synonyms",
+            get(search_synonyms::get_search_synonyms),
+        )
+        .route(
+            "/admin/search-synonyms",
+            post(search_synonyms::create_search_synonym),
+        )
+        .route(
+            "/admin/search-synonyms/{id}",
+            put(search_synonyms::update_search_synonym),
+        )
+        .route(
+            "/admin/search-synonyms/{id}",
+            delete(search_synonyms::delete_search_synonym),
+        )
         // cable types
         .route("/admin/cable-types", get(admin::get_cable_types))
         .route("/admin/cable-types", post(admin::create_cable_type))
@@ -181,6 +324,13 @@ fn admin_routes() -> Router<AppState> {
             "/admin/cable-types/{type_id}/variants/{variant_id}",
             delete(admin::delete_cable_variant),
         )
+        // reviews
+        .route("/admin/reviews", get(reviews::search_reviews))
+        .route(
+            "/admin/reviews/{id}/status",
+            patch(reviews::moderate_review),
+        )
+        .route("/admin/reviews/{id}", delete(reviews::delete_review))
         // tasks
         .route("/admin/tasks", get(tasks::search_tasks))
         .route("/admin/tasks", post(tasks::create_task))
@@ -227,6 +377,13 @@ fn admin_routes() -> Router<AppState> {
         .route("/admin/users", get(admin::search_users))
         .route("/admin/users/{id}", put(admin::update_user))
         .route("/admin/users/{id}", delete(admin::delete_user))
+        .route("/admin/users/{id}/coins", get(coins::get_user_coins))
+        .route("/admin/users/{id}/coins", post(coins::adjust_user_coins))
+        // payments
+        .route(
+            "/admin/payments/reconciliation",
+            get(admin::get_reconciliation_reports),
+        )
         .layer(middleware::from_fn(admin_middleware))
 }
 
@@ -235,13 +392,43 @@ fn operator_routes() -> Router<AppState> {
         .route("/admin/orders", get(admin::get_orders))
         .route("/admin/orders", post(admin::create_order))
         .route("/admin/orders/export", get(admin::export_orders))
+        .route("/admin/inventory/low-stock", get(inventory::get_low_stock))
+        .route(
+            "/admin/inventory/low-stock/export",
+            get(inventory::export_low_stock),
+        )
         .route(
             "/admin/orders/{id}/status",
             patch(admin::update_order_status),
         )
+        .route(
+            "/admin/orders/{id}/history",
+            get(admin::get_order_status_history),
+        )
+        .route("/admin/orders/{id}/refunds", get(admin::get_order_refunds))
+        .route("/admin/orders/{id}/refunds", post(admin::refund_order))
         .route(
             "/admin/orders/payment-link",
             post(admin::create_payment_link),
         )
+        // campaigns
+        .route("/admin/campaigns", get(campaigns::search_campaigns))
+        .route("/admin/campaigns", post(campaigns::create_campaign))
+        .route("/admin/campaigns/{id}", get(campaigns::get_campaign))
+        .route("/admin/campaigns/{id}", put(campaigns::update_campaign))
+        .route("/admin/campaigns/{id}", delete(campaigns::delete_campaign))
+        // coupons
+        .route("/admin/coupons", get(coupons::search_coupons))
+        .route("/admin/coupons", post(coupons::create_coupon))
+        .route("/admin/coupons/{id}", get(coupons::get_coupon))
+        .route("/admin/coupons/{id}", put(coupons::update_coupon))
+        .route("/admin/coupons/{id}", delete(coupons::delete_coupon))
+        // product questions
+        .route("/admin/questions", get(questions::search_questions))
+        .route(
+            "/admin/questions/{id}/answer",
+            put(questions::answer_question),
+        )
+        .route("/admin/questions/{id}", delete(questions::delete_question))
         .layer(middleware::from_fn(operator_middleware))
 }
@@ -1,26 +1,32 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 
 use axum::{
     Extension, Json,
     extract::{Path, Query, State},
     http::StatusCode,
-    response::{IntoResponse, Redirect},
+    response::{IntoResponse, Redirect, Response},
 };
 use rust_decimal::{Decimal, prelude::ToPrimitive};
-use serde_json::json;
 use uuid::Uuid;
 
 use crate::{
     AppState,
     error::{AppError, Result, SESSION_EXPIRED},
     models::{
-        CableVariant, CheckoutAnalyticsEvent, CheckoutPaymentMethod, CheckoutRequest,
-        CheckoutResponse, CommentImage, CommentImageUploadUrl, CommentImageUrlRequest,
-        CommentImageUrlResponse, OrderCommentImage, OrderItemData, OrderResponse,
+        CheckoutAnalyticsEvent, CheckoutPaymentMethod, CheckoutRequest, CheckoutResponse,
+        CommentImage, CommentImageUploadUrl, CommentImageUrlRequest, CommentImageUrlResponse,
+        CouponType, IdempotencyScope, OrderCommentImage, OrderItemData, OrderResponse, OrderStatus,
+        OrderStatusSource,
     },
-    queries::{admin_queries, order_queries, products_queries, user_queries},
-    services::{delivery_service, email_service, flitt_service, image_url_service},
-    utils::extractors::{LenientClaims, OptionalClaims, extract_user_id},
+    queries::{admin_queries, coin_queries, order_queries, reservation_queries, user_queries},
+    services::{
+        coupon_service, delivery_service, email_service, flitt_service, image_url_service,
+        pricing_service::Catalog,
+    },
+    utils::extractors::{
+        FingerprintedJson, IdempotencyKey, LenientClaims, OptionalClaims, extract_user_id,
+    },
+    utils::idempotency::{self, Committed},
     utils::jwt::Claims,
 };
 
@@ -39,6 +45,8 @@ pub async fn track_checkout_analytics(
 }
 
 const MAX_COMMENT_IMAGES: usize = 5;
+/// Coins may cover at most this share of the products subtotal.
+const MAX_COINS_REDEEM_PERCENT: i32 = 50;
 
 fn comment_image_extension(content_type: &str) -> Result<&'static str> {
     match content_type {
@@ -59,22 +67,30 @@ fn comment_images_prefix(state: &AppState) -> &'static str {
     }
 }
 
-pub async fn generate_comment_image_urls(
-    State(state): State<AppState>,
-    Json(payload): Json<CommentImageUrlRequest>,
-) -> Result<Json<CommentImageUrlResponse>> {
+/// Validates an upload request and presigns one S3 PUT per image under
+/// `env_prefix`, letting `record` store each image before its URL is handed out.
+pub(crate) async fn presign_image_uploads<F, Fut>(
+    state: &AppState,
+    payload: &CommentImageUrlRequest,
+    max_images: usize,
+    env_prefix: &str,
+    mut record: F,
+) -> Result<CommentImageUrlResponse>
+where
+    F: FnMut(Uuid, &'static str) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
     if payload.images.is_empty() {
         return Err(AppError::BadRequest(
             "სურათები არ არის მითითებული".to_string(),
         ));
     }
-    if payload.images.len() > MAX_COMMENT_IMAGES {
+    if payload.images.len() > max_images {
         return Err(AppError::BadRequest(format!(
-            "მაქსიმუმ {MAX_COMMENT_IMAGES} სურათია დაშვებული"
+            "მაქსიმუმ {max_images} სურათია დაშვებული"
         )));
     }
 
-    let env_prefix = comment_images_prefix(&state);
     let mut images = Vec::with_capacity(payload.images.len());
 
     for req in &payload.images {
@@ -99,7 +115,7 @@ pub async fn generate_comment_image_urls(
 
         let public_url = format!("{}/{}", state.assets_url, key);
 
-        order_queries::add_comment_image(&state.db, image_uuid, extension).await?;
+        record(image_uuid, extension).await?;
 
         images.push(CommentImageUploadUrl {
             image_uuid,
@@ -108,19 +124,54 @@ pub async fn generate_comment_image_urls(
         });
     }
 
-    Ok(Json(CommentImageUrlResponse { images }))
+    Ok(CommentImageUrlResponse { images })
+}
+
+pub async fn generate_comment_image_urls(
+    State(state): State<AppState>,
+    Json(payload): Json<CommentImageUrlRequest>,
+) -> Result<Json<CommentImageUrlResponse>> {
+    let db = &state.db;
+    let response = presign_image_uploads(
+        &state,
+        &payload,
+        MAX_COMMENT_IMAGES,
+        comment_images_prefix(&state),
+        |image_uuid, extension| order_queries::add_comment_image(db, image_uuid, extension),
+    )
+    .await?;
+
+    Ok(Json(response))
 }
 
 pub async fn checkout(
     State(state): State<AppState>,
     OptionalClaims(claims): OptionalClaims,
-    Json(payload): Json<CheckoutRequest>,
-) -> Result<Json<CheckoutResponse>> {
+    IdempotencyKey(key): IdempotencyKey,
+    FingerprintedJson(payload, fingerprint): FingerprintedJson<CheckoutRequest>,
+) -> Result<Response> {
     let user_id = claims.as_ref().and_then(|c| extract_user_id(c).ok());
 
+    idempotency::run_once(
+        &state.db,
+        IdempotencyScope::Checkout,
+        key,
+        user_id,
+        &fingerprint,
+        |committed| place_order(&state, user_id, payload, committed),
+    )
+    .await
+}
+
+async fn place_order(
+    state: &AppState,
+    user_id: Option<i32>,
+    payload: CheckoutRequest,
+    committed: Committed,
+) -> Result<CheckoutResponse> {
     validate_checkout_request(&payload)?;
 
-    let (order_items, subtotal) = build_order_items(&state, &payload).await?;
+    let (order_items, subtotal) = build_order_items(state, &payload).await?;
 
     let delivery = delivery_service::calculate_delivery(
         &payload.delivery_type,
@@ -135,10 +186,62 @@ pub async fn checkout(
         Decimal::ZERO
     };
 
+    let coupon = match payload
+        .coupon_code
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+    {
+        Some(code) => Some(
+            coupon_service::apply(
+                &state.db,
+                code,
+                user_id,
+                &payload.email,
+                &order_items,
+                delivery,
+            )
+            .await?,
+        ),
+        None => None,
+    };
+    let coupon_discount = coupon.as_ref().map_or(0, |c| c.discount);
+    let goods_total = match &coupon {
+        Some(c) if c.discount_type != CouponType::FreeDelivery => {
+            subtotal - Decimal::from(c.discount) / Decimal::from(100)
+        }
+        _ => subtotal,
+    };
+
+    let coins = payload.coins_to_redeem;
+    if coins > 0 {
+        let Some(user_id) = user_id else {
+            return Err(AppError::Unauthorized(
+                "ქოინების გამოსაყენებლად საჭიროა ავტორიზაცია".to_string(),
+            ));
+        };
+        let max_coins = (goods_total * Decimal::from(MAX_COINS_REDEEM_PERCENT))
+            .trunc()
+            .to_i32()
+            .unwrap_or(0);
+        if coins > max_coins {
+            return Err(AppError::BadRequest(format!(
+                "ქოინებით შესაძლებელია პროდუქტების ღირებულების მაქსიმუმ {MAX_COINS_REDEEM_PERCENT}%-ის დაფარვა"
+            )));
+        }
+        if coin_queries::get_balance(&state.db, user_id).await? < i64::from(coins) {
+            return Err(AppError::BadRequest(
+                "ქოინების ბალანსი არასაკმარისია".to_string(),
+            ));
+        }
+    }
+
     let amount_tetri = ((subtotal + delivery + cash_on_delivery_fee) * Decimal::from(100))
         .trunc()
         .to_i32()
-        .ok_or_else(|| AppError::InternalError("თანხის გამოთვლა ვერ მოხერხდა".to_string()))?;
+        .ok_or_else(|| AppError::InternalError("თანხის გამოთვლა ვერ მოხერხდა".to_string()))?
+        - coupon_discount
+        - coins;
 
     if amount_tetri <= 0 {
         return Err(AppError::BadRequest(
@@ -148,16 +251,18 @@ pub async fn checkout(
 
     let order_id = format!("tene_{}", Uuid::new_v4());
 
+    let contact = order_queries::OrderContact::from_checkout(&payload, coupon.as_ref());
     let order = order_queries::create_order_with_items(
         &state.db,
         user_id,
         &order_id,
         amount_tetri,
-        "pending",
-        &payload,
+        OrderStatus::Pending,
+        &contact,
         &order_items,
     )
     .await?;
+    committed.set();
 
     if !payload.comment_image_uuids.is_empty() {
         order_queries::attach_comment_images(&state.db, order.id, &payload.comment_image_uuids)
@@ -166,34 +271,69 @@ pub async fn checkout(
 
     if payload.payment_method == CheckoutPaymentMethod::CashOnDelivery {
         let approved = order_queries::update_order_status_and_deduct_stock(
-            &state.db, &order_id, "approved", None,
+            &state.db,
+            &order_id,
+            OrderStatus::Approved,
+            None,
+            OrderStatusSource::Checkout,
         )
         .await?;
 
         match approved {
             Some((order, true)) => {
-                send_order_emails(&state, &order).await;
+                send_order_emails(state, &order).await;
             }
             _ => {
                 tracing::warn!("cash on delivery order {} could not be approved", order_id);
+                // nothing expires a COD order, so give back its coins and
+                // coupon use now
+                order_queries::update_order_status_and_deduct_stock(
+                    &state.db,
+                    &order_id,
+                    OrderStatus::Declined,
+                    None,
+                    OrderStatusSource::Checkout,
+                )
+                .await?;
                 return Err(AppError::BadRequest(
                     "შეკვეთის დადასტურება ვერ მოხერხდა, პროდუქტი აღარ არის მარაგში".to_string(),
                 ));
             }
         }
 
-        return Ok(Json(CheckoutResponse {
+        return Ok(CheckoutResponse {
             order_id,
             checkout_url: None,
-        }));
+        });
+    }
+
+    let reserved = reservation_queries::reserve_for_order(
+        &state.db,
+        order.id,
+        &order_items,
+        state.reservation_ttl_minutes,
+    )
+    .await?;
+
+    if !reserved {
+        order_queries::update_order_status_and_deduct_stock(
+            &state.db,
+            &order_id,
+            OrderStatus::Declined,
+            None,
+            OrderStatusSource::Checkout,
+        )
+        .await?;
+        return Err(AppError::BadRequest(
+            "პროდუქტი აღარ არის მარაგში".to_string(),
+        ));
     }
 
     let server_callback_url = format!("{}/payments/callback", state.backend_url);
     let response_url = format!("{}/payments/redirect", state.backend_url);
 
     let checkout_url = flitt_service::create_checkout_url(
-        state.flitt_merchant_id,
-        &state.flitt_secret_key,
+        &state.flitt,
         &order_id,
         amount_tetri,
         &format!("Tene order {}", order_id),
@@ -204,10 +344,10 @@ pub async fn checkout(
 
     order_queries::update_order_checkout_url(&state.db, &order_id, &checkout_url).await?;
 
-    Ok(Json(CheckoutResponse {
+    Ok(CheckoutResponse {
         order_id,
         checkout_url: Some(checkout_url),
-    }))
+    })
 }
 
 async fn send_order_emails(state: &AppState, order: &crate::models::Order) {
@@ -273,6 +413,12 @@ fn validate_checkout_request(payload: &CheckoutRequest) -> Result<()> {
         )));
     }
 
+    if payload.coins_to_redeem < 0 {
+        return Err(AppError::BadRequest(
+            "ქოინების რაოდენობა არ შეიძლება იყოს უარყოფითი".to_string(),
+        ));
+    }
+
     if payload.delivery_type != "pickup" {
         if payload.address.is_empty() {
             return Err(AppError::BadRequest("მისამართი აუცილებელია".to_string()));
@@ -304,146 +450,56 @@ async fn build_order_items(
     state: &AppState,
     payload: &CheckoutRequest,
 ) -> Result<(Vec<OrderItemData>, Decimal)> {
-    let mut demand: HashMap<(&str, Option<&str>), i32> = HashMap::new();
-    for item in &payload.items {
-        *demand
-            .entry((item.product_id.as_str(), item.color.as_deref()))
-            .or_insert(0) += item.quantity;
-    }
-
     let requested_ids: Vec<String> = payload.items.iter().map(|i| i.product_id.clone()).collect();
-    let all_products = products_queries::find_by_ids(&state.db, &requested_ids).await?;
-    let all_images =
-        products_queries::find_images_by_product_ids(&state.db, &requested_ids).await?;
-
-    let cable_type_ids: Vec<i32> = all_products
-        .values()
-        .filter_map(|p| p.cable_type_id)
-        .collect();
-    let cable_variants =
-        products_queries::find_cable_variants_by_type_ids(&state.db, &cable_type_ids).await?;
+    let catalog = Catalog::load(&state.db, &requested_ids).await?;
+    let demand = catalog.demand_by_variant(&payload.items);
 
     let mut subtotal = Decimal::ZERO;
     let mut order_items = Vec::with_capacity(payload.items.len());
 
     for item in &payload.items {
-        let product = all_products.get(&item.product_id).ok_or_else(|| {
-            AppError::NotFound(format!("პროდუქტი {} ვერ მოიძებნა", item.product_id))
-        })?;
-
-        if !product.enabled {
-            return Err(AppError::BadRequest(format!(
-                "პროდუქტი {} მიუწვდომელია",
-                item.product_id
-            )));
-        }
-
-        let images = all_images
-            .get(&item.product_id)
-            .map(|v| v.as_slice())
-            .unwrap_or_default();
-
-        let distinct_colors: HashSet<_> = images
-            .iter()
-            .filter_map(|img| img.color.as_deref())
-            .collect();
-
-        if distinct_colors.len() > 1 && item.color.is_none() {
-            return Err(AppError::BadRequest(format!(
-                "ფერი აუცილებელია პროდუქტისთვის {}",
-                item.product_id
-            )));
-        }
-
-        let image = match &item.color {
-            Some(color) => images
-                .iter()
-                .find(|img| img.color.as_deref() == Some(color.as_str())),
-            None => images.iter().find(|img| img.is_primary).or(images.first()),
-        }
-        .ok_or_else(|| {
-            AppError::BadRequest(format!(
-                "ფერი მიუწვდომელია პროდუქტისთვის {}",
-                item.product_id
-            ))
-        })?;
-
-        let total_demand = demand[&(item.product_id.as_str(), item.color.as_deref())];
-        let stock: i32 = match &item.color {
-            Some(color) => images
-                .iter()
-                .filter(|img| img.color.as_deref() == Some(color.as_str()))
-                .map(|img| img.quantity)
-                .sum(),
-            None => image.quantity,
-        };
-
-        if stock < total_demand {
-            return Err(AppError::BadRequest(format!(
-                "არასაკმარისი მარაგი პროდუქტისთვის {}",
-                item.product_id
-            )));
-        }
-
-        let variant = match &item.cable_config {
-            Some(cfg) => {
-                let cable_type_id = product.cable_type_id.ok_or_else(|| {
-                    AppError::BadRequest(format!("პროდუქტი {} არ არის კაბელი", item.product_id))
-                })?;
-                let v = cable_variants
-                    .get(&(cable_type_id, cfg.watts, cfg.length_cm))
-                    .ok_or_else(|| {
-                        AppError::BadRequest(format!(
-                            "არასწორი კაბელის კონფიგურაცია პროდუქტისთვის {}",
-                            item.product_id
-                        ))
-                    })?;
-                Some(v)
-            }
-            None => None,
-        };
-
-        let price = item_price(product.price, product.discounted_price, variant);
-        subtotal += price * Decimal::from(item.quantity);
-
-        let cable_config_json = item
-            .cable_config
-            .as_ref()
-            .map(|c| json!({ "watts": c.watts, "length_cm": c.length_cm }));
-
-        order_items.push(OrderItemData {
-            product_id: Some(item.product_id.clone()),
-            color: item.color.clone(),
-            quantity: item.quantity,
-            price,
-            product_name: product.name.clone(),
-            image: serde_json::to_value(image)?,
-            cable_config: cable_config_json,
-        });
+        let line = catalog.price_line(item, &demand)?;
+        subtotal += line.price * Decimal::from(line.quantity);
+        order_items.push(line);
     }
 
     Ok((order_items, subtotal))
 }
 
-fn item_price(
-    base_price: Decimal,
-    discounted_price: Option<Decimal>,
-    variant: Option<&CableVariant>,
-) -> Decimal {
-    if let Some(v) = variant {
-        return v.price;
-    }
-    match discounted_price {
-        Some(dp) if dp < base_price => dp,
-        _ => base_price,
+/// Applies a payment status reported by Flitt, whether it arrived through the
+/// callback or was polled by the reconciler.
+pub(crate) async fn apply_flitt_status(
+    state: &AppState,
+    order_id: &str,
+    status: OrderStatus,
+    payment_id: Option<i32>,
+) -> Result<Option<crate::models::Order>> {
+    let Some((order, stock_ok)) = order_queries::update_order_status_and_deduct_stock(
+        &state.db,
+        order_id,
+        status,
+        payment_id,
+        OrderStatusSource::Flitt,
+    )
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    if !stock_ok {
+        tracing::warn!("Insufficient stock for approved order {}", order_id);
+    } else if status == OrderStatus::Approved {
+        send_order_emails(state, &order).await;
     }
+
+    Ok(Some(order))
 }
 
 pub async fn flitt_callback(
     State(state): State<AppState>,
     Json(payload): Json<serde_json::Value>,
 ) -> impl IntoResponse {
-    if !flitt_service::verify_callback_signature(&state.flitt_secret_key, &payload) {
+    if !flitt_service::verify_callback_signature(&state.flitt.secret_key, &payload) {
         tracing::warn!("Invalid Flitt callback signature");
         return StatusCode::BAD_REQUEST;
     }
@@ -473,22 +529,17 @@ pub async fn flitt_callback(
         payment_id
     );
 
-    match order_queries::update_order_status_and_deduct_stock(
-        &state.db,
-        order_id,
-        order_status,
-        payment_id,
-    )
-    .await
-    {
-        Ok(Some((order, stock_ok))) => {
-            if !stock_ok {
-                tracing::warn!("Insufficient stock for approved order {}", order_id);
-            } else if order_status == "approved" {
-                send_order_emails(&state, &order).await;
-            }
-            StatusCode::OK
-        }
+    let Some(status) = OrderStatus::from_flitt(order_status) else {
+        tracing::warn!(
+            "Flitt callback: unknown status {} for order {}",
+            order_status,
+            order_id
+        );
+        return StatusCode::OK;
+    };
+
+    match apply_flitt_status(&state, order_id, status, payment_id).await {
+        Ok(Some(_)) => StatusCode::OK,
         Ok(None) => {
             tracing::warn!(
                 "Flitt callback: order {} not found or already processed",
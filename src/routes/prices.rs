@@ -0,0 +1,109 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use chrono::Utc;
+use rust_decimal::Decimal;
+
+use crate::{
+    AppState,
+    error::{AppError, Result},
+    models::{
+        CreatePriceScheduleRequest, PriceHistoryEntry, PriceScheduleQuery,
+        PriceScheduleSearchResponse, Product, ScheduledPriceChange,
+    },
+    queries::{price_queries, products_queries},
+    services::product_service,
+    utils::jwt::Claims,
+};
+
+async fn find_product(state: &AppState, id: &str) -> Result<Product> {
+    products_queries::find_by_id(&state.db, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("პროდუქტი id-ით {} ვერ მოიძებნა", id)))
+}
+
+pub async fn get_price_history(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<PriceHistoryEntry>>> {
+    find_product(&state, &id).await?;
+    let history = price_queries::get_price_history(&state.db, &id).await?;
+
+    Ok(Json(history))
+}
+
+/// The change is checked against the product's current prices here and again
+/// when it is applied, since the product may change in between.
+pub async fn create_price_schedule(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<String>,
+    Json(payload): Json<CreatePriceScheduleRequest>,
+) -> Result<(StatusCode, Json<ScheduledPriceChange>)> {
+    let product = find_product(&state, &id).await?;
+
+    if payload.price.is_none() && payload.discount.is_none() && payload.discounted_price.is_none() {
+        return Err(AppError::BadRequest(
+            "მიუთითეთ price, discount ან discounted_price".to_string(),
+        ));
+    }
+    if payload.price.is_some_and(|p| p <= Decimal::ZERO) {
+        return Err(AppError::BadRequest("price უნდა იყოს დადებითი".to_string()));
+    }
+    if payload.apply_at <= Utc::now() {
+        return Err(AppError::BadRequest(
+            "apply_at მომავალში უნდა იყოს".to_string(),
+        ));
+    }
+
+    let mut request = product_service::price_change_request(
+        payload.price,
+        payload.discount,
+        payload.discounted_price,
+    );
+    product_service::prepare_update(&state.db, &product, &mut request).await?;
+
+    let schedule =
+        price_queries::create_schedule(&state.db, &product.id, &payload, claims.user_id).await?;
+
+    Ok((StatusCode::CREATED, Json(schedule)))
+}
+
+pub async fn search_price_schedules(
+    State(state): State<AppState>,
+    Query(params): Query<PriceScheduleQuery>,
+) -> Result<Json<PriceScheduleSearchResponse>> {
+    let (schedules, total, limit, offset) =
+        price_queries::search_schedules(&state.db, &params).await?;
+
+    Ok(Json(PriceScheduleSearchResponse {
+        schedules,
+        total,
+        limit,
+        offset,
+    }))
+}
+
+pub async fn cancel_price_schedule(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<ScheduledPriceChange>> {
+    if price_queries::find_schedule(&state.db, id).await?.is_none() {
+        return Err(AppError::NotFound(format!(
+            "ფასის ცვლილება id-ით {} ვერ მოიძებნა",
+            id
+        )));
+    }
+
+    let schedule = price_queries::cancel_schedule(&state.db, id)
+        .await?
+        .ok_or_else(|| {
+            AppError::BadRequest(
+                "გაუქმება შესაძლებელია მხოლოდ მოლოდინში მყოფი ცვლილების".to_string(),
+            )
+        })?;
+
+    Ok(Json(schedule))
+}
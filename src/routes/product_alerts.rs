@@ -0,0 +1,93 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    error::{AppError, Result},
+    models::{ProductAlertKind, ProductAlertRequest},
+    queries::{
+        product_alert_queries::{self, NewProductAlert},
+        products_queries, variant_queries,
+    },
+    services::pricing_service,
+    utils::extractors::OptionalClaims,
+};
+
+pub async fn subscribe_product_alert(
+    State(state): State<AppState>,
+    OptionalClaims(claims): OptionalClaims,
+    Path(product_id): Path<String>,
+    Json(payload): Json<ProductAlertRequest>,
+) -> Result<StatusCode> {
+    let email = payload
+        .email
+        .as_deref()
+        .or(claims.as_ref().map(|c| c.email.as_str()))
+        .map(|e| e.trim().to_lowercase())
+        .unwrap_or_default();
+    if email.is_empty() || !email.contains('@') {
+        return Err(AppError::BadRequest("არასწორი ელფოსტა".to_string()));
+    }
+
+    let product = products_queries::find_by_id(&state.db, &product_id)
+        .await?
+        .filter(|p| p.enabled)
+        .ok_or_else(|| AppError::NotFound(format!("პროდუქტი {} ვერ მოიძებნა", product_id)))?;
+
+    let color = payload
+        .color
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty());
+    let variants = variant_queries::find_by_product_id(&state.db, &product.id).await?;
+    let matching: Vec<_> = variants
+        .iter()
+        .filter(|v| color.is_none() || v.color.as_deref() == color)
+        .collect();
+    if matching.is_empty() && color.is_some() {
+        return Err(AppError::BadRequest(format!(
+            "ფერი მიუწვდომელია პროდუქტისთვის {}",
+            product.id
+        )));
+    }
+
+    let reference_price = match payload.kind {
+        ProductAlertKind::BackInStock => {
+            if matching.iter().map(|v| v.stock).sum::<i32>() > 0 {
+                return Err(AppError::BadRequest("პროდუქტი მარაგშია".to_string()));
+            }
+            None
+        }
+        ProductAlertKind::PriceDrop => Some(pricing_service::effective_price(&product)),
+    };
+
+    product_alert_queries::subscribe(
+        &state.db,
+        &NewProductAlert {
+            product_id: &product.id,
+            color,
+            kind: payload.kind,
+            email: &email,
+            user_id: claims.map(|c| c.user_id),
+            reference_price,
+        },
+    )
+    .await?;
+
+    Ok(StatusCode::CREATED)
+}
+
+pub async fn unsubscribe_product_alert(
+    State(state): State<AppState>,
+    Path(token): Path<Uuid>,
+) -> Result<StatusCode> {
+    if !product_alert_queries::unsubscribe(&state.db, token).await? {
+        return Err(AppError::NotFound("ბმული არასწორია".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
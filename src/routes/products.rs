@@ -2,17 +2,19 @@ use axum::{
     Json,
     extract::{Path, Query, State},
 };
-use http::StatusCode;
+use http::{HeaderMap, StatusCode, header::USER_AGENT};
 
 use crate::{
     AppState,
     error::{AppError, Result},
     models::{
-        Brand, CableType, CableTypeWithVariants, CableVariant, ProductFacets, ProductQuery,
-        ProductResponse, ProductSearchResponse, TopProductsQuery,
+        Brand, CableType, CableTypeWithVariants, CableVariant, ProductDetailResponse,
+        ProductFacets, ProductQuery, ProductResponse, ProductSearchResponse, ProductViewRequest,
+        RelatedProductsQuery, TopProductsQuery,
     },
-    queries::{admin_queries, products_queries},
-    utils::extractors::LenientClaims,
+    queries::{admin_queries, bundle_queries, products_queries, question_queries, variant_queries},
+    services::{recommendation_service, view_service},
+    utils::extractors::{ClientIp, LenientClaims},
 };
 
 pub async fn search_product(
@@ -30,17 +32,27 @@ pub async fn search_product(
 pub async fn get_product(
     State(state): State<AppState>,
     Path(id): Path<String>,
-) -> Result<Json<ProductResponse>> {
+) -> Result<Json<ProductDetailResponse>> {
     let (data, images, categories, seo) = products_queries::find_product_bundle(&state.db, &id)
         .await?
         .ok_or(AppError::NotFound("პროდუქტი ვერ მოიძებნა".to_string()))?;
 
-    Ok(Json(ProductResponse {
-        videos: ProductResponse::videos_from(&data),
-        data,
-        images,
-        categories,
-        seo,
+    let variants = variant_queries::find_by_product_id(&state.db, &data.id).await?;
+    let bundle = bundle_queries::find_by_bundle_id(&state.db, &data.id).await?;
+    let questions = question_queries::get_answered_for_product(&state.db, &data.id).await?;
+
+    Ok(Json(ProductDetailResponse {
+        product: ProductResponse {
+            videos: ProductResponse::videos_from(&data),
+            rating: ProductResponse::rating_from(&data),
+            data,
+            images,
+            categories,
+            seo,
+        },
+        variants,
+        bundle,
+        questions,
     }))
 }
 
@@ -59,8 +71,9 @@ pub async fn get_product_facets(
 pub async fn get_related_products(
     State(state): State<AppState>,
     Path(id): Path<String>,
+    Query(params): Query<RelatedProductsQuery>,
 ) -> Result<Json<Vec<ProductResponse>>> {
-    let related = products_queries::get_related_products(&state.db, &id, 12).await?;
+    let related = recommendation_service::related(&state.db, &id, params.strategy, 12).await?;
     Ok(Json(related))
 }
 
@@ -109,11 +122,22 @@ pub async fn add_product_views(
     State(state): State<AppState>,
     Path(id): Path<String>,
     LenientClaims(claims): LenientClaims,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    body: Option<Json<ProductViewRequest>>,
 ) -> Result<StatusCode> {
     let user_id = claims.map(|c| c.user_id);
-    products_queries::add_product_views(&state.db, &id, user_id).await?;
+    let Json(body) = body.unwrap_or_default();
+    let user_agent = headers.get(USER_AGENT).and_then(|h| h.to_str().ok());
+
+    let recorded =
+        view_service::record_view(&state.db, &id, user_id, body.session_id, ip, user_agent).await?;
 
-    Ok(StatusCode::CREATED)
+    Ok(if recorded {
+        StatusCode::CREATED
+    } else {
+        StatusCode::NO_CONTENT
+    })
 }
 
 pub async fn get_top_products(
@@ -0,0 +1,127 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+
+use crate::{
+    AppState,
+    error::{AppError, Result},
+    models::{
+        AnswerQuestionRequest, CreateQuestionRequest, ProductQuestion, QuestionQuery,
+        QuestionSearchResponse,
+    },
+    queries::{products_queries, question_queries},
+    services::email_service,
+    utils::{extractors::extract_user_id, jwt::Claims},
+};
+
+const MAX_QUESTION_LENGTH: usize = 1000;
+const MAX_ANSWER_LENGTH: usize = 5000;
+
+/// Questions stay hidden until an operator answers them.
+pub async fn ask_question(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<CreateQuestionRequest>,
+) -> Result<(StatusCode, Json<ProductQuestion>)> {
+    let user_id = extract_user_id(&claims)?;
+
+    let text = payload.question.trim();
+    if text.is_empty() || text.chars().count() > MAX_QUESTION_LENGTH {
+        return Err(AppError::BadRequest(format!(
+            "კითხვა უნდა შეიცავდეს 1-დან {MAX_QUESTION_LENGTH}-მდე სიმბოლოს"
+        )));
+    }
+
+    let product = products_queries::find_by_id(&state.db, &payload.product_id)
+        .await?
+        .filter(|p| p.enabled)
+        .ok_or_else(|| {
+            AppError::NotFound(format!("პროდუქტი {} ვერ მოიძებნა", payload.product_id))
+        })?;
+
+    let question = question_queries::create_question(
+        &state.db,
+        &product.id,
+        user_id,
+        &claims.name,
+        &claims.email,
+        text,
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(question)))
+}
+
+pub async fn search_questions(
+    State(state): State<AppState>,
+    Query(params): Query<QuestionQuery>,
+) -> Result<Json<QuestionSearchResponse>> {
+    let (questions, total, limit, offset) =
+        question_queries::search_questions(&state.db, &params).await?;
+
+    Ok(Json(QuestionSearchResponse {
+        questions,
+        total,
+        limit,
+        offset,
+    }))
+}
+
+/// Editing an answer does not email the asker again.
+pub async fn answer_question(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<i32>,
+    Json(payload): Json<AnswerQuestionRequest>,
+) -> Result<Json<ProductQuestion>> {
+    let answer = payload.answer.trim();
+    if answer.is_empty() || answer.chars().count() > MAX_ANSWER_LENGTH {
+        return Err(AppError::BadRequest(format!(
+            "პასუხი უნდა შეიცავდეს 1-დან {MAX_ANSWER_LENGTH}-მდე სიმბოლოს"
+        )));
+    }
+
+    let (question, first_answer) =
+        question_queries::answer_question(&state.db, id, answer, claims.user_id).await?;
+
+    if first_answer {
+        let product_name = products_queries::find_by_id(&state.db, &question.product_id)
+            .await?
+            .map(|p| p.name)
+            .unwrap_or_else(|| question.product_id.clone());
+        let product_url = format!("{}/products/{}", state.frontend_url, question.product_id);
+
+        if let Err(e) = email_service::send_question_answered_email(
+            &state.ses_client,
+            &question,
+            &product_name,
+            &product_url,
+        )
+        .await
+        {
+            tracing::error!(
+                "Failed to send answer notification for question {}: {:?}",
+                question.id,
+                e
+            );
+        }
+    }
+
+    Ok(Json(question))
+}
+
+pub async fn delete_question(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<StatusCode> {
+    if question_queries::delete_question(&state.db, id).await? == 0 {
+        return Err(AppError::NotFound(format!(
+            "კითხვა id-ით {} ვერ მოიძებნა",
+            id
+        )));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,58 @@
+use axum::{
+    Extension, Json,
+    extract::{Query, State},
+    http::StatusCode,
+};
+
+use crate::{
+    AppState,
+    error::Result,
+    models::{MergeViewsRequest, ProductResponse, RecentlyViewedQuery},
+    queries::products_queries,
+    utils::{extractors::extract_user_id, jwt::Claims},
+};
+
+const DEFAULT_LIMIT: i64 = 20;
+const MAX_LIMIT: i64 = 50;
+
+pub async fn get_recently_viewed(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(params): Query<RecentlyViewedQuery>,
+) -> Result<Json<Vec<ProductResponse>>> {
+    let user_id = extract_user_id(&claims)?;
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let ids = products_queries::get_recently_viewed_ids(&state.db, user_id, limit).await?;
+    let response = products_queries::build_products_response_ordered(&state.db, &ids).await?;
+
+    Ok(Json(response))
+}
+
+/// Called after login with the session id the client sent along with its
+/// anonymous views.
+pub async fn merge_recently_viewed(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<MergeViewsRequest>,
+) -> Result<Json<Vec<ProductResponse>>> {
+    let user_id = extract_user_id(&claims)?;
+
+    products_queries::claim_session_views(&state.db, user_id, payload.session_id).await?;
+
+    let ids = products_queries::get_recently_viewed_ids(&state.db, user_id, DEFAULT_LIMIT).await?;
+    let response = products_queries::build_products_response_ordered(&state.db, &ids).await?;
+
+    Ok(Json(response))
+}
+
+pub async fn clear_recently_viewed(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<StatusCode> {
+    let user_id = extract_user_id(&claims)?;
+
+    products_queries::clear_view_history(&state.db, user_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
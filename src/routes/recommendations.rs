@@ -0,0 +1,28 @@
+use axum::{
+    Extension, Json,
+    extract::{Query, State},
+};
+
+use crate::{
+    AppState,
+    error::Result,
+    models::{ProductResponse, RecommendationsQuery},
+    services::recommendation_service,
+    utils::{extractors::extract_user_id, jwt::Claims},
+};
+
+const DEFAULT_LIMIT: i64 = 12;
+const MAX_LIMIT: i64 = 48;
+
+pub async fn get_recommendations(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(params): Query<RecommendationsQuery>,
+) -> Result<Json<Vec<ProductResponse>>> {
+    let user_id = extract_user_id(&claims)?;
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let products = recommendation_service::for_user(&state.db, user_id, limit).await?;
+
+    Ok(Json(products))
+}
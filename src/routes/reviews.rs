@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+
+use crate::{
+    AppState,
+    error::{AppError, Result},
+    models::{
+        CommentImage, CommentImageUrlRequest, CommentImageUrlResponse, CreateReviewRequest,
+        ProductReview, ProductReviewResponse, PublicReview, PublicReviewSearchResponse,
+        ReviewModerationRequest, ReviewQuery, ReviewSearchResponse, ReviewStatus,
+    },
+    queries::{products_queries, review_queries},
+    routes::orders::presign_image_uploads,
+    services::image_url_service,
+    utils::{extractors::extract_user_id, jwt::Claims},
+};
+
+const MAX_REVIEW_IMAGES: usize = 5;
+const MAX_REVIEW_LENGTH: usize = 5000;
+
+fn review_images_prefix(state: &AppState) -> &'static str {
+    match state.environment {
+        crate::config::Environment::Staging => "review-images-staging",
+        crate::config::Environment::Main => "review-images-main",
+    }
+}
+
+async fn review_responses(
+    state: &AppState,
+    reviews: Vec<(ProductReview, String)>,
+) -> Result<Vec<ProductReviewResponse>> {
+    let review_ids: Vec<i32> = reviews.iter().map(|(r, _)| r.id).collect();
+    let images = review_queries::get_images_for_reviews(&state.db, &review_ids).await?;
+
+    let env_prefix = review_images_prefix(state);
+    let mut images_by_review: HashMap<i32, Vec<CommentImage>> = HashMap::new();
+    for img in images {
+        let Some(review_id) = img.review_id else {
+            continue;
+        };
+        images_by_review
+            .entry(review_id)
+            .or_default()
+            .push(CommentImage {
+                url: format!(
+                    "{}/{}/{}.{}",
+                    state.assets_url, env_prefix, img.image_uuid, img.extension
+                ),
+                image_uuid: img.image_uuid,
+            });
+    }
+
+    Ok(reviews
+        .into_iter()
+        .map(|(review, author_name)| ProductReviewResponse {
+            images: images_by_review.remove(&review.id).unwrap_or_default(),
+            review,
+            author_name,
+        })
+        .collect())
+}
+
+async fn search_response(state: &AppState, params: &ReviewQuery) -> Result<ReviewSearchResponse> {
+    let (reviews, total, limit, offset) = review_queries::search_reviews(&state.db, params).await?;
+
+    Ok(ReviewSearchResponse {
+        reviews: review_responses(state, reviews).await?,
+        total,
+        limit,
+        offset,
+    })
+}
+
+pub async fn get_product_reviews(
+    State(state): State<AppState>,
+    Path(product_id): Path<String>,
+    Query(mut params): Query<ReviewQuery>,
+) -> Result<Json<PublicReviewSearchResponse>> {
+    params.status = Some(ReviewStatus::Approved);
+    params.product_id = Some(product_id);
+
+    let response = search_response(&state, &params).await?;
+    Ok(Json(PublicReviewSearchResponse {
+        reviews: response
+            .reviews
+            .into_iter()
+            .map(PublicReview::from)
+            .collect(),
+        total: response.total,
+        limit: response.limit,
+        offset: response.offset,
+    }))
+}
+
+pub async fn generate_review_image_urls(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<CommentImageUrlRequest>,
+) -> Result<Json<CommentImageUrlResponse>> {
+    let user_id = extract_user_id(&claims)?;
+
+    let db = &state.db;
+    let response = presign_image_uploads(
+        &state,
+        &payload,
+        MAX_REVIEW_IMAGES,
+        review_images_prefix(&state),
+        |image_uuid, extension| {
+            review_queries::add_review_image(db, user_id, image_uuid, extension)
+        },
+    )
+    .await?;
+
+    Ok(Json(response))
+}
+
+/// New reviews wait in the moderation queue until an admin approves them.
+pub async fn create_review(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<CreateReviewRequest>,
+) -> Result<(StatusCode, Json<ProductReview>)> {
+    let user_id = extract_user_id(&claims)?;
+
+    if !(1..=5).contains(&payload.rating) {
+        return Err(AppError::BadRequest(
+            "შეფასება უნდა იყოს 1-დან 5-მდე".to_string(),
+        ));
+    }
+    let body = payload
+        .body
+        .as_deref()
+        .map(str::trim)
+        .filter(|b| !b.is_empty());
+    if body.is_some_and(|b| b.chars().count() > MAX_REVIEW_LENGTH) {
+        return Err(AppError::BadRequest(format!(
+            "ტექსტი არ უნდა აღემატებოდეს {MAX_REVIEW_LENGTH} სიმბოლოს"
+        )));
+    }
+    if payload.image_uuids.len() > MAX_REVIEW_IMAGES {
+        return Err(AppError::BadRequest(format!(
+            "მაქსიმუმ {MAX_REVIEW_IMAGES} სურათია დაშვებული"
+        )));
+    }
+
+    if products_queries::find_by_id(&state.db, &payload.product_id)
+        .await?
+        .is_none()
+    {
+        return Err(AppError::NotFound(format!(
+            "პროდუქტი {} ვერ მოიძებნა",
+            payload.product_id
+        )));
+    }
+
+    if !review_queries::has_purchased(&state.db, user_id, &payload.product_id).await? {
+        return Err(AppError::Forbidden(
+            "შეფასების დატოვება შეუძლიათ მხოლოდ პროდუქტის მყიდველებს".to_string(),
+        ));
+    }
+
+    if review_queries::find_by_user_and_product(&state.db, user_id, &payload.product_id)
+        .await?
+        .is_some()
+    {
+        return Err(AppError::Conflict(
+            "ამ პროდუქტზე შეფასება უკვე დატოვებული გაქვთ".to_string(),
+        ));
+    }
+
+    let review = review_queries::create_review(
+        &state.db,
+        user_id,
+        &payload.product_id,
+        payload.rating,
+        body,
+        &payload.image_uuids,
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(review)))
+}
+
+/// Moderation queue. Defaults to reviews that still await a decision.
+pub async fn search_reviews(
+    State(state): State<AppState>,
+    Query(mut params): Query<ReviewQuery>,
+) -> Result<Json<ReviewSearchResponse>> {
+    params.status = params.status.or(Some(ReviewStatus::Pending));
+
+    Ok(Json(search_response(&state, &params).await?))
+}
+
+pub async fn moderate_review(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<i32>,
+    Json(payload): Json<ReviewModerationRequest>,
+) -> Result<Json<ProductReview>> {
+    if payload.status == ReviewStatus::Pending {
+        return Err(AppError::BadRequest(
+            "სტატუსი უნდა იყოს approved ან rejected".to_string(),
+        ));
+    }
+
+    let comment = payload
+        .comment
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty());
+
+    let review =
+        review_queries::moderate_review(&state.db, id, payload.status, claims.user_id, comment)
+            .await?;
+
+    Ok(Json(review))
+}
+
+pub async fn delete_review(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<StatusCode> {
+    let images = review_queries::delete_review(&state.db, id).await?;
+
+    let env_prefix = review_images_prefix(&state);
+    for img in images {
+        let key = format!("{}/{}.{}", env_prefix, img.image_uuid, img.extension);
+        if let Err(e) =
+            image_url_service::delete_single_object(&state.s3_client, &state.s3_bucket, &key).await
+        {
+            tracing::warn!("failed to delete review image {key}: {e}");
+        }
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,82 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use http::StatusCode;
+
+use crate::{
+    AppState,
+    error::{AppError, Result},
+    models::{SearchSynonym, SearchSynonymRequest},
+    queries::search_queries,
+};
+
+/// Trims the words and drops blanks, repeats and the term itself.
+fn normalize_request(req: SearchSynonymRequest) -> Result<(String, Vec<String>)> {
+    let term = req.term.trim().to_string();
+    if term.is_empty() {
+        return Err(AppError::BadRequest("სიტყვა აუცილებელია".to_string()));
+    }
+
+    let mut synonyms: Vec<String> = Vec::with_capacity(req.synonyms.len());
+    for synonym in req.synonyms {
+        let synonym = synonym.trim();
+        if synonym.is_empty()
+            || synonym.eq_ignore_ascii_case(&term)
+            || synonyms.iter().any(|s| s.eq_ignore_ascii_case(synonym))
+        {
+            continue;
+        }
+        synonyms.push(synonym.to_string());
+    }
+    if synonyms.is_empty() {
+        return Err(AppError::BadRequest(
+            "მიუთითეთ ერთი სინონიმი მაინც".to_string(),
+        ));
+    }
+
+    Ok((term, synonyms))
+}
+
+pub async fn get_search_synonyms(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<SearchSynonym>>> {
+    let synonyms = search_queries::get_synonyms(&state.db).await?;
+    Ok(Json(synonyms))
+}
+
+pub async fn create_search_synonym(
+    State(state): State<AppState>,
+    Json(payload): Json<SearchSynonymRequest>,
+) -> Result<(StatusCode, Json<SearchSynonym>)> {
+    let (term, synonyms) = normalize_request(payload)?;
+
+    let synonym = search_queries::create_synonym(&state.db, &term, &synonyms).await?;
+    Ok((StatusCode::CREATED, Json(synonym)))
+}
+
+pub async fn update_search_synonym(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Json(payload): Json<SearchSynonymRequest>,
+) -> Result<Json<SearchSynonym>> {
+    let (term, synonyms) = normalize_request(payload)?;
+
+    let synonym = search_queries::update_synonym(&state.db, id, &term, &synonyms)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("სინონიმი id-ით {} ვერ მოიძებნა", id)))?;
+    Ok(Json(synonym))
+}
+
+pub async fn delete_search_synonym(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<StatusCode> {
+    if search_queries::delete_synonym(&state.db, id).await? == 0 {
+        return Err(AppError::NotFound(format!(
+            "სინონიმი id-ით {} ვერ მოიძებნა",
+            id
+        )));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,145 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use rust_decimal::Decimal;
+
+use crate::{
+    AppState,
+    error::{AppError, Result},
+    models::{CreateVariantRequest, ProductVariant, UpdateVariantRequest},
+    queries::{bundle_queries, products_queries, variant_queries},
+    services::product_alert_service,
+    utils::jwt::Claims,
+};
+
+/// Rejects variant ids that don't belong to the product.
+pub(crate) async fn ensure_variant(
+    state: &AppState,
+    product_id: &str,
+    variant_id: i32,
+) -> Result<ProductVariant> {
+    variant_queries::find_by_id(&state.db, product_id, variant_id)
+        .await?
+        .ok_or_else(|| {
+            AppError::BadRequest(format!(
+                "ვარიანტი {} ვერ მოიძებნა პროდუქტისთვის {}",
+                variant_id, product_id
+            ))
+        })
+}
+
+fn validate_variant(
+    sku: Option<&str>,
+    stock: Option<i32>,
+    price_override: Option<Decimal>,
+) -> Result<()> {
+    if sku.is_some_and(|s| s.trim().is_empty()) {
+        return Err(AppError::BadRequest("SKU აუცილებელია".to_string()));
+    }
+    if stock.is_some_and(|s| s < 0) {
+        return Err(AppError::BadRequest(
+            "მარაგი არ შეიძლება იყოს უარყოფითი".to_string(),
+        ));
+    }
+    if price_override.is_some_and(|p| p.is_sign_negative()) {
+        return Err(AppError::BadRequest(
+            "ფასი არ შეიძლება იყოს უარყოფითი".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+async fn restock_alerts(state: &AppState, product_id: &str, before: &[ProductVariant]) {
+    if let Err(e) = product_alert_service::queue_restock_alerts(&state.db, product_id, before).await
+    {
+        tracing::error!("failed to queue back-in-stock alerts for {product_id}: {e}");
+    }
+}
+
+pub async fn get_product_variants(
+    State(state): State<AppState>,
+    Path(product_id): Path<String>,
+) -> Result<Json<Vec<ProductVariant>>> {
+    let variants = variant_queries::find_by_product_id(&state.db, &product_id).await?;
+
+    Ok(Json(variants))
+}
+
+pub async fn create_product_variant(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(product_id): Path<String>,
+    Json(mut payload): Json<CreateVariantRequest>,
+) -> Result<(StatusCode, Json<ProductVariant>)> {
+    if products_queries::find_by_id(&state.db, &product_id)
+        .await?
+        .is_none()
+    {
+        return Err(AppError::NotFound(format!(
+            "პროდუქტი {} ვერ მოიძებნა",
+            product_id
+        )));
+    }
+
+    if bundle_queries::is_bundle(&state.db, &product_id).await? {
+        return Err(AppError::Conflict(
+            "ნაკრებს საკუთარი ვარიანტები არ აქვს".to_string(),
+        ));
+    }
+
+    payload.sku = payload.sku.trim().to_string();
+    validate_variant(
+        Some(&payload.sku),
+        Some(payload.stock),
+        payload.price_override,
+    )?;
+
+    let before = variant_queries::find_by_product_id(&state.db, &product_id).await?;
+    let variant =
+        variant_queries::create_variant(&state.db, &product_id, &payload, claims.user_id).await?;
+    restock_alerts(&state, &product_id, &before).await;
+
+    Ok((StatusCode::CREATED, Json(variant)))
+}
+
+pub async fn update_product_variant(
+    State(state): State<AppState>,
+    Path((product_id, variant_id)): Path<(String, i32)>,
+    Json(mut payload): Json<UpdateVariantRequest>,
+) -> Result<Json<ProductVariant>> {
+    payload.sku = payload.sku.map(|s| s.trim().to_string());
+    validate_variant(payload.sku.as_deref(), None, payload.price_override)?;
+
+    let variant = variant_queries::update_variant(&state.db, &product_id, variant_id, &payload)
+        .await?
+        .ok_or_else(|| {
+            AppError::NotFound(format!(
+                "ვარიანტი {} ვერ მოიძებნა პროდუქტისთვის {}",
+                variant_id, product_id
+            ))
+        })?;
+
+    Ok(Json(variant))
+}
+
+pub async fn delete_product_variant(
+    State(state): State<AppState>,
+    Path((product_id, variant_id)): Path<(String, i32)>,
+) -> Result<StatusCode> {
+    if bundle_queries::is_component(&state.db, variant_id).await? {
+        return Err(AppError::Conflict(
+            "ვარიანტი ნაკრების შემადგენელია".to_string(),
+        ));
+    }
+
+    if variant_queries::delete_variant(&state.db, &product_id, variant_id).await? == 0 {
+        return Err(AppError::NotFound(format!(
+            "ვარიანტი {} ვერ მოიძებნა პროდუქტისთვის {}",
+            variant_id, product_id
+        )));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
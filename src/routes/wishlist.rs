@@ -0,0 +1,59 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+
+use crate::{
+    AppState,
+    error::{AppError, Result},
+    models::ProductResponse,
+    queries::{products_queries, wishlist_queries},
+    utils::{extractors::extract_user_id, jwt::Claims},
+};
+
+pub async fn get_wishlist(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Vec<ProductResponse>>> {
+    let user_id = extract_user_id(&claims)?;
+
+    let ids = wishlist_queries::get_product_ids(&state.db, user_id).await?;
+    let response = products_queries::build_products_response_ordered(&state.db, &ids).await?;
+
+    Ok(Json(response))
+}
+
+pub async fn add_to_wishlist(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(product_id): Path<String>,
+) -> Result<StatusCode> {
+    let user_id = extract_user_id(&claims)?;
+
+    if products_queries::find_by_id(&state.db, &product_id)
+        .await?
+        .is_none()
+    {
+        return Err(AppError::NotFound(format!(
+            "პროდუქტი {} ვერ მოიძებნა",
+            product_id
+        )));
+    }
+
+    wishlist_queries::add_product(&state.db, user_id, &product_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn remove_from_wishlist(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(product_id): Path<String>,
+) -> Result<StatusCode> {
+    let user_id = extract_user_id(&claims)?;
+
+    wishlist_queries::remove_product(&state.db, user_id, &product_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,771 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    io::Cursor,
+    str::FromStr,
+};
+
+use calamine::{Reader, Xlsx, open_workbook_from_rs};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+
+use crate::{
+    error::{AppError, Result},
+    models::{
+        CatalogFileFormat, CatalogJob, CatalogJobKind, CatalogReport, CatalogRowError,
+        CreateCategoryRequest, Faq, PriceChangeOrigin, Product, ProductRequest, ProductResponse,
+        ProductSeoRequest,
+    },
+    queries::{admin_queries, catalog_queries, category_queries, products_queries},
+    services::{product_alert_service, product_service},
+};
+
+/// Column order of exports. Imports take any subset as long as `id` is there;
+/// columns left out keep their current values.
+const COLUMNS: [&str; 20] = [
+    "id",
+    "name",
+    "description",
+    "price",
+    "discounted_price",
+    "brand",
+    "categories",
+    "warranty",
+    "enabled",
+    "coins_eligible",
+    "low_stock_threshold",
+    "quantity",
+    "specifications",
+    "videos",
+    "seo_slug",
+    "seo_meta_title",
+    "seo_meta_description",
+    "seo_meta_keywords",
+    "seo_search_terms",
+    "seo_no_index",
+];
+
+const SEO_COLUMNS: [&str; 6] = [
+    "seo_slug",
+    "seo_meta_title",
+    "seo_meta_description",
+    "seo_meta_keywords",
+    "seo_search_terms",
+    "seo_no_index",
+];
+
+/// XLSX files are zip archives; anything else is read as CSV.
+pub fn detect_format(input: &[u8]) -> CatalogFileFormat {
+    if input.starts_with(b"PK\x03\x04") {
+        CatalogFileFormat::Xlsx
+    } else {
+        CatalogFileFormat::Csv
+    }
+}
+
+/// Runs a claimed job to the end and records how it went.
+pub async fn run_job(pool: &PgPool, job: &CatalogJob) -> Result<()> {
+    let result = match job.kind {
+        CatalogJobKind::Import => run_import(pool, job).await,
+        CatalogJobKind::Export => run_export(pool, job).await,
+    };
+
+    if let Err(e) = result {
+        tracing::error!("catalog job {} failed: {e}", job.id);
+        catalog_queries::fail_job(pool, job.id, None, &row_message(&e)).await?;
+    }
+    Ok(())
+}
+
+async fn run_import(pool: &PgPool, job: &CatalogJob) -> Result<()> {
+    let input = catalog_queries::find_input(pool, job.id)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("ატვირთული ფაილი ვერ მოიძებნა".to_string()))?;
+    let sheet = read_sheet(job.format, &input)?;
+
+    let (rows, mut report, mut lookups) = plan(pool, &sheet).await?;
+    if job.dry_run {
+        return catalog_queries::complete_job(pool, job.id, &report, None).await;
+    }
+    if !report.errors.is_empty() {
+        return catalog_queries::fail_job(
+            pool,
+            job.id,
+            Some(&report),
+            "ფაილი შეიცავს შეცდომებს, არცერთი პროდუქტი არ შეცვლილა",
+        )
+        .await;
+    }
+
+    // rows were checked against the catalog as it was at planning time, so a
+    // row can still fail here; the rest go ahead
+    report.created = 0;
+    report.updated = 0;
+    for row in rows {
+        let (line, id) = (row.row, row.id.clone());
+        match apply_row(pool, job, row, &mut lookups).await {
+            Ok(true) => report.created += 1,
+            Ok(false) => report.updated += 1,
+            Err(e) => report.errors.push(CatalogRowError {
+                row: line,
+                product_id: Some(id),
+                message: row_message(&e),
+            }),
+        }
+    }
+
+    catalog_queries::complete_job(pool, job.id, &report, None).await
+}
+
+async fn run_export(pool: &PgPool, job: &CatalogJob) -> Result<()> {
+    let rows = catalog_queries::get_export_rows(pool).await?;
+    let records: Vec<Vec<String>> = rows
+        .into_iter()
+        .map(|row| {
+            let product = &row.product;
+            let videos = ProductResponse::videos_from(product)
+                .into_iter()
+                .map(|v| v.url)
+                .collect::<Vec<_>>()
+                .join("\n");
+            let specifications = match product.specifications.as_object() {
+                Some(map) if map.is_empty() => String::new(),
+                _ => product.specifications.to_string(),
+            };
+
+            vec![
+                product.id.clone(),
+                product.name.clone(),
+                product.description.clone().unwrap_or_default(),
+                product.price.to_string(),
+                product
+                    .discounted_price
+                    .map(|p| p.to_string())
+                    .unwrap_or_default(),
+                product.brand_name.clone().unwrap_or_default(),
+                row.category_paths.join("; "),
+                product.warranty.clone().unwrap_or_default(),
+                product.enabled.to_string(),
+                product.coins_eligible.to_string(),
+                product
+                    .low_stock_threshold
+                    .map(|t| t.to_string())
+                    .unwrap_or_default(),
+                product.quantity.to_string(),
+                specifications,
+                videos,
+                row.seo_slug.clone().unwrap_or_default(),
+                row.seo_meta_title.clone().unwrap_or_default(),
+                row.seo_meta_description.clone().unwrap_or_default(),
+                row.seo_meta_keywords
+                    .as_deref()
+                    .unwrap_or_default()
+                    .join(", "),
+                row.seo_search_terms
+                    .as_deref()
+                    .unwrap_or_default()
+                    .join(", "),
+                row.seo_no_index.map(|v| v.to_string()).unwrap_or_default(),
+            ]
+        })
+        .collect();
+
+    let output = match job.format {
+        CatalogFileFormat::Xlsx => write_xlsx(&records)?,
+        CatalogFileFormat::Csv => write_csv(&records)?,
+    };
+    let report = CatalogReport {
+        rows: records.len(),
+        ..Default::default()
+    };
+
+    catalog_queries::complete_job(pool, job.id, &report, Some(&output)).await
+}
+
+fn write_xlsx(records: &[Vec<String>]) -> Result<Vec<u8>> {
+    use rust_xlsxwriter::{Format, Workbook};
+
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+
+    let header = Format::new().set_bold();
+    for (col, title) in COLUMNS.iter().enumerate() {
+        sheet
+            .write_string_with_format(0, col as u16, *title, &header)
+            .map_err(|e| {
+                AppError::InternalError(format!("excel-ის გენერაცია ვერ მოხერხდა: {}", e))
+            })?;
+    }
+
+    for (i, record) in records.iter().enumerate() {
+        let row = (i + 1) as u32;
+        for (col, value) in record.iter().enumerate() {
+            sheet.write_string(row, col as u16, value).map_err(|e| {
+                AppError::InternalError(format!("excel-ის გენერაცია ვერ მოხერხდა: {}", e))
+            })?;
+        }
+    }
+
+    workbook
+        .save_to_buffer()
+        .map_err(|e| AppError::InternalError(format!("excel-ის შენახვა ვერ მოხერხდა: {}", e)))
+}
+
+fn write_csv(records: &[Vec<String>]) -> Result<Vec<u8>> {
+    let csv_error =
+        |e: csv::Error| AppError::InternalError(format!("CSV-ის გენერაცია ვერ მოხერხდა: {}", e));
+
+    // the BOM makes Excel read the file as UTF-8
+    let mut writer = csv::Writer::from_writer(b"\xEF\xBB\xBF".to_vec());
+    writer.write_record(COLUMNS).map_err(csv_error)?;
+    for record in records {
+        writer.write_record(record).map_err(csv_error)?;
+    }
+
+    writer
+        .into_inner()
+        .map_err(|e| AppError::InternalError(format!("CSV-ის გენერაცია ვერ მოხერხდა: {}", e)))
+}
+
+/// Rows of the first sheet, with the spreadsheet row number of the first one.
+struct Sheet {
+    first_row: usize,
+    rows: Vec<Vec<String>>,
+}
+
+fn read_sheet(format: CatalogFileFormat, input: &[u8]) -> Result<Sheet> {
+    match format {
+        CatalogFileFormat::Csv => {
+            let mut reader = csv::ReaderBuilder::new()
+                .has_headers(false)
+                .flexible(true)
+                .from_reader(input.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(input));
+
+            let mut rows = Vec::new();
+            for record in reader.records() {
+                let record = record.map_err(|e| {
+                    AppError::BadRequest(format!("CSV ფაილის წაკითხვა ვერ მოხერხდა: {}", e))
+                })?;
+                rows.push(record.iter().map(|c| c.trim().to_string()).collect());
+            }
+            Ok(Sheet { first_row: 1, rows })
+        }
+        CatalogFileFormat::Xlsx => {
+            let mut workbook: Xlsx<_> = open_workbook_from_rs(Cursor::new(input)).map_err(|e| {
+                AppError::BadRequest(format!("excel ფაილის წაკითხვა ვერ მოხერხდა: {}", e))
+            })?;
+            let range = workbook
+                .worksheet_range_at(0)
+                .ok_or_else(|| AppError::BadRequest("ფაილში ფურცელი არ არის".to_string()))?
+                .map_err(|e| {
+                    AppError::BadRequest(format!("excel ფაილის წაკითხვა ვერ მოხერხდა: {}", e))
+                })?;
+
+            let rows = range
+                .rows()
+                .map(|r| r.iter().map(|c| c.to_string().trim().to_string()).collect())
+                .collect();
+            Ok(Sheet {
+                first_row: range.start().map(|(r, _)| r as usize + 1).unwrap_or(1),
+                rows,
+            })
+        }
+    }
+}
+
+/// Maps each known column to its position in the file.
+fn read_header(header: &[String]) -> Result<HashMap<&'static str, usize>> {
+    let mut columns = HashMap::new();
+    for (i, title) in header.iter().enumerate() {
+        let title = title.to_lowercase();
+        if title.is_empty() {
+            continue;
+        }
+        let column = COLUMNS
+            .iter()
+            .find(|c| **c == title)
+            .ok_or_else(|| AppError::BadRequest(format!("უცნობი სვეტი: {}", title)))?;
+        if columns.insert(*column, i).is_some() {
+            return Err(AppError::BadRequest(format!(
+                "სვეტი {} რამდენჯერმეა მითითებული",
+                title
+            )));
+        }
+    }
+
+    if !columns.contains_key("id") {
+        return Err(AppError::BadRequest("სვეტი id აუცილებელია".to_string()));
+    }
+    Ok(columns)
+}
+
+struct Cells<'a> {
+    columns: &'a HashMap<&'static str, usize>,
+    values: &'a [String],
+}
+
+impl Cells<'_> {
+    /// None when the column isn't in the file; a blank cell is `Some("")`.
+    fn get(&self, column: &str) -> Option<&str> {
+        self.columns
+            .get(column)
+            .map(|&i| self.values.get(i).map(String::as_str).unwrap_or(""))
+    }
+
+    fn text(&self, column: &str) -> Option<String> {
+        self.get(column)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    }
+
+    fn parse<T: FromStr>(&self, column: &str) -> std::result::Result<Option<T>, String> {
+        match self.get(column) {
+            None | Some("") => Ok(None),
+            Some(v) => v
+                .replace(',', ".")
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("{}: არასწორი მნიშვნელობა '{}'", column, v)),
+        }
+    }
+
+    fn flag(&self, column: &str) -> std::result::Result<Option<bool>, String> {
+        match self.get(column).map(str::to_lowercase).as_deref() {
+            None | Some("") => Ok(None),
+            Some("true" | "1" | "yes" | "კი") => Ok(Some(true)),
+            Some("false" | "0" | "no" | "არა") => Ok(Some(false)),
+            Some(v) => Err(format!("{}: არასწორი მნიშვნელობა '{}'", column, v)),
+        }
+    }
+
+    fn list(&self, column: &str, separator: char) -> Option<Vec<String>> {
+        self.get(column).map(|v| {
+            v.split(separator)
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+    }
+}
+
+/// One data row as read from the file, before it is checked against the
+/// catalog.
+struct ParsedRow {
+    row: usize,
+    id: String,
+    request: ProductRequest,
+    brand: Option<String>,
+    categories: Option<Vec<Vec<String>>>,
+    seo: SeoCells,
+}
+
+#[derive(Default)]
+struct SeoCells {
+    slug: Option<Option<String>>,
+    meta_title: Option<Option<String>>,
+    meta_description: Option<Option<String>>,
+    meta_keywords: Option<Vec<String>>,
+    search_terms: Option<Vec<String>>,
+    no_index: Option<bool>,
+}
+
+/// Blank cells clear the discount, the low-stock threshold, videos,
+/// categories and SEO fields; other blank cells keep the current value.
+/// `quantity` is only used for new products, since stock changes go through
+/// the warehouse ledger.
+fn parse_row(row: usize, cells: &Cells) -> std::result::Result<ParsedRow, String> {
+    let id = cells
+        .text("id")
+        .ok_or_else(|| "id აუცილებელია".to_string())?;
+
+    let mut request = ProductRequest {
+        id: Some(id.clone()),
+        name: cells.text("name"),
+        description: cells.text("description"),
+        price: cells.parse::<Decimal>("price")?,
+        quantity: cells.parse("quantity")?,
+        warranty: cells.text("warranty"),
+        enabled: cells.flag("enabled")?,
+        coins_eligible: cells.flag("coins_eligible")?,
+        ..Default::default()
+    };
+
+    if cells.get("discounted_price").is_some() {
+        match cells.parse::<Decimal>("discounted_price")? {
+            Some(price) => request.discounted_price = Some(price),
+            None => request.discount = Some(Decimal::ZERO),
+        }
+    }
+    if cells.get("low_stock_threshold").is_some() {
+        request.low_stock_threshold = Some(cells.parse("low_stock_threshold")?);
+    }
+    if let Some(raw) = cells.text("specifications") {
+        let value: serde_json::Value =
+            serde_json::from_str(&raw).map_err(|_| "specifications: არასწორი JSON".to_string())?;
+        if !value.is_object() {
+            return Err("specifications უნდა იყოს JSON ობიექტი".to_string());
+        }
+        request.specifications = Some(value);
+    }
+    request.videos = cells.get("videos").map(|v| {
+        v.split_whitespace()
+            .map(|url| url.trim_end_matches(',').to_string())
+            .collect()
+    });
+
+    let categories = cells.list("categories", ';').map(|paths| {
+        paths
+            .iter()
+            .map(|path| {
+                path.split('/')
+                    .map(|slug| slug.trim().to_string())
+                    .filter(|slug| !slug.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .filter(|path| !path.is_empty())
+            .collect()
+    });
+
+    let seo = SeoCells {
+        slug: cells
+            .get("seo_slug")
+            .map(|v| Some(v.to_string()).filter(|v| !v.is_empty())),
+        meta_title: cells
+            .get("seo_meta_title")
+            .map(|v| Some(v.to_string()).filter(|v| !v.is_empty())),
+        meta_description: cells
+            .get("seo_meta_description")
+            .map(|v| Some(v.to_string()).filter(|v| !v.is_empty())),
+        meta_keywords: cells.list("seo_meta_keywords", ','),
+        search_terms: cells.list("seo_search_terms", ','),
+        no_index: match cells.get("seo_no_index") {
+            Some(_) => Some(cells.flag("seo_no_index")?.unwrap_or(false)),
+            None => None,
+        },
+    };
+
+    Ok(ParsedRow {
+        row,
+        id,
+        request,
+        brand: cells.text("brand"),
+        categories,
+        seo,
+    })
+}
+
+/// A row that passed validation, ready to be written.
+struct PlannedRow {
+    row: usize,
+    id: String,
+    request: ProductRequest,
+    videos: Option<serde_json::Value>,
+    existing: Option<Product>,
+    brand: Option<String>,
+    categories: Option<Vec<Vec<String>>>,
+}
+
+/// Brand and category ids by name and slug, filled in as missing ones are
+/// created.
+struct Lookups {
+    brands: HashMap<String, i32>,
+    categories: HashMap<String, i32>,
+}
+
+/// Validates every row against the catalog without writing anything.
+async fn plan(pool: &PgPool, sheet: &Sheet) -> Result<(Vec<PlannedRow>, CatalogReport, Lookups)> {
+    let (header, data) = sheet
+        .rows
+        .split_first()
+        .ok_or_else(|| AppError::BadRequest("ფაილი ცარიელია".to_string()))?;
+    let columns = read_header(header)?;
+    let uses_seo = SEO_COLUMNS.iter().any(|c| columns.contains_key(c));
+
+    let lookups = Lookups {
+        brands: admin_queries::get_brands(pool)
+            .await?
+            .into_iter()
+            .map(|b| (b.name, b.id))
+            .collect(),
+        categories: category_queries::get_all(pool, false)
+            .await?
+            .into_iter()
+            .map(|c| (c.slug, c.id))
+            .collect(),
+    };
+
+    let mut report = CatalogReport::default();
+    let mut brands_created = BTreeSet::new();
+    let mut categories_created = BTreeSet::new();
+    let mut seen_ids = HashSet::new();
+    let mut seen_slugs: HashMap<String, String> = HashMap::new();
+    let mut planned = Vec::new();
+
+    for (i, values) in data.iter().enumerate() {
+        if values.iter().all(String::is_empty) {
+            continue;
+        }
+        report.rows += 1;
+        let row = sheet.first_row + i + 1;
+
+        let mut error = |product_id: Option<&str>, message: String| {
+            report.errors.push(CatalogRowError {
+                row,
+                product_id: product_id.map(str::to_string),
+                message,
+            })
+        };
+
+        let parsed = match parse_row(
+            row,
+            &Cells {
+                columns: &columns,
+                values,
+            },
+        ) {
+            Ok(parsed) => parsed,
+            Err(message) => {
+                let id = values.get(columns["id"]).filter(|id| !id.is_empty());
+                error(id.map(String::as_str), message);
+                continue;
+            }
+        };
+        if !seen_ids.insert(parsed.id.clone()) {
+            error(Some(&parsed.id), "პროდუქტი ფაილში რამდენჯერმეა".to_string());
+            continue;
+        }
+
+        let existing = products_queries::find_by_id(pool, &parsed.id).await?;
+        let mut request = parsed.request;
+        if uses_seo {
+            request.seo = Some(merge_seo(pool, existing.as_ref(), parsed.seo).await?);
+        }
+        let slug_taken = request
+            .seo
+            .as_ref()
+            .and_then(|seo| seo.slug.clone())
+            .and_then(|slug| {
+                let other = seen_slugs.insert(slug.clone(), parsed.id.clone())?;
+                Some((slug, other))
+            });
+        if let Some((slug, other)) = slug_taken {
+            error(
+                Some(&parsed.id),
+                format!("slug '{}' უკვე გამოყენებულია პროდუქტზე {}", slug, other),
+            );
+            continue;
+        }
+
+        let videos = match &existing {
+            Some(product) => product_service::prepare_update(pool, product, &mut request).await,
+            None => product_service::prepare_create(pool, &mut request)
+                .await
+                .map(Some),
+        };
+        let videos = match videos {
+            Ok(videos) => videos,
+            Err(e) => {
+                error(Some(&parsed.id), row_message(&e));
+                continue;
+            }
+        };
+
+        if let Some(brand) = parsed
+            .brand
+            .as_ref()
+            .filter(|b| !lookups.brands.contains_key(*b))
+        {
+            brands_created.insert(brand.clone());
+        }
+        for path in parsed.categories.iter().flatten() {
+            for (depth, slug) in path.iter().enumerate() {
+                if !lookups.categories.contains_key(slug) {
+                    categories_created.insert(path[..=depth].join("/"));
+                }
+            }
+        }
+
+        if existing.is_some() {
+            report.updated += 1;
+        } else {
+            report.created += 1;
+        }
+        planned.push(PlannedRow {
+            row: parsed.row,
+            id: parsed.id,
+            request,
+            videos,
+            existing,
+            brand: parsed.brand,
+            categories: parsed.categories,
+        });
+    }
+
+    report.brands_created = brands_created.into_iter().collect();
+    report.categories_created = categories_created.into_iter().collect();
+    Ok((planned, report, lookups))
+}
+
+/// SEO is saved as a whole, so columns left out of the file keep the stored
+/// values.
+async fn merge_seo(
+    pool: &PgPool,
+    existing: Option<&Product>,
+    cells: SeoCells,
+) -> Result<ProductSeoRequest> {
+    let current = match existing {
+        Some(product) => admin_queries::get_product_seo(pool, &product.id).await?,
+        None => None,
+    };
+
+    let mut seo = match current {
+        Some(current) => ProductSeoRequest {
+            meta_title: current.meta_title,
+            meta_description: current.meta_description,
+            meta_keywords: current.meta_keywords,
+            slug: current.slug,
+            search_terms: current.search_terms,
+            faqs: serde_json::from_value::<Vec<Faq>>(current.faqs).unwrap_or_default(),
+            og_image_uuid: current.og_image_uuid,
+            no_index: current.no_index,
+        },
+        None => ProductSeoRequest {
+            meta_title: None,
+            meta_description: None,
+            meta_keywords: Vec::new(),
+            slug: None,
+            search_terms: Vec::new(),
+            faqs: Vec::new(),
+            og_image_uuid: None,
+            no_index: false,
+        },
+    };
+
+    if let Some(slug) = cells.slug {
+        seo.slug = slug;
+    }
+    if let Some(meta_title) = cells.meta_title {
+        seo.meta_title = meta_title;
+    }
+    if let Some(meta_description) = cells.meta_description {
+        seo.meta_description = meta_description;
+    }
+    if let Some(meta_keywords) = cells.meta_keywords {
+        seo.meta_keywords = meta_keywords;
+    }
+    if let Some(search_terms) = cells.search_terms {
+        seo.search_terms = search_terms;
+    }
+    if let Some(no_index) = cells.no_index {
+        seo.no_index = no_index;
+    }
+    Ok(seo)
+}
+
+/// Returns true when the product was created.
+async fn apply_row(
+    pool: &PgPool,
+    job: &CatalogJob,
+    row: PlannedRow,
+    lookups: &mut Lookups,
+) -> Result<bool> {
+    let mut request = row.request;
+    if let Some(brand) = &row.brand {
+        request.brand_id = Some(resolve_brand(pool, brand, lookups).await?);
+    }
+
+    let product = match &row.existing {
+        Some(existing) => {
+            let product = admin_queries::update_product(
+                pool,
+                &row.id,
+                &request,
+                row.videos.as_ref(),
+                PriceChangeOrigin::import(job.created_by_user_id),
+            )
+            .await?;
+            if let Err(e) =
+                product_alert_service::queue_price_drop_alerts(pool, existing, &product).await
+            {
+                tracing::error!("failed to queue price-drop alerts for {}: {e}", product.id);
+            }
+            product
+        }
+        None => {
+            let videos = row.videos.unwrap_or_else(|| serde_json::json!([]));
+            admin_queries::create_product(
+                pool,
+                &request,
+                &videos,
+                job.created_by_user_id,
+                PriceChangeOrigin::import(job.created_by_user_id),
+            )
+            .await?
+        }
+    };
+
+    if let Some(ref seo) = request.seo {
+        admin_queries::upsert_product_seo(pool, &product.id, seo).await?;
+    }
+    if let Some(paths) = &row.categories {
+        let mut category_ids = Vec::with_capacity(paths.len());
+        for path in paths {
+            let id = resolve_category(pool, path, lookups).await?;
+            if !category_ids.contains(&id) {
+                category_ids.push(id);
+            }
+        }
+        category_queries::assign_categories_to_product(pool, &product.id, &category_ids).await?;
+    }
+
+    Ok(row.existing.is_none())
+}
+
+async fn resolve_brand(pool: &PgPool, name: &str, lookups: &mut Lookups) -> Result<i32> {
+    if let Some(&id) = lookups.brands.get(name) {
+        return Ok(id);
+    }
+    let brand = admin_queries::create_brand(pool, name).await?;
+    lookups.brands.insert(brand.name, brand.id);
+    Ok(brand.id)
+}
+
+/// Missing categories along the path are created under the previous one, named
+/// after their slug until someone renames them.
+async fn resolve_category(pool: &PgPool, path: &[String], lookups: &mut Lookups) -> Result<i32> {
+    let mut parent_id = None;
+    for slug in path {
+        let id = match lookups.categories.get(slug) {
+            Some(&id) => id,
+            None => {
+                let category = category_queries::create_category(
+                    pool,
+                    CreateCategoryRequest {
+                        parent_id,
+                        name: slug.clone(),
+                        slug: slug.clone(),
+                        description: None,
+                        display_order: None,
+                        enabled: Some(true),
+                    },
+                )
+                .await?;
+                lookups.categories.insert(category.slug, category.id);
+                category.id
+            }
+        };
+        parent_id = Some(id);
+    }
+
+    parent_id.ok_or_else(|| AppError::BadRequest("კატეგორია ცარიელია".to_string()))
+}
+
+/// The message an operator should see, without the error-kind prefix.
+fn row_message(err: &AppError) -> String {
+    match err {
+        AppError::BadRequest(msg) | AppError::Conflict(msg) | AppError::NotFound(msg) => {
+            msg.clone()
+        }
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::HashSet;
+
+use chrono::Utc;
+use rust_decimal::{Decimal, dec, prelude::ToPrimitive};
+use sqlx::PgPool;
+
+use crate::{
+    error::{AppError, Result},
+    models::{AppliedCoupon, Coupon, CouponType, OrderItemData},
+    queries::coupon_queries,
+};
+
+/// Discount in GEL for `eligible_subtotal` worth of covered items and the
+/// order's `delivery` price.
+pub fn calculate_discount(
+    coupon: &Coupon,
+    eligible_subtotal: Decimal,
+    delivery: Decimal,
+) -> Decimal {
+    match coupon.discount_type {
+        CouponType::Percentage => {
+            (eligible_subtotal * coupon.value.min(dec!(100)) / dec!(100)).round_dp(2)
+        }
+        CouponType::FixedAmount => coupon.value.min(eligible_subtotal),
+        CouponType::FreeDelivery => delivery,
+    }
+}
+
+/// Validates `code` for a checkout and works out its discount. Minimum
+/// subtotals are measured against the items the coupon covers.
+pub async fn apply(
+    pool: &PgPool,
+    code: &str,
+    user_id: Option<i32>,
+    email: &str,
+    items: &[OrderItemData],
+    delivery: Decimal,
+) -> Result<AppliedCoupon> {
+    let coupon = coupon_queries::find_by_code(pool, code.trim())
+        .await?
+        .filter(|c| c.enabled)
+        .ok_or_else(|| AppError::BadRequest("პრომო კოდი არ არსებობს".to_string()))?;
+
+    let now = Utc::now();
+    if coupon.starts_at.is_some_and(|starts| starts > now) {
+        return Err(AppError::BadRequest(
+            "პრომო კოდი ჯერ არ არის აქტიური".to_string(),
+        ));
+    }
+    if coupon.ends_at.is_some_and(|ends| ends <= now) {
+        return Err(AppError::BadRequest("პრომო კოდს ვადა გაუვიდა".to_string()));
+    }
+
+    coupon_queries::check_limits_now(pool, &coupon, user_id, email).await?;
+
+    let eligible: Option<HashSet<String>> = if coupon.category_ids.is_empty()
+        && coupon.brand_ids.is_empty()
+    {
+        None
+    } else {
+        let product_ids: Vec<String> = items.iter().filter_map(|i| i.product_id.clone()).collect();
+        Some(coupon_queries::find_eligible_product_ids(pool, &coupon, &product_ids).await?)
+    };
+
+    let eligible_subtotal: Decimal = items
+        .iter()
+        .filter(|item| match (&eligible, &item.product_id) {
+            (None, _) => true,
+            (Some(ids), Some(id)) => ids.contains(id),
+            (Some(_), None) => false,
+        })
+        .map(|item| item.price * Decimal::from(item.quantity))
+        .sum();
+
+    if eligible_subtotal <= Decimal::ZERO {
+        return Err(AppError::BadRequest(
+            "პრომო კოდი კალათის პროდუქტებზე არ ვრცელდება".to_string(),
+        ));
+    }
+    if let Some(min) = coupon.min_subtotal.filter(|min| eligible_subtotal < *min) {
+        return Err(AppError::BadRequest(format!(
+            "პრომო კოდის გამოსაყენებლად საჭიროა მინიმუმ {} ₾-ის პროდუქცია",
+            min
+        )));
+    }
+
+    let discount = calculate_discount(&coupon, eligible_subtotal, delivery);
+    if discount <= Decimal::ZERO {
+        return Err(AppError::BadRequest(
+            "პრომო კოდი ამ შეკვეთაზე ფასდაკლებას არ იძლევა".to_string(),
+        ));
+    }
+
+    let discount = (discount * Decimal::from(100))
+        .trunc()
+        .to_i32()
+        .ok_or_else(|| AppError::InternalError("თანხის გამოთვლა ვერ მოხერხდა".to_string()))?;
+
+    Ok(AppliedCoupon {
+        coupon_id: coupon.id,
+        code: coupon.code,
+        discount_type: coupon.discount_type,
+        discount,
+    })
+}
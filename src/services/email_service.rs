@@ -3,7 +3,10 @@ use rust_decimal::{Decimal, prelude::ToPrimitive};
 
 use crate::{
     error::{AppError, Result},
-    models::{Order, OrderItem},
+    models::{
+        CartSnapshotItem, LowStockVariant, Order, OrderItem, PendingProductAlert, ProductAlertKind,
+        ProductQuestion,
+    },
 };
 
 const SENDER_EMAIL: &str = "Tene <support@tene.ge>";
@@ -65,6 +68,173 @@ pub async fn send_operator_order_notification(
     Ok(())
 }
 
+pub async fn send_abandoned_cart_email(
+    ses_client: &SesClient,
+    recipient: &str,
+    items: &[CartSnapshotItem],
+    restore_url: &str,
+    unsubscribe_url: &str,
+) -> Result<()> {
+    let mut rows = String::new();
+    for item in items {
+        let mut meta_parts: Vec<String> = Vec::new();
+        meta_parts.push(format!("რაოდენობა: {}", item.quantity));
+        if let Some(color) = &item.color {
+            meta_parts.push(format!("ფერი: {}", html_escape(color)));
+        }
+        if let Some(cfg) = &item.cable_config {
+            meta_parts.push(format!("{}W · {}სმ", cfg.watts, cfg.length_cm));
+        }
+
+        rows.push_str(&format!(
+            "<tr>\
+                <td>\
+                    <div class=\"item-name\">{name}</div>\
+                    <div class=\"item-meta\">{meta}</div>\
+                </td>\
+             </tr>",
+            name = html_escape(item.name.as_deref().unwrap_or(&item.product_id)),
+            meta = meta_parts.join(" · "),
+        ));
+    }
+
+    let html = include_str!("../utils/abandoned_cart.html")
+        .replace("{{items_rows}}", &rows)
+        .replace("{{restore_url}}", &html_escape(restore_url))
+        .replace("{{unsubscribe_url}}", &html_escape(unsubscribe_url));
+
+    send_email(
+        ses_client,
+        SENDER_EMAIL,
+        recipient,
+        "თქვენი კალათა გელოდებათ",
+        &html,
+    )
+    .await
+}
+
+pub async fn send_product_alert_email(
+    ses_client: &SesClient,
+    alert: &PendingProductAlert,
+    product_url: &str,
+    unsubscribe_url: &str,
+) -> Result<()> {
+    let (title, intro) = match alert.kind {
+        ProductAlertKind::BackInStock => (
+            "პროდუქტი ისევ მარაგშია",
+            "პროდუქტი, რომელსაც ელოდებოდით, ისევ ხელმისაწვდომია.".to_string(),
+        ),
+        ProductAlertKind::PriceDrop => (
+            "ფასი შემცირდა",
+            match alert.price {
+                Some(price) => format!("ახალი ფასი: {} ₾", format_money(price)),
+                None => "პროდუქტის ფასი შემცირდა.".to_string(),
+            },
+        ),
+    };
+    let product_meta = alert
+        .color
+        .as_deref()
+        .map(|c| format!("ფერი: {}", html_escape(c)))
+        .unwrap_or_default();
+
+    let html = include_str!("../utils/product_alert.html")
+        .replace("{{title}}", title)
+        .replace("{{intro}}", &html_escape(&intro))
+        .replace("{{product_name}}", &html_escape(&alert.product_name))
+        .replace("{{product_meta}}", &product_meta)
+        .replace("{{product_url}}", &html_escape(product_url))
+        .replace("{{unsubscribe_url}}", &html_escape(unsubscribe_url));
+
+    send_email(ses_client, SENDER_EMAIL, &alert.email, title, &html).await
+}
+
+pub async fn send_question_answered_email(
+    ses_client: &SesClient,
+    question: &ProductQuestion,
+    product_name: &str,
+    product_url: &str,
+) -> Result<()> {
+    let html = include_str!("../utils/question_answered.html")
+        .replace("{{product_name}}", &html_escape(product_name))
+        .replace("{{question}}", &html_escape(&question.question))
+        .replace(
+            "{{answer}}",
+            &html_escape(question.answer.as_deref().unwrap_or_default()),
+        )
+        .replace("{{product_url}}", &html_escape(product_url));
+
+    send_email(
+        ses_client,
+        SENDER_EMAIL,
+        &question.email,
+        "თქვენს კითხვას უპასუხეს",
+        &html,
+    )
+    .await
+}
+
+/// Returns how many operators the digest reached.
+pub async fn send_low_stock_digest(
+    ses_client: &SesClient,
+    operator_emails: &[String],
+    variants: &[LowStockVariant],
+    days: i32,
+    frontend_url: &str,
+) -> Result<usize> {
+    let mut rows = String::new();
+    for variant in variants {
+        let mut meta_parts: Vec<String> = Vec::new();
+        meta_parts.push(format!("ზღვარი: {}", variant.threshold));
+        meta_parts.push(format!("გაყიდული {} დღეში: {}", days, variant.units_sold));
+        if let Some(cover) = variant.days_of_cover {
+            meta_parts.push(format!("საკმარისია ~{} დღე", cover.round()));
+        }
+        if variant.suggested_reorder > 0 {
+            meta_parts.push(format!("შესაკვეთი: {}", variant.suggested_reorder));
+        }
+
+        rows.push_str(&format!(
+            "<tr>\
+                <td>\
+                    <div class=\"item-name\"><a href=\"{url}\">{name}</a></div>\
+                    <div class=\"item-meta\">{id} · {meta}</div>\
+                </td>\
+                <td class=\"item-stock{out}\">{stock}</td>\
+             </tr>",
+            url = html_escape(&format!("{}/products/{}", frontend_url, variant.product_id)),
+            name = html_escape(&match &variant.color {
+                Some(color) => format!("{} ({})", variant.name, color),
+                None => variant.name.clone(),
+            }),
+            id = html_escape(&variant.sku),
+            meta = meta_parts.join(" · "),
+            out = if variant.stock <= 0 { " out" } else { "" },
+            stock = variant.stock,
+        ));
+    }
+
+    let title = "მარაგი იწურება";
+    let intro = format!(
+        "{} ვარიანტის მარაგი ზღვარს ქვემოთაა ან ამოიწურა.",
+        variants.len()
+    );
+    let html = include_str!("../utils/low_stock_digest.html")
+        .replace("{{title}}", title)
+        .replace("{{intro}}", &html_escape(&intro))
+        .replace("{{items_rows}}", &rows);
+
+    let mut delivered = 0;
+    for recipient in operator_emails {
+        match send_email(ses_client, SENDER_EMAIL, recipient, title, &html).await {
+            Ok(()) => delivered += 1,
+            Err(e) => tracing::error!("Failed to send low-stock digest to {}: {:?}", recipient, e),
+        }
+    }
+
+    Ok(delivered)
+}
+
 fn render_order_confirmation(order: &Order, items: &[OrderItem]) -> String {
     let mut rows = String::new();
     let mut subtotal = Decimal::ZERO;
@@ -109,13 +279,35 @@ fn render_order_confirmation(order: &Order, items: &[OrderItem]) -> String {
     }
 
     let total_gel = Decimal::from(order.amount) / Decimal::from(100);
-    let delivery_amount = total_gel - subtotal;
+    let coins_gel = Decimal::from(order.coins_redeemed) / Decimal::from(100);
+    let coupon_gel = Decimal::from(order.coupon_discount) / Decimal::from(100);
+    let delivery_amount = total_gel + coins_gel + coupon_gel - subtotal;
     let delivery_price = if delivery_amount <= Decimal::ZERO {
         "უფასო".to_string()
     } else {
         format!("{} ₾", format_money(delivery_amount))
     };
 
+    let mut discount_rows = String::new();
+    if let Some(code) = order
+        .coupon_code
+        .as_deref()
+        .filter(|_| order.coupon_discount > 0)
+    {
+        discount_rows.push_str(&format!(
+            "<tr><td>პრომო კოდი ({code})</td><td class=\"value\">-{amount} ₾</td></tr>",
+            code = html_escape(code),
+            amount = format_money(coupon_gel),
+        ));
+    }
+    if order.coins_redeemed > 0 {
+        discount_rows.push_str(&format!(
+            "<tr><td>ქოინები ({coins})</td><td class=\"value\">-{amount} ₾</td></tr>",
+            coins = order.coins_redeemed,
+            amount = format_money(coins_gel),
+        ));
+    }
+
     let customer = if order.customer_type == "company" {
         order.organization_name.clone().unwrap_or_default()
     } else {
@@ -205,6 +397,7 @@ fn render_order_confirmation(order: &Order, items: &[OrderItem]) -> String {
         .replace("{{items_rows}}", &rows)
         .replace("{{subtotal}}", &format_money(subtotal))
         .replace("{{delivery_price}}", &delivery_price)
+        .replace("{{discount_rows}}", &discount_rows)
         .replace("{{total}}", &format_money(total_gel))
         .replace("{{customer}}", &html_escape(&customer))
         .replace("{{email}}", &html_escape(&order.email))
@@ -2,9 +2,24 @@ use std::collections::BTreeMap;
 
 use sha1::{Digest, Sha1};
 
-use crate::error::{AppError, Result};
+use crate::{
+    config::FlittConfig,
+    error::{AppError, Result},
+};
 
-const FLITT_CHECKOUT_URL: &str = "https://pay.flitt.com/api/checkout/url";
+const CHECKOUT_PATH: &str = "/checkout/url";
+const REVERSE_PATH: &str = "/reverse/order_id";
+const STATUS_PATH: &str = "/status/order_id";
+
+/// What Flitt's status API reports for one of our orders.
+#[derive(Debug, Clone)]
+pub struct FlittOrderStatus {
+    pub order_status: String,
+    pub payment_id: Option<i32>,
+    pub amount: Option<i32>,
+    /// Total refunded so far, in tetri.
+    pub reversal_amount: Option<i32>,
+}
 
 pub fn generate_signature(secret_key: &str, params: &BTreeMap<String, String>) -> String {
     let mut parts: Vec<&str> = Vec::with_capacity(params.len() + 1);
@@ -57,8 +72,7 @@ pub fn verify_callback_signature(secret_key: &str, params: &serde_json::Value) -
 }
 
 pub async fn create_checkout_url(
-    merchant_id: i32,
-    secret_key: &str,
+    flitt: &FlittConfig,
     order_id: &str,
     amount: i32,
     order_desc: &str,
@@ -68,7 +82,7 @@ pub async fn create_checkout_url(
     let mut params = BTreeMap::new();
     params.insert("amount".to_string(), amount.to_string());
     params.insert("currency".to_string(), "GEL".to_string());
-    params.insert("merchant_id".to_string(), merchant_id.to_string());
+    params.insert("merchant_id".to_string(), flitt.merchant_id.to_string());
     params.insert("order_desc".to_string(), order_desc.to_string());
     params.insert("order_id".to_string(), order_id.to_string());
     params.insert("response_url".to_string(), response_url.to_string());
@@ -78,12 +92,12 @@ pub async fn create_checkout_url(
     );
     params.insert("version".to_string(), "1.0.1".to_string());
 
-    let signature = generate_signature(secret_key, &params);
+    let signature = generate_signature(&flitt.secret_key, &params);
 
     let request_body = serde_json::json!({
         "request": {
             "version": "1.0.1",
-            "merchant_id": merchant_id,
+            "merchant_id": flitt.merchant_id,
             "order_id": order_id,
             "order_desc": order_desc,
             "amount": amount,
@@ -94,22 +108,159 @@ pub async fn create_checkout_url(
         }
     });
 
+    let response_obj = send_request(flitt, CHECKOUT_PATH, request_body).await?;
+
+    let checkout_url = response_obj
+        .get("checkout_url")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| AppError::InternalError("Flitt პასუხში checkout_url აკლია".to_string()))?;
+
+    Ok(checkout_url.to_string())
+}
+
+/// Flitt's answer to a reverse request.
+#[derive(Debug, Clone)]
+pub enum ReverseOutcome {
+    /// Flitt accepted the request with this `reverse_status`.
+    Status(String),
+    /// Flitt answered with an error response, so nothing was reversed.
+    Rejected(String),
+}
+
+/// Refunds `amount` tetri of a paid order. An `Err` means the outcome is
+/// unknown: the request may have reached Flitt without us seeing the answer.
+pub async fn reverse(
+    flitt: &FlittConfig,
+    order_id: &str,
+    amount: i32,
+    comment: &str,
+) -> Result<ReverseOutcome> {
+    let mut params = BTreeMap::new();
+    params.insert("amount".to_string(), amount.to_string());
+    params.insert("comment".to_string(), comment.to_string());
+    params.insert("currency".to_string(), "GEL".to_string());
+    params.insert("merchant_id".to_string(), flitt.merchant_id.to_string());
+    params.insert("order_id".to_string(), order_id.to_string());
+    params.insert("version".to_string(), "1.0".to_string());
+
+    let signature = generate_signature(&flitt.secret_key, &params);
+
+    let request_body = serde_json::json!({
+        "request": {
+            "version": "1.0",
+            "merchant_id": flitt.merchant_id,
+            "order_id": order_id,
+            "amount": amount,
+            "currency": "GEL",
+            "comment": comment,
+            "signature": signature,
+        }
+    });
+
+    let response_obj = post(flitt, REVERSE_PATH, request_body).await?;
+
+    if let Err(error_message) = check_response_status(&response_obj) {
+        return Ok(ReverseOutcome::Rejected(error_message));
+    }
+
+    let reverse_status = response_obj
+        .get("reverse_status")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| AppError::InternalError("Flitt პასუხში reverse_status აკლია".to_string()))?;
+
+    Ok(ReverseOutcome::Status(reverse_status.to_string()))
+}
+
+pub async fn get_order_status(flitt: &FlittConfig, order_id: &str) -> Result<FlittOrderStatus> {
+    let mut params = BTreeMap::new();
+    params.insert("merchant_id".to_string(), flitt.merchant_id.to_string());
+    params.insert("order_id".to_string(), order_id.to_string());
+    params.insert("version".to_string(), "1.0".to_string());
+
+    let signature = generate_signature(&flitt.secret_key, &params);
+
+    let request_body = serde_json::json!({
+        "request": {
+            "version": "1.0",
+            "merchant_id": flitt.merchant_id,
+            "order_id": order_id,
+            "signature": signature,
+        }
+    });
+
+    let response_obj = send_request(flitt, STATUS_PATH, request_body).await?;
+
+    let order_status = response_obj
+        .get("order_status")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| AppError::InternalError("Flitt პასუხში order_status აკლია".to_string()))?;
+
+    Ok(FlittOrderStatus {
+        order_status: order_status.to_string(),
+        payment_id: int_field(&response_obj, "payment_id"),
+        amount: int_field(&response_obj, "amount"),
+        reversal_amount: int_field(&response_obj, "reversal_amount"),
+    })
+}
+
+// Flitt sends numbers either as JSON numbers or as strings depending on the endpoint
+fn int_field(obj: &serde_json::Value, key: &str) -> Option<i32> {
+    match obj.get(key)? {
+        serde_json::Value::Number(n) => n.as_i64().map(|v| v as i32),
+        serde_json::Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+async fn send_request(
+    flitt: &FlittConfig,
+    path: &str,
+    request_body: serde_json::Value,
+) -> Result<serde_json::Value> {
+    let response_obj = post(flitt, path, request_body).await?;
+
+    check_response_status(&response_obj).map_err(|error_message| {
+        AppError::InternalError(format!("Flitt მოთხოვნა ვერ შესრულდა: {}", error_message))
+    })?;
+
+    Ok(response_obj)
+}
+
+/// Sends a request and returns Flitt's `response` object, whatever its
+/// `response_status`. Errors are transport failures, 5xx replies and bodies we
+/// can't read, where Flitt may still have acted on the request.
+async fn post(
+    flitt: &FlittConfig,
+    path: &str,
+    request_body: serde_json::Value,
+) -> Result<serde_json::Value> {
+    let url = format!("{}{}", flitt.api_url.trim_end_matches('/'), path);
     let client = reqwest::Client::new();
     let response = client
-        .post(FLITT_CHECKOUT_URL)
+        .post(&url)
         .json(&request_body)
         .send()
         .await
         .map_err(|e| AppError::InternalError(format!("Flitt API მოთხოვნა ვერ მოხერხდა: {}", e)))?;
 
+    if response.status().is_server_error() {
+        return Err(AppError::InternalError(format!(
+            "Flitt API-მ დააბრუნა {}",
+            response.status()
+        )));
+    }
+
     let body: serde_json::Value = response.json().await.map_err(|e| {
         AppError::InternalError(format!("Flitt პასუხის გარჩევა ვერ მოხერხდა: {}", e))
     })?;
 
-    let response_obj = body
-        .get("response")
-        .ok_or_else(|| AppError::InternalError("არასწორი Flitt პასუხის ფორმატი".to_string()))?;
+    body.get("response")
+        .cloned()
+        .ok_or_else(|| AppError::InternalError("არასწორი Flitt პასუხის ფორმატი".to_string()))
+}
 
+/// Returns Flitt's `error_message` unless the response is a success.
+fn check_response_status(response_obj: &serde_json::Value) -> std::result::Result<(), String> {
     let response_status = response_obj
         .get("response_status")
         .and_then(|v| v.as_str())
@@ -121,16 +272,8 @@ pub async fn create_checkout_url(
             .get("error_message")
             .and_then(|v| v.as_str())
             .unwrap_or("Unknown Flitt error");
-        return Err(AppError::InternalError(format!(
-            "Flitt შეკვეთის შექმნა ვერ მოხერხდა: {}",
-            error_message
-        )));
+        return Err(error_message.to_string());
     }
 
-    let checkout_url = response_obj
-        .get("checkout_url")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| AppError::InternalError("Flitt პასუხში checkout_url აკლია".to_string()))?;
-
-    Ok(checkout_url.to_string())
+    Ok(())
 }
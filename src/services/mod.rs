@@ -1,4 +1,11 @@
+pub mod catalog_service;
+pub mod coupon_service;
 pub mod delivery_service;
 pub mod email_service;
 pub mod flitt_service;
 pub mod image_url_service;
+pub mod pricing_service;
+pub mod product_alert_service;
+pub mod product_service;
+pub mod recommendation_service;
+pub mod view_service;
@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde_json::json;
+use sqlx::PgPool;
+
+use crate::{
+    error::{AppError, Result},
+    models::{
+        BundleComponent, BundleItem, CableVariant, CartItem, OrderItemData, Product, ProductImage,
+        ProductVariant,
+    },
+    queries::{bundle_queries, products_queries, reservation_queries, variant_queries},
+};
+
+/// Current prices, stock and holds for a set of products. Checkout and the
+/// server-side cart both price their lines through this.
+pub struct Catalog {
+    products: HashMap<String, Product>,
+    images: HashMap<String, Vec<ProductImage>>,
+    variants: HashMap<String, Vec<ProductVariant>>,
+    bundles: HashMap<String, Vec<BundleComponent>>,
+    reserved: HashMap<i32, i32>,
+    cable_variants: HashMap<(i32, i32, i32), CableVariant>,
+}
+
+impl Catalog {
+    pub async fn load(pool: &PgPool, product_ids: &[String]) -> Result<Self> {
+        let products = products_queries::find_by_ids(pool, product_ids).await?;
+        let images = products_queries::find_images_by_product_ids(pool, product_ids).await?;
+        let variants = variant_queries::find_by_product_ids(pool, product_ids).await?;
+        let bundles = bundle_queries::find_by_bundle_ids(pool, product_ids).await?;
+
+        // bundles hold their components' stock, so holds on those count too
+        let mut stocked_ids = product_ids.to_vec();
+        stocked_ids.extend(bundles.values().flatten().map(|c| c.product_id.clone()));
+        let reserved = reservation_queries::find_reserved_quantities(pool, &stocked_ids).await?;
+
+        let cable_type_ids: Vec<i32> = products.values().filter_map(|p| p.cable_type_id).collect();
+        let cable_variants =
+            products_queries::find_cable_variants_by_type_ids(pool, &cable_type_ids).await?;
+
+        Ok(Self {
+            products,
+            images,
+            variants,
+            bundles,
+            reserved,
+            cable_variants,
+        })
+    }
+
+    /// Picks the variant a line refers to: by id when given, otherwise the
+    /// only variant with the line's color (or the only variant at all).
+    pub fn resolve_variant(&self, item: &CartItem) -> Result<&ProductVariant> {
+        let variants = self
+            .variants
+            .get(&item.product_id)
+            .map(|v| v.as_slice())
+            .unwrap_or_default();
+
+        let candidates: Vec<&ProductVariant> = variants
+            .iter()
+            .filter(|v| match (item.variant_id, item.color.as_deref()) {
+                (Some(id), _) => v.id == id,
+                (None, Some(color)) => v.color.as_deref() == Some(color),
+                (None, None) => true,
+            })
+            .collect();
+
+        match candidates.as_slice() {
+            [variant] => Ok(variant),
+            [] if item.variant_id.is_none() && item.color.is_some() => Err(AppError::BadRequest(
+                format!("ფერი მიუწვდომელია პროდუქტისთვის {}", item.product_id),
+            )),
+            [] => Err(AppError::BadRequest(format!(
+                "ვარიანტი მიუწვდომელია პროდუქტისთვის {}",
+                item.product_id
+            ))),
+            _ if item.color.is_none() => Err(AppError::BadRequest(format!(
+                "ფერი აუცილებელია პროდუქტისთვის {}",
+                item.product_id
+            ))),
+            _ => Err(AppError::BadRequest(format!(
+                "ვარიანტი აუცილებელია პროდუქტისთვის {}",
+                item.product_id
+            ))),
+        }
+    }
+
+    fn bundle(&self, product_id: &str) -> Option<&[BundleComponent]> {
+        self.bundles
+            .get(product_id)
+            .map(|c| c.as_slice())
+            .filter(|c| !c.is_empty())
+    }
+
+    /// Total quantity per variant across the cart, which is what stock is
+    /// checked against. Bundle lines count against their components; lines
+    /// that don't resolve are left out.
+    pub fn demand_by_variant(&self, items: &[CartItem]) -> HashMap<i32, i32> {
+        let mut demand: HashMap<i32, i32> = HashMap::new();
+        for item in items {
+            if let Some(components) = self.bundle(&item.product_id) {
+                for component in components {
+                    *demand.entry(component.variant_id).or_insert(0) +=
+                        component.quantity * item.quantity;
+                }
+            } else if let Ok(variant) = self.resolve_variant(item) {
+                *demand.entry(variant.id).or_insert(0) += item.quantity;
+            }
+        }
+        demand
+    }
+
+    fn has_stock(
+        &self,
+        variant_id: i32,
+        stock: i32,
+        wanted: i32,
+        demand: &HashMap<i32, i32>,
+    ) -> bool {
+        let held = self.reserved.get(&variant_id).copied().unwrap_or(0);
+        let wanted = demand.get(&variant_id).copied().unwrap_or(wanted);
+        stock - held >= wanted
+    }
+
+    /// Validates and prices one line against the cart-wide `demand`.
+    pub fn price_line(&self, item: &CartItem, demand: &HashMap<i32, i32>) -> Result<OrderItemData> {
+        let product = self.products.get(&item.product_id).ok_or_else(|| {
+            AppError::NotFound(format!("პროდუქტი {} ვერ მოიძებნა", item.product_id))
+        })?;
+
+        if !product.enabled {
+            return Err(AppError::BadRequest(format!(
+                "პროდუქტი {} მიუწვდომელია",
+                item.product_id
+            )));
+        }
+
+        let bundle = self.bundle(&item.product_id);
+        let variant = match bundle {
+            Some(_) => None,
+            None => Some(self.resolve_variant(item)?),
+        };
+
+        let in_stock = match (bundle, variant) {
+            (Some(components), _) => components
+                .iter()
+                .all(|c| self.has_stock(c.variant_id, c.stock, c.quantity * item.quantity, demand)),
+            (None, Some(variant)) => {
+                self.has_stock(variant.id, variant.stock, item.quantity, demand)
+            }
+            (None, None) => false,
+        };
+        if !in_stock {
+            return Err(AppError::BadRequest(format!(
+                "არასაკმარისი მარაგი პროდუქტისთვის {}",
+                item.product_id
+            )));
+        }
+
+        self.quote_line(item)
+    }
+
+    /// Resolves and prices one line without the availability and stock checks;
+    /// operators entering orders by hand go through this directly.
+    pub fn quote_line(&self, item: &CartItem) -> Result<OrderItemData> {
+        let product = self.products.get(&item.product_id).ok_or_else(|| {
+            AppError::NotFound(format!("პროდუქტი {} ვერ მოიძებნა", item.product_id))
+        })?;
+
+        let bundle = self.bundle(&item.product_id);
+        let variant = match bundle {
+            Some(_) => None,
+            None => Some(self.resolve_variant(item)?),
+        };
+
+        let images = self
+            .images
+            .get(&item.product_id)
+            .map(|v| v.as_slice())
+            .unwrap_or_default();
+        let variant_color = variant.and_then(|v| v.color.as_ref());
+        let image = images
+            .iter()
+            .find(|img| variant.is_some_and(|v| img.variant_id == Some(v.id)))
+            .or_else(|| {
+                images
+                    .iter()
+                    .find(|img| variant_color.is_some() && img.color.as_ref() == variant_color)
+            })
+            .or_else(|| images.iter().find(|img| img.is_primary))
+            .or(images.first());
+
+        let cable_variant = match &item.cable_config {
+            Some(cfg) => {
+                let cable_type_id = product.cable_type_id.ok_or_else(|| {
+                    AppError::BadRequest(format!("პროდუქტი {} არ არის კაბელი", item.product_id))
+                })?;
+                let v = self
+                    .cable_variants
+                    .get(&(cable_type_id, cfg.watts, cfg.length_cm))
+                    .ok_or_else(|| {
+                        AppError::BadRequest(format!(
+                            "არასწორი კაბელის კონფიგურაცია პროდუქტისთვის {}",
+                            item.product_id
+                        ))
+                    })?;
+                Some(v)
+            }
+            None => None,
+        };
+
+        let price_override = variant.and_then(|v| v.price_override);
+        let (price, campaign) = match (cable_variant, price_override) {
+            (Some(cable_variant), _) => with_campaign(product, cable_variant.price),
+            (None, Some(price_override)) => with_campaign(product, price_override),
+            (None, None) => (effective_price(product), campaign_discount(product)),
+        };
+
+        let cable_config_json = item
+            .cable_config
+            .as_ref()
+            .map(|c| json!({ "watts": c.watts, "length_cm": c.length_cm }));
+
+        Ok(OrderItemData {
+            product_id: Some(item.product_id.clone()),
+            variant_id: variant.map(|v| v.id),
+            color: item.color.clone().or_else(|| variant_color.cloned()),
+            quantity: item.quantity,
+            price,
+            product_name: product.name.clone(),
+            image: serde_json::to_value(image)?,
+            cable_config: cable_config_json,
+            campaign_id: campaign.map(|(id, _)| id),
+            campaign_discount: campaign.map(|(_, discount)| discount),
+            bundle_items: bundle
+                .map(|components| components.iter().map(BundleItem::from).collect()),
+        })
+    }
+}
+
+/// What a customer pays for the product right now, ignoring cable variants.
+pub fn effective_price(product: &Product) -> Decimal {
+    product.campaign_price.unwrap_or_else(|| own_price(product))
+}
+
+/// The campaign pricing the product, and how much it takes off each unit
+/// compared to the product's own price.
+pub fn campaign_discount(product: &Product) -> Option<(i32, Decimal)> {
+    let campaign_id = product.campaign_id?;
+    let price = product.campaign_price?;
+    Some((campaign_id, own_price(product) - price))
+}
+
+/// `base` with the product's campaign percentage taken off, for prices that
+/// don't come from the product itself. Rounds like the campaign view does.
+fn with_campaign(product: &Product, base: Decimal) -> (Decimal, Option<(i32, Decimal)>) {
+    match (product.campaign_id, product.campaign_discount_percent) {
+        (Some(campaign_id), Some(percent)) => {
+            let price = (base * (Decimal::ONE_HUNDRED - percent) / Decimal::ONE_HUNDRED)
+                .round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero);
+            (price, Some((campaign_id, base - price)))
+        }
+        _ => (base, None),
+    }
+}
+
+fn own_price(product: &Product) -> Decimal {
+    match product.discounted_price {
+        Some(dp) if dp < product.price => dp,
+        _ => product.price,
+    }
+}
@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use sqlx::PgPool;
+
+use crate::{
+    error::Result,
+    models::{Product, ProductVariant},
+    queries::{product_alert_queries, variant_queries},
+    services::pricing_service,
+};
+
+fn stock_by_color(variants: &[ProductVariant]) -> HashMap<&str, i32> {
+    let mut stock: HashMap<&str, i32> = HashMap::new();
+    for variant in variants {
+        if let Some(color) = variant.color.as_deref() {
+            *stock.entry(color).or_insert(0) += variant.stock;
+        }
+    }
+    stock
+}
+
+/// Compares stock before an admin change with the stock now and queues
+/// back-in-stock emails for every color that went from zero to positive.
+pub async fn queue_restock_alerts(
+    pool: &PgPool,
+    product_id: &str,
+    before: &[ProductVariant],
+) -> Result<()> {
+    let after = variant_queries::find_by_product_id(pool, product_id).await?;
+
+    let old_stock = stock_by_color(before);
+    let restocked_colors: Vec<String> = stock_by_color(&after)
+        .into_iter()
+        .filter(|(color, qty)| *qty > 0 && old_stock.get(color).copied().unwrap_or(0) <= 0)
+        .map(|(color, _)| color.to_string())
+        .collect();
+
+    let total = |variants: &[ProductVariant]| variants.iter().map(|v| v.stock).sum::<i32>();
+    let product_restocked = total(before) <= 0 && total(&after) > 0;
+
+    if restocked_colors.is_empty() && !product_restocked {
+        return Ok(());
+    }
+
+    let queued = product_alert_queries::enqueue_back_in_stock(
+        pool,
+        product_id,
+        &restocked_colors,
+        product_restocked,
+    )
+    .await?;
+    if queued > 0 {
+        tracing::info!("queued {} back-in-stock alerts for {}", queued, product_id);
+    }
+
+    Ok(())
+}
+
+/// Queues price-drop emails when an update lowered the price customers pay.
+pub async fn queue_price_drop_alerts(
+    pool: &PgPool,
+    before: &Product,
+    after: &Product,
+) -> Result<()> {
+    let new_price = pricing_service::effective_price(after);
+    if new_price >= pricing_service::effective_price(before) {
+        return Ok(());
+    }
+
+    let queued = product_alert_queries::enqueue_price_drop(pool, &after.id, new_price).await?;
+    if queued > 0 {
+        tracing::info!("queued {} price-drop alerts for {}", queued, after.id);
+    }
+
+    Ok(())
+}
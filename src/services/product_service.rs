@@ -0,0 +1,227 @@
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+
+use crate::{
+    error::{AppError, Result},
+    models::{Product, ProductRequest, ProductVideo, VideoPlatform},
+    queries::{admin_queries, products_queries},
+};
+
+/// Validates a new product and normalises its discount. Returns the videos to
+/// store with it.
+pub async fn prepare_create(
+    pool: &PgPool,
+    payload: &mut ProductRequest,
+) -> Result<serde_json::Value> {
+    let id = payload
+        .id
+        .clone()
+        .ok_or_else(|| AppError::BadRequest("id აუცილებელია".to_string()))?;
+
+    if payload.name.is_none() {
+        return Err(AppError::BadRequest("სახელი აუცილებელია".to_string()));
+    }
+
+    if payload.price.is_none() {
+        return Err(AppError::BadRequest("ფასი აუცილებელია".to_string()));
+    }
+
+    check_low_stock_threshold(payload)?;
+
+    if products_queries::find_by_id(pool, &id).await?.is_some() {
+        return Err(AppError::Conflict(format!(
+            "პროდუქტი id-ით {} უკვე არსებობს",
+            id
+        )));
+    }
+
+    if let Some((discount, discounted_price)) =
+        resolve_discount(payload.price, payload.discount, payload.discounted_price)?
+    {
+        payload.discount = Some(discount);
+        payload.discounted_price = discounted_price;
+    } else {
+        payload.discount = None;
+        payload.discounted_price = None;
+    }
+
+    check_seo_slug(pool, payload, &id).await?;
+
+    match payload.videos {
+        Some(ref urls) => validate_videos(urls),
+        None => Ok(serde_json::json!([])),
+    }
+}
+
+/// Validates changes to `existing` and normalises the discount against its
+/// current price. Returns the videos to store, if they change.
+pub async fn prepare_update(
+    pool: &PgPool,
+    existing: &Product,
+    payload: &mut ProductRequest,
+) -> Result<Option<serde_json::Value>> {
+    check_low_stock_threshold(payload)?;
+
+    let effective_price = payload.price.unwrap_or(existing.price);
+    match resolve_discount(
+        Some(effective_price),
+        payload.discount,
+        payload.discounted_price,
+    )? {
+        Some((discount, discounted_price)) => {
+            payload.discount = Some(discount);
+            payload.discounted_price = discounted_price;
+        }
+        None => {
+            payload.discount = match existing.discounted_price {
+                Some(dp) if dp < effective_price => {
+                    payload.discounted_price = Some(dp);
+                    Some(discount_percent(effective_price, dp))
+                }
+                Some(_) => {
+                    payload.discounted_price = None;
+                    Some(Decimal::ZERO)
+                }
+                None => None,
+            };
+        }
+    }
+
+    check_seo_slug(pool, payload, &existing.id).await?;
+
+    match payload.videos {
+        Some(ref urls) => Ok(Some(validate_videos(urls)?)),
+        None => Ok(None),
+    }
+}
+
+fn check_low_stock_threshold(payload: &ProductRequest) -> Result<()> {
+    if payload.low_stock_threshold.flatten().is_some_and(|t| t < 0) {
+        return Err(AppError::BadRequest(
+            "მარაგის ზღვარი არ შეიძლება იყოს უარყოფითი".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+async fn check_seo_slug(pool: &PgPool, payload: &ProductRequest, id: &str) -> Result<()> {
+    let Some(slug) = payload.seo.as_ref().and_then(|seo| seo.slug.as_ref()) else {
+        return Ok(());
+    };
+    match admin_queries::find_product_seo_by_slug(pool, slug).await? {
+        Some(other_id) if other_id != id => Err(AppError::Conflict(format!(
+            "slug '{}' უკვე გამოყენებულია",
+            slug
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn resolve_discount(
+    price: Option<Decimal>,
+    discount: Option<Decimal>,
+    discounted_price: Option<Decimal>,
+) -> Result<Option<(Decimal, Option<Decimal>)>> {
+    if discount.is_some() && discounted_price.is_some() {
+        return Err(AppError::BadRequest(
+            "discount და discounted_price ერთდროულად ვერ მიეთითება".to_string(),
+        ));
+    }
+
+    if let Some(d) = discount {
+        if d < Decimal::ZERO || d > Decimal::from(100) {
+            return Err(AppError::BadRequest(
+                "discount უნდა იყოს 0-დან 100-მდე".to_string(),
+            ));
+        }
+        if d == Decimal::ZERO {
+            return Ok(Some((Decimal::ZERO, None)));
+        }
+        let p = price
+            .ok_or_else(|| AppError::BadRequest("discount-ისთვის price აუცილებელია".to_string()))?;
+        if p <= Decimal::ZERO {
+            return Err(AppError::BadRequest("price უნდა იყოს დადებითი".to_string()));
+        }
+        let dp = (p * (Decimal::ONE - d / Decimal::from(100))).round_dp(2);
+        return Ok(Some((discount_percent(p, dp), Some(dp))));
+    }
+
+    if let Some(dp) = discounted_price {
+        let p = price.ok_or_else(|| {
+            AppError::BadRequest("discounted_price-ისთვის price აუცილებელია".to_string())
+        })?;
+        if p <= Decimal::ZERO {
+            return Err(AppError::BadRequest("price უნდა იყოს დადებითი".to_string()));
+        }
+        if dp < Decimal::ZERO || dp > p {
+            return Err(AppError::BadRequest(
+                "discounted_price უნდა იყოს 0-დან price-მდე".to_string(),
+            ));
+        }
+        let dp = dp.round_dp(2);
+        if dp == p {
+            return Ok(Some((Decimal::ZERO, None)));
+        }
+        return Ok(Some((discount_percent(p, dp), Some(dp))));
+    }
+
+    Ok(None)
+}
+
+fn discount_percent(price: Decimal, discounted_price: Decimal) -> Decimal {
+    ((price - discounted_price) * Decimal::from(100) / price).round_dp(2)
+}
+
+fn detect_video_platform(url: &str) -> Option<VideoPlatform> {
+    let host = url
+        .split_once("://")
+        .map(|(_, rest)| rest)
+        .unwrap_or(url)
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or("")
+        .trim_start_matches("www.")
+        .to_ascii_lowercase();
+
+    match host.as_str() {
+        "youtube.com" | "m.youtube.com" | "youtu.be" => Some(VideoPlatform::Youtube),
+        "facebook.com" | "m.facebook.com" | "fb.watch" | "fb.com" => Some(VideoPlatform::Facebook),
+        _ => None,
+    }
+}
+
+fn validate_videos(urls: &[String]) -> Result<serde_json::Value> {
+    let mut videos = Vec::with_capacity(urls.len());
+    for url in urls {
+        let url = url.trim();
+        if !(url.starts_with("http://") || url.starts_with("https://")) {
+            return Err(AppError::BadRequest(format!("არასწორი ვიდეო URL: {}", url)));
+        }
+        let platform = detect_video_platform(url).ok_or_else(|| {
+            AppError::BadRequest(format!(
+                "მხოლოდ YouTube ან Facebook ვიდეო URL-ებია დაშვებული: {}",
+                url
+            ))
+        })?;
+        videos.push(ProductVideo {
+            platform,
+            url: url.to_string(),
+        });
+    }
+    serde_json::to_value(videos)
+        .map_err(|e| AppError::InternalError(format!("ვიდეოების სერიალიზაცია ვერ მოხერხდა: {}", e)))
+}
+
+/// The product update a scheduled price change stands for.
+pub fn price_change_request(
+    price: Option<Decimal>,
+    discount: Option<Decimal>,
+    discounted_price: Option<Decimal>,
+) -> ProductRequest {
+    ProductRequest {
+        price,
+        discount,
+        discounted_price,
+        ..Default::default()
+    }
+}
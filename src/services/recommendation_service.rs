@@ -0,0 +1,88 @@
+use std::collections::HashSet;
+
+use sqlx::PgPool;
+
+use crate::{
+    error::Result,
+    models::{ProductResponse, RelatedStrategy},
+    queries::{admin_queries, products_queries, recommendation_queries},
+};
+
+/// How far back a user's own views count as interest for `/recommendations`.
+const USER_SEED_WINDOW_DAYS: i32 = 90;
+
+pub async fn related(
+    pool: &PgPool,
+    product_id: &str,
+    strategy: RelatedStrategy,
+    limit: i64,
+) -> Result<Vec<ProductResponse>> {
+    match strategy {
+        RelatedStrategy::Similar => {
+            products_queries::get_related_products(pool, product_id, limit).await
+        }
+        RelatedStrategy::FrequentlyBoughtTogether => {
+            products_queries::get_bought_together(pool, product_id, limit).await
+        }
+        RelatedStrategy::AlsoViewed => {
+            let ids =
+                recommendation_queries::get_related_ids(pool, product_id, true, limit).await?;
+            products_queries::build_products_response_ordered(pool, &ids).await
+        }
+        RelatedStrategy::Recommended => {
+            let ids =
+                recommendation_queries::get_related_ids(pool, product_id, false, limit).await?;
+            let mut products =
+                products_queries::build_products_response_ordered(pool, &ids).await?;
+
+            // Products without enough traffic have few or no scores yet.
+            if (products.len() as i64) < limit {
+                let similar = products_queries::get_related_products(
+                    pool,
+                    product_id,
+                    limit + products.len() as i64,
+                )
+                .await?;
+                top_up(&mut products, similar, &[], limit);
+            }
+
+            Ok(products)
+        }
+    }
+}
+
+/// Products scored against what the user recently viewed or bought, falling
+/// back to the curated top products.
+pub async fn for_user(pool: &PgPool, user_id: i32, limit: i64) -> Result<Vec<ProductResponse>> {
+    let seeds =
+        recommendation_queries::get_user_seed_ids(pool, user_id, USER_SEED_WINDOW_DAYS).await?;
+    let ids = recommendation_queries::get_ids_for_seeds(pool, &seeds, limit).await?;
+    let mut products = products_queries::build_products_response_ordered(pool, &ids).await?;
+
+    if (products.len() as i64) < limit {
+        let top_ids = admin_queries::get_top_product_ids(pool, None).await?;
+        let top = products_queries::build_products_response_ordered(pool, &top_ids).await?;
+        top_up(&mut products, top, &seeds, limit);
+    }
+
+    Ok(products)
+}
+
+fn top_up(
+    products: &mut Vec<ProductResponse>,
+    extra: Vec<ProductResponse>,
+    exclude: &[String],
+    limit: i64,
+) {
+    let mut seen: HashSet<String> = products.iter().map(|p| p.data.id.clone()).collect();
+    seen.extend(exclude.iter().cloned());
+
+    for product in extra {
+        if products.len() as i64 >= limit {
+            break;
+        }
+        if product.data.enabled && seen.insert(product.data.id.clone()) {
+            products.push(product);
+        }
+    }
+}
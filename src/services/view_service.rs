@@ -0,0 +1,80 @@
+use std::net::IpAddr;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    queries::products_queries,
+};
+
+/// A refresh or a back-and-forth within this window counts as one view.
+const DEDUP_WINDOW_MINUTES: i32 = 30;
+const RATE_WINDOW_SECS: i32 = 60;
+/// Written views per client address; generous enough for shared office or
+/// mobile NATs. Repeats are already dropped by the dedup, so this caps how
+/// many distinct products one address can add views to.
+const MAX_VIEWS_PER_WINDOW: i64 = 60;
+
+/// Lowercase User-Agent fragments of crawlers, link previews and scripts.
+const BOT_MARKERS: &[&str] = &[
+    "bot",
+    "crawl",
+    "spider",
+    "slurp",
+    "scrapy",
+    "headless",
+    "lighthouse",
+    "facebookexternalhit",
+    "preview",
+    "curl",
+    "wget",
+    "python-requests",
+    "go-http-client",
+    "java/",
+];
+
+pub fn is_bot(user_agent: Option<&str>) -> bool {
+    let Some(ua) = user_agent.map(str::trim).filter(|ua| !ua.is_empty()) else {
+        return true;
+    };
+    let ua = ua.to_lowercase();
+    BOT_MARKERS.iter().any(|marker| ua.contains(marker))
+}
+
+/// Records a product view. Views from bots and repeats inside the dedup window
+/// are dropped and reported as `false`; clients over the rate limit get an
+/// error.
+pub async fn record_view(
+    pool: &PgPool,
+    product_id: &str,
+    user_id: Option<i32>,
+    session_id: Option<Uuid>,
+    ip: Option<IpAddr>,
+    user_agent: Option<&str>,
+) -> Result<bool> {
+    if is_bot(user_agent) {
+        return Ok(false);
+    }
+
+    let ip = ip.map(|ip| ip.to_string());
+    if let Some(ip) = &ip {
+        let recent =
+            products_queries::count_recent_views_from_ip(pool, ip, RATE_WINDOW_SECS).await?;
+        if recent >= MAX_VIEWS_PER_WINDOW {
+            return Err(AppError::TooManyRequests(
+                "ძალიან ბევრი მოთხოვნა, სცადეთ მოგვიანებით".to_string(),
+            ));
+        }
+    }
+
+    products_queries::add_product_views(
+        pool,
+        product_id,
+        user_id,
+        session_id,
+        ip.as_deref(),
+        DEDUP_WINDOW_MINUTES,
+    )
+    .await
+}
@@ -1,4 +1,14 @@
-use axum::{extract::FromRequestParts, http::request::Parts};
+use std::net::{IpAddr, SocketAddr};
+
+use axum::{
+    Json,
+    body::{Body, Bytes},
+    extract::{ConnectInfo, FromRequest, FromRequestParts, Request},
+    http::request::Parts,
+    response::{IntoResponse, Response},
+};
+use serde::de::DeserializeOwned;
+use sha1::{Digest, Sha1};
 
 use crate::{
     AppState,
@@ -56,3 +66,95 @@ impl FromRequestParts<AppState> for LenientClaims {
         Ok(LenientClaims(claims))
     }
 }
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+pub struct IdempotencyKey(pub Option<String>);
+
+impl FromRequestParts<AppState> for IdempotencyKey {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &AppState) -> Result<Self> {
+        let Some(header) = parts.headers.get(IDEMPOTENCY_KEY_HEADER) else {
+            return Ok(IdempotencyKey(None));
+        };
+
+        let key = header
+            .to_str()
+            .ok()
+            .map(str::trim)
+            .filter(|k| !k.is_empty() && k.len() <= MAX_IDEMPOTENCY_KEY_LEN)
+            .ok_or_else(|| AppError::BadRequest("არასწორი Idempotency-Key".to_string()))?;
+
+        Ok(IdempotencyKey(Some(key.to_string())))
+    }
+}
+
+/// A JSON body together with a hash of its raw bytes, which idempotent
+/// endpoints use to tell a retry from a different request reusing its key.
+pub struct FingerprintedJson<T>(pub T, pub String);
+
+impl<T, S> FromRequest<S> for FingerprintedJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> std::result::Result<Self, Response> {
+        let (parts, body) = req.into_parts();
+
+        // the extensions carry the configured body limit
+        let mut body_req = Request::new(body);
+        *body_req.extensions_mut() = parts.extensions.clone();
+        let bytes = Bytes::from_request(body_req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        let fingerprint = format!("{:x}", Sha1::digest(&bytes));
+
+        let Json(value) =
+            Json::<T>::from_request(Request::from_parts(parts, Body::from(bytes)), state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+
+        Ok(FingerprintedJson(value, fingerprint))
+    }
+}
+
+/// Address of the client. Behind `trusted_proxies` proxies it is the hop the
+/// outermost one recorded in `X-Forwarded-For`; anything to the left of that
+/// came from the client and can't be trusted. Otherwise it is the socket peer.
+pub struct ClientIp(pub Option<IpAddr>);
+
+impl FromRequestParts<AppState> for ClientIp {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self> {
+        let hops: Vec<&str> = parts
+            .headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|h| h.to_str().ok())
+            .flat_map(|h| h.split(','))
+            .map(str::trim)
+            .collect();
+
+        let forwarded = match state.trusted_proxies {
+            0 => None,
+            n => hops
+                .len()
+                .checked_sub(n)
+                .and_then(|i| hops[i].parse::<IpAddr>().ok()),
+        };
+
+        let ip = forwarded.or_else(|| {
+            parts
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip())
+        });
+
+        Ok(ClientIp(ip))
+    }
+}
@@ -0,0 +1,99 @@
+use std::{
+    future::Future,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::{
+    error::{AppError, Result},
+    models::{IdempotencyClaim, IdempotencyScope},
+    queries::idempotency_queries,
+};
+
+/// Set by a handler once it has done something a retry must not repeat, such
+/// as creating the order. A failure after that is stored for the key like a
+/// success instead of freeing the key.
+#[derive(Clone, Default)]
+pub struct Committed(Arc<AtomicBool>);
+
+impl Committed {
+    pub fn set(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    fn is_set(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Runs `handler` once per `Idempotency-Key`. Retries with the same key and
+/// request `fingerprint` get the response of the first run instead of running
+/// it again; a run that failed before `Committed::set` frees the key for a
+/// retry. Requests without a key always run.
+pub async fn run_once<T, F, H>(
+    pool: &PgPool,
+    scope: IdempotencyScope,
+    key: Option<String>,
+    user_id: Option<i32>,
+    fingerprint: &str,
+    handler: H,
+) -> Result<Response>
+where
+    T: Serialize,
+    F: Future<Output = Result<T>>,
+    H: FnOnce(Committed) -> F,
+{
+    let committed = Committed::default();
+
+    let Some(key) = key else {
+        return Ok(Json(handler(committed).await?).into_response());
+    };
+
+    match idempotency_queries::claim(pool, scope, &key, user_id, fingerprint).await? {
+        IdempotencyClaim::Acquired => {}
+        IdempotencyClaim::Completed(status, response) => {
+            let status = StatusCode::from_u16(status).unwrap_or(StatusCode::OK);
+            return Ok((status, Json(response)).into_response());
+        }
+        IdempotencyClaim::InProgress => {
+            return Err(AppError::Conflict(
+                "მოთხოვნა ამ Idempotency-Key-ით უკვე მუშავდება".to_string(),
+            ));
+        }
+    }
+
+    match handler(committed.clone()).await {
+        Ok(response) => {
+            let response = serde_json::to_value(&response)?;
+            idempotency_queries::complete(pool, scope, &key, StatusCode::OK.as_u16(), &response)
+                .await?;
+            Ok(Json(response).into_response())
+        }
+        Err(e) if committed.is_set() => {
+            let response = e.into_response();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .map_err(|e| AppError::InternalError(e.to_string()))?;
+            let body: serde_json::Value = serde_json::from_slice(&body)?;
+            idempotency_queries::complete(pool, scope, &key, status.as_u16(), &body).await?;
+            Ok((status, Json(body)).into_response())
+        }
+        Err(e) => {
+            if let Err(release_err) = idempotency_queries::release(pool, scope, &key).await {
+                tracing::error!("failed to release idempotency key {key}: {release_err}");
+            }
+            Err(e)
+        }
+    }
+}
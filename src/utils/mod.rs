@@ -1,2 +1,3 @@
 pub mod extractors;
+pub mod idempotency;
 pub mod jwt;